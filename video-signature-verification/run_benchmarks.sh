@@ -16,4 +16,7 @@ echo -e "\033[0;32m===== Running Horner's Circuit Benchmarks =====\033[0m"
 cargo run --release --bin horners_signature_verification
 
 echo -e "\033[0;32m===== Running Barycentric Circuit Benchmarks =====\033[0m"
-cargo run --release --bin barycentric_signature_verification
\ No newline at end of file
+cargo run --release --bin barycentric_signature_verification
+
+echo -e "\033[0;32m===== Running Batched Barycentric vs Horner Benchmarks =====\033[0m"
+cargo run --release --bin batched_barycentric_signature_verification
@@ -0,0 +1,24 @@
+// Compares the batched barycentric circuit with Horner's, for messages of 2^16 to 2^22 elements.
+
+use ark_std::UniformRand;
+use video_signature_verification::driver;
+use video_signature_verification::gadgets::{BatchedBarycentric, Horner};
+use video_signature_verification::F;
+
+const LOG_MESSAGE_LENGTHS: std::ops::RangeInclusive<usize> = 16..=22;
+
+#[global_allocator]
+static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
+fn main() {
+    let mut rng = ark_std::test_rng();
+    for log_len in LOG_MESSAGE_LENGTHS {
+        let message: Vec<F> = (0..1 << log_len).map(|_| F::rand(&mut rng)).collect();
+
+        println!("== MESSAGE_LENGTH 2^{}: Horner ==", log_len);
+        driver::run::<Horner>(message.clone()).unwrap();
+
+        println!("== MESSAGE_LENGTH 2^{}: batched barycentric ==", log_len);
+        driver::run::<BatchedBarycentric>(message).unwrap();
+    }
+}
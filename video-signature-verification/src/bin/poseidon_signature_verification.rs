@@ -11,7 +11,6 @@ use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
 use ark_crypto_primitives::sponge::{CryptographicSponge, FieldBasedCryptographicSponge};
 use ark_ff::vec::Vec;
 use ark_ff::PrimeField;
-use ark_ff::UniformRand;
 use ark_groth16::Groth16;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
@@ -21,8 +20,7 @@ use ark_relations::*;
 use ark_std::rand::{RngCore, SeedableRng};
 use ark_std::test_rng;
 use ark_std::time::{SystemTime, UNIX_EPOCH};
-
-const LENGTH: usize = 1 << 16;
+use video_signature_verification::packing;
 
 /// Generate default parameters (bls381-fr-only) for alpha = 17, state-size = 8
 pub fn poseidon_parameters_for_test<F: PrimeField>() -> PoseidonConfig<F> {
@@ -727,22 +725,23 @@ pub fn poseidon_parameters_for_test<F: PrimeField>() -> PoseidonConfig<F> {
     }
 }
 
+// Hashes the elements `pixels` pack into.
 pub struct AbsorbCircuit<F: PrimeField> {
-    pub a: Vec<F>,
+    pub pixels: Vec<u8>,
     pub hash: F,
 }
 
 #[allow(dead_code)]
 impl<F: PrimeField> AbsorbCircuit<F> {
-    pub fn new(a: Vec<F>, hash: F) -> Self {
-        Self { a, hash }
+    pub fn new(pixels: Vec<u8>, hash: F) -> Self {
+        Self { pixels, hash }
     }
 }
 
 impl<F: PrimeField> Clone for AbsorbCircuit<F> {
     fn clone(&self) -> Self {
         AbsorbCircuit {
-            a: self.a.clone(),
+            pixels: self.pixels.clone(),
             hash: self.hash,
         }
     }
@@ -750,11 +749,8 @@ impl<F: PrimeField> Clone for AbsorbCircuit<F> {
 
 impl<F: PrimeField> ConstraintSynthesizer<F> for AbsorbCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        let mut a = Vec::new();
-
-        for elem in self.a.iter() {
-            a.push(FpVar::new_witness(cs.clone(), || Ok(elem))?);
-        }
+        let bytes = UInt8::new_witness_vec(cs.clone(), &self.pixels[..])?;
+        let a = packing::pack_var(&bytes)?;
 
         let res_target = FpVar::<F>::new_input(cs.clone(), || Ok(&self.hash))?;
         let params = poseidon_parameters_for_test();
@@ -772,14 +768,15 @@ fn main() {
     let rng = &mut ark_std::test_rng();
     let sponge_params = poseidon_parameters_for_test();
 
-    let absorb: Vec<_> = (0..LENGTH).map(|_| Fr::rand(rng)).collect();
+    let pixels = packing::random_720p_frame(rng);
+    let absorb: Vec<Fr> = packing::pack(&pixels);
 
     let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
     native_sponge.absorb(&absorb);
     let hash = native_sponge.squeeze_native_field_elements(1);
     // println!("Native Hash {:?}", hash);
 
-    let c = AbsorbCircuit::<Fr>::new(absorb.clone(), hash[0]);
+    let c = AbsorbCircuit::<Fr>::new(pixels, hash[0]);
     {
         let mut circuit = c.clone();
         let mut cs = ConstraintSystem::new_ref();
@@ -787,7 +784,7 @@ fn main() {
         println!("Num constraints: {:?}", cs.num_constraints());
         println!(
             "Constraints per field element: {:?}",
-            cs.num_constraints() / LENGTH
+            cs.num_constraints() / absorb.len()
         );
     }
 
@@ -0,0 +1,430 @@
+// Setup, commit, prove and verify for any `EvaluationGadget`.
+//
+// Adapted from: https://github.com/Pratyush/hekaton-system/blob/main/cp-groth16/benches/bench.rs
+//
+// `EvalCircuit` is the two-stage circuit every gadget shares, and `run` takes it through
+// CP-Groth16 next to the gadget's polynomial commitment, printing how long every step took.
+// `verify` never takes the evaluation point from the prover: it derives it from the stage-0
+// commitment in the proof and the PCS commitment, see `transcript`.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use ark_cp_groth16::{
+    committer::CommitmentBuilder,
+    generator::generate_parameters,
+    verifier::{prepare_verifying_key, verify_proof},
+    MultiStageConstraintSynthesizer, MultiStageConstraintSystem, Proof, VerifyingKey,
+};
+use ark_groth16::r1cs_to_qap::LibsnarkReduction as QAP;
+use ark_r1cs_std::{
+    eq::EqGadget,
+    fields::fp::FpVar,
+    prelude::{AllocVar, FieldVar, UInt8},
+};
+use ark_relations::{
+    ns,
+    r1cs::{ConstraintSystemRef, SynthesisError},
+};
+use ark_std::rand::RngCore;
+use ark_std::time::Instant;
+use ark_std::{UniformRand, Zero};
+use rand::Rng;
+
+use crate::gadgets::EvaluationGadget;
+use crate::packing;
+use crate::pcs::MessagePcs;
+use crate::transcript;
+use crate::{E, F};
+
+#[derive(Debug)]
+pub enum DriverError {
+    Synthesis(SynthesisError),
+    /// The CP-Groth16 proof did not verify.
+    Groth16Rejected,
+    /// The polynomial commitment opening did not verify.
+    PcsRejected,
+}
+
+impl fmt::Display for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DriverError::Synthesis(e) => write!(f, "constraint synthesis failed: {}", e),
+            DriverError::Groth16Rejected => write!(f, "CP-Groth16 proof rejected"),
+            DriverError::PcsRejected => write!(f, "polynomial commitment opening rejected"),
+        }
+    }
+}
+
+impl std::error::Error for DriverError {}
+
+impl From<SynthesisError> for DriverError {
+    fn from(e: SynthesisError) -> Self {
+        DriverError::Synthesis(e)
+    }
+}
+
+/// A multistage circuit
+/// Stage 1. Witness the message, or the pixel bytes it packs.
+/// Stage 2. Prove that the message evaluates to a given value at a point derived in an F-S way
+/// from the commitment to stage 1.
+pub struct EvalCircuit<G> {
+    pub message: Vec<F>,
+
+    // The bytes `message` packs, if it was built from pixels. Elements past them are zero.
+    pub pixels: Option<Vec<u8>>,
+
+    // The variables corresponding to `message` that are generated after stage 0.
+    message_var: Option<Vec<FpVar<F>>>,
+
+    // The evaluation point. Derived via Fiat-Shamir + Commit and Prove.
+    pub point: Option<Vec<F>>,
+
+    // The evaluation of `self.message` at `self.point`.
+    pub evaluation: Option<F>,
+
+    _gadget: PhantomData<G>,
+}
+
+impl<G> Clone for EvalCircuit<G> {
+    fn clone(&self) -> Self {
+        Self {
+            message: self.message.clone(),
+            pixels: self.pixels.clone(),
+            message_var: self.message_var.clone(),
+            point: self.point.clone(),
+            evaluation: self.evaluation,
+            _gadget: PhantomData,
+        }
+    }
+}
+
+impl<G: EvaluationGadget> EvalCircuit<G> {
+    pub fn new(message: Vec<F>) -> Self {
+        Self {
+            message,
+            pixels: None,
+            message_var: None,
+            point: None,
+            evaluation: None,
+            _gadget: PhantomData,
+        }
+    }
+
+    /// The message `pixels` pack into, padded with zeros to `message_len` elements.
+    pub fn from_pixels(pixels: Vec<u8>, message_len: usize) -> Self {
+        let mut message = packing::pack(&pixels);
+        assert!(
+            message.len() <= message_len,
+            "pixels pack into more than {} elements",
+            message_len
+        );
+        message.resize(message_len, F::zero());
+        Self {
+            pixels: Some(pixels),
+            ..Self::new(message)
+        }
+    }
+
+    pub fn point_len(&self) -> usize {
+        G::point_len(self.message.len())
+    }
+
+    /// Fixes the evaluation point and computes the evaluation there.
+    pub fn add_point(&mut self, point: Vec<F>) {
+        self.evaluation = Some(G::evaluate(&self.message, &point));
+        self.point = Some(point);
+    }
+
+    /// The public inputs of stage 1: the point, then the evaluation.
+    pub fn public_inputs(&self) -> Vec<F> {
+        let mut inputs = self.point.clone().unwrap();
+        inputs.push(self.evaluation.unwrap());
+        inputs
+    }
+
+    fn stage_0(&mut self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let message_var = match &self.pixels {
+            Some(pixels) => {
+                let bytes = UInt8::new_witness_vec(ns!(cs, "pixels"), &pixels[..])?;
+                let mut message_var = packing::pack_var(&bytes)?;
+                message_var.resize(self.message.len(), FpVar::zero());
+                message_var
+            }
+            None => self
+                .message
+                .iter()
+                .map(|c| FpVar::new_witness(ns!(cs, "message"), || Ok(c)))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        self.message_var = Some(message_var);
+
+        Ok(())
+    }
+
+    fn stage_1(&mut self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let point = (0..self.point_len())
+            .map(|i| {
+                FpVar::new_input(ns!(cs, "point"), || {
+                    self.point
+                        .as_ref()
+                        .map(|point| point[i])
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let evaluation = FpVar::new_input(ns!(cs, "evaluation"), || {
+            self.evaluation.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let claimed_eval = G::evaluate_var(cs, self.message_var.as_ref().unwrap(), &point)?;
+        claimed_eval.enforce_equal(&evaluation)?;
+        Ok(())
+    }
+}
+
+impl<G: EvaluationGadget> MultiStageConstraintSynthesizer<F> for EvalCircuit<G> {
+    fn total_num_stages(&self) -> usize {
+        2
+    }
+
+    fn generate_constraints(
+        &mut self,
+        stage: usize,
+        cs: &mut MultiStageConstraintSystem<F>,
+    ) -> Result<(), SynthesisError> {
+        match stage {
+            0 => cs.synthesize_with(|c| self.stage_0(c)),
+            1 => cs.synthesize_with(|c| self.stage_1(c)),
+            _ => panic!("unexpected stage stage {}", stage),
+        }
+    }
+}
+
+/// Number of constraints `circuit` has.
+pub fn num_constraints<G: EvaluationGadget>(
+    mut circuit: EvalCircuit<G>,
+    rng: &mut impl Rng,
+) -> Result<usize, SynthesisError> {
+    let mut cs = MultiStageConstraintSystem::default();
+    circuit.generate_constraints(0, &mut cs)?;
+    let point = (0..circuit.point_len()).map(|_| F::rand(rng)).collect();
+    circuit.add_point(point);
+    circuit.generate_constraints(1, &mut cs)?;
+    Ok(cs.num_constraints())
+}
+
+/// Verifies that the message committed to by `pcs_commitment`, which is also the message
+/// witnessed in `proof`, evaluates to `evaluation` at the point derived from both commitments.
+#[allow(clippy::too_many_arguments)]
+pub fn verify<G: EvaluationGadget, R: RngCore>(
+    vk: &VerifyingKey<E>,
+    pcs: &G::Pcs,
+    message_len: usize,
+    proof: &Proof<E>,
+    pcs_commitment: &<G::Pcs as MessagePcs>::Commitment,
+    evaluation: F,
+    pcs_proof: &<G::Pcs as MessagePcs>::Proof,
+    rng: &mut R,
+) -> Result<(), DriverError> {
+    let point =
+        transcript::evaluation_point(&proof.ds[0], pcs_commitment, G::point_len(message_len));
+    let mut inputs = point.clone();
+    inputs.push(evaluation);
+
+    let start = Instant::now();
+    let pvk = prepare_verifying_key(vk);
+    if !verify_proof(&pvk, proof, &inputs)? {
+        return Err(DriverError::Groth16Rejected);
+    }
+    println!("CP-Groth16 verify: {} s", start.elapsed().as_secs_f64());
+
+    let start = Instant::now();
+    if !pcs.check(pcs_commitment, &point, evaluation, pcs_proof, rng) {
+        return Err(DriverError::PcsRejected);
+    }
+    println!("PCS verify: {} s", start.elapsed().as_secs_f64());
+
+    Ok(())
+}
+
+/// Sets up CP-Groth16 and the gadget's PCS for messages as long as `message`, commits to it with
+/// both, proves the circuit and opens the PCS at the derived point, then verifies all of it,
+/// printing how long each step took.
+pub fn run<G: EvaluationGadget>(message: Vec<F>) -> Result<(), DriverError> {
+    run_with(EvalCircuit::<G>::new(message), None)
+}
+
+/// `run` for the message `pixels` pack into, padded to `message_len` elements. The circuit packs
+/// the pixels itself, so the constraint counts include range-checking every byte.
+pub fn run_pixels<G: EvaluationGadget>(
+    pixels: Vec<u8>,
+    message_len: usize,
+) -> Result<(), DriverError> {
+    println!(
+        "Pixel bytes: {}, packed into {} of {} elements",
+        pixels.len(),
+        packing::packed_len(pixels.len()),
+        message_len
+    );
+    run_with(EvalCircuit::<G>::from_pixels(pixels, message_len), None)
+}
+
+// `run`, except that a `chosen_point` replaces the one derived from the commitments, the way a
+// cheating prover would. Split out so tests can check such proofs are rejected.
+fn run_with<G: EvaluationGadget>(
+    circuit: EvalCircuit<G>,
+    chosen_point: Option<Vec<F>>,
+) -> Result<(), DriverError> {
+    let message_len = circuit.message.len();
+    let mut rng = ark_std::test_rng();
+
+    // Sanity-check the circuit/get constraint counts
+    let constraints = num_constraints(circuit.clone(), &mut rng)?;
+    println!("Num constraints: {:?}", constraints);
+    println!(
+        "Constraints per field element: {:.2}",
+        constraints as f64 / message_len as f64
+    );
+
+    // Setup
+    let start = Instant::now();
+    let pk = generate_parameters::<_, E, QAP>(circuit.clone(), &mut rng)?;
+    println!("Groth16 setup: {} s", start.elapsed().as_secs_f64());
+
+    let mut rng = ark_std::test_rng();
+    let mut cb = CommitmentBuilder::<_, E, QAP>::new(circuit, &pk);
+
+    let start = Instant::now();
+    let pcs = <G::Pcs as MessagePcs>::setup(message_len, &mut rng);
+    println!("PCS setup: {} s", start.elapsed().as_secs_f64());
+
+    // Commit
+    let start = Instant::now();
+    let (comm, rand) = cb.commit(&mut rng)?;
+    println!("Groth16 commit: {} s", start.elapsed().as_secs_f64());
+
+    let start = Instant::now();
+    let (pcs_comm, pcs_state) = pcs.commit(&cb.circuit.message, &mut rng);
+    println!("PCS commit: {} s", start.elapsed().as_secs_f64());
+
+    let start = Instant::now();
+    let point = chosen_point
+        .unwrap_or_else(|| transcript::evaluation_point(&comm, &pcs_comm, cb.circuit.point_len()));
+    cb.circuit.add_point(point.clone());
+    println!(
+        "Point derivation (add_point): {} s",
+        start.elapsed().as_secs_f64()
+    );
+
+    // Prove / Open
+    let evaluation = cb.circuit.evaluation.unwrap();
+    let start = Instant::now();
+    let proof = cb.prove(&[comm], &[rand], &mut rng)?;
+    println!("Groth16 prove: {} s", start.elapsed().as_secs_f64());
+
+    let start = Instant::now();
+    let pcs_proof = pcs.open(&pcs_comm, &pcs_state, &point);
+    println!("PCS open: {} s", start.elapsed().as_secs_f64());
+
+    // Verify
+    verify::<G, _>(
+        &pk.vk(),
+        &pcs,
+        message_len,
+        &proof,
+        &pcs_comm,
+        evaluation,
+        &pcs_proof,
+        &mut rng,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::{Barycentric, BatchedBarycentric, Horner, Multilinear};
+    use ark_ff::PrimeField;
+
+    fn message(len: u64) -> Vec<F> {
+        (0..len).map(|i| F::from(3 * i + 1)).collect()
+    }
+
+    #[test]
+    fn derived_point_verifies() {
+        run::<Horner>(message(16)).unwrap();
+        run::<Barycentric>(message(16)).unwrap();
+        run::<BatchedBarycentric>(message(16)).unwrap();
+        run::<Multilinear>(message(16)).unwrap();
+    }
+
+    #[test]
+    fn proof_for_another_point_is_rejected() {
+        // Both the circuit and the PCS opening are honest for the chosen point, so only the
+        // derivation of the point can catch them.
+        let result = run_with(
+            EvalCircuit::<Horner>::new(message(16)),
+            Some(vec![F::from(5u64)]),
+        );
+        assert!(matches!(result, Err(DriverError::Groth16Rejected)));
+
+        let result = run_with(
+            EvalCircuit::<Multilinear>::new(message(16)),
+            Some(vec![F::from(5u64); 4]),
+        );
+        assert!(matches!(result, Err(DriverError::Groth16Rejected)));
+    }
+
+    #[test]
+    fn batched_barycentric_matches_barycentric() {
+        let point = [F::from(5u64)];
+        assert_eq!(
+            BatchedBarycentric::evaluate(&message(16), &point),
+            Barycentric::evaluate(&message(16), &point)
+        );
+
+        let mut rng = ark_std::test_rng();
+        let batched = num_constraints(
+            EvalCircuit::<BatchedBarycentric>::new(message(64)),
+            &mut rng,
+        )
+        .unwrap();
+        let unbatched =
+            num_constraints(EvalCircuit::<Barycentric>::new(message(64)), &mut rng).unwrap();
+        assert!(batched < unbatched);
+    }
+
+    #[test]
+    fn pixel_messages_verify() {
+        // 100 bytes pack into four elements, the last one partly filled.
+        let pixels: Vec<u8> = (0..100).map(|i| (7 * i) as u8).collect();
+        run_pixels::<Horner>(pixels.clone(), 4).unwrap();
+        run_pixels::<Multilinear>(pixels.clone(), 8).unwrap();
+
+        let circuit = EvalCircuit::<Multilinear>::from_pixels(pixels.clone(), 8);
+        assert_eq!(
+            circuit.message[3],
+            F::from_le_bytes_mod_order(&pixels[93..])
+        );
+        assert_eq!(circuit.message[4], F::zero());
+    }
+
+    #[test]
+    fn multilinear_messages_need_not_be_a_power_of_two() {
+        run::<Multilinear>(message(11)).unwrap();
+
+        let mut padded = message(11);
+        padded.resize(16, F::zero());
+        let point = [F::from(2u64), F::from(3u64), F::from(5u64), F::from(7u64)];
+        assert_eq!(
+            Multilinear::evaluate(&message(11), &point),
+            Multilinear::evaluate(&padded, &point)
+        );
+
+        // The padding is neither witnessed nor met by the eq table.
+        let mut rng = ark_std::test_rng();
+        let unpadded = num_constraints(EvalCircuit::<Multilinear>::new(message(11)), &mut rng);
+        let padded = num_constraints(EvalCircuit::<Multilinear>::new(padded), &mut rng);
+        assert!(unpadded.unwrap() < padded.unwrap());
+    }
+}
@@ -0,0 +1,272 @@
+// Ways of evaluating a message as a polynomial, natively and in-circuit.
+//
+// - `Horner` reads the message as the coefficients of a univariate polynomial. One
+//   multiplication per element, so one constraint per element.
+// - `Barycentric` reads it as the values of a univariate polynomial over the subgroup of
+//   `len`-th roots of unity and evaluates with the barycentric formula, one inversion per element.
+// - `BatchedBarycentric` evaluates the same polynomial with the same formula, but shows all the
+//   denominators are invertible at once, so each element costs a single constraint.
+// - `Multilinear` reads it as the values of a multilinear polynomial over the boolean hypercube
+//   and takes its inner product with the eq table of the point. A message of any length is
+//   padded with zeros to fill the hypercube, but the padding is never witnessed: only the part of
+//   the eq table that meets the message is computed.
+
+use ark_ff::{batch_inversion, FftField, Field, One, Zero};
+use ark_poly::{DenseMultilinearExtension, Polynomial};
+use ark_r1cs_std::{
+    fields::fp::FpVar,
+    prelude::{AllocVar, FieldVar},
+    R1CSVar,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::log2;
+
+use crate::pcs::{KzgCoefficients, KzgEvaluations, MessagePcs, MultilinearKzg};
+use crate::F;
+
+pub trait EvaluationGadget {
+    /// Commits to the polynomial this gadget evaluates.
+    type Pcs: MessagePcs;
+
+    /// Number of field elements in an evaluation point for a message of `message_len` elements.
+    fn point_len(message_len: usize) -> usize;
+
+    /// Evaluates `message` at `point` outside the circuit.
+    fn evaluate(message: &[F], point: &[F]) -> F;
+
+    /// Evaluates the witnessed `message` at the public `point`.
+    fn evaluate_var(
+        cs: ConstraintSystemRef<F>,
+        message: &[FpVar<F>],
+        point: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError>;
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Horner;
+
+impl EvaluationGadget for Horner {
+    type Pcs = KzgCoefficients;
+
+    fn point_len(_message_len: usize) -> usize {
+        1
+    }
+
+    fn evaluate(message: &[F], point: &[F]) -> F {
+        message
+            .iter()
+            .rev()
+            .fold(F::zero(), |acc, c| acc * point[0] + c)
+    }
+
+    fn evaluate_var(
+        _cs: ConstraintSystemRef<F>,
+        message: &[FpVar<F>],
+        point: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        // This way of implementing evaluation takes half the constraints of the one in Hekaton.
+        Ok(message
+            .iter()
+            .rev()
+            .fold(FpVar::zero(), |acc, x| (acc * &point[0]) + x))
+    }
+}
+
+// Generator of the subgroup of `len`-th roots of unity.
+fn subgroup_generator(len: usize) -> F {
+    let mut omega = F::TWO_ADIC_ROOT_OF_UNITY;
+    for _ in log2(len)..F::TWO_ADICITY {
+        omega = omega.square();
+    }
+    omega
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Barycentric;
+
+impl EvaluationGadget for Barycentric {
+    type Pcs = KzgEvaluations;
+
+    fn point_len(_message_len: usize) -> usize {
+        1
+    }
+
+    fn evaluate(message: &[F], point: &[F]) -> F {
+        let point = point[0];
+        let degree = message.len();
+        let omega = subgroup_generator(degree);
+
+        // Will contain powers of omega needed for sum
+        let mut omega_acc = F::one();
+        let mut sum = F::zero();
+        for evaluation in message {
+            sum += *evaluation * omega_acc * (point - omega_acc).inverse().unwrap();
+            omega_acc *= omega;
+        }
+
+        let front_quantity = (point.pow([degree as u64]) - F::one()) / F::from(degree as u64);
+        front_quantity * sum
+    }
+
+    fn evaluate_var(
+        _cs: ConstraintSystemRef<F>,
+        message: &[FpVar<F>],
+        point: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let point = &point[0];
+        let degree = message.len();
+        let omega = FpVar::constant(subgroup_generator(degree));
+
+        let mut omega_acc = FpVar::one();
+        let mut sum = FpVar::zero();
+        // Simple version: invert each denominator on its own, no batching.
+        for evaluation in message {
+            let numerator = evaluation * &omega_acc;
+            let denominator = point - &omega_acc;
+            sum += numerator * denominator.inverse()?;
+            omega_acc *= &omega;
+        }
+
+        let front_quantity = (point.pow_by_constant([degree as u64])? - F::one())
+            * FpVar::constant(F::from(degree as u64).inverse().unwrap());
+        Ok(front_quantity * sum)
+    }
+}
+
+// The `len`-th roots of unity, in the order the message is laid out over them.
+fn subgroup_powers(len: usize) -> Vec<F> {
+    let omega = subgroup_generator(len);
+    std::iter::successors(Some(F::one()), |w| Some(*w * omega))
+        .take(len)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BatchedBarycentric;
+
+impl EvaluationGadget for BatchedBarycentric {
+    type Pcs = KzgEvaluations;
+
+    fn point_len(_message_len: usize) -> usize {
+        1
+    }
+
+    fn evaluate(message: &[F], point: &[F]) -> F {
+        let point = point[0];
+        let degree = message.len();
+        let powers = subgroup_powers(degree);
+        let mut denominators: Vec<F> = powers.iter().map(|w| point - w).collect();
+        batch_inversion(&mut denominators);
+        let sum: F = message
+            .iter()
+            .zip(&powers)
+            .zip(&denominators)
+            .map(|((evaluation, w), inverse)| *evaluation * w * inverse)
+            .sum();
+
+        let front_quantity = (point.pow([degree as u64]) - F::one()) / F::from(degree as u64);
+        front_quantity * sum
+    }
+
+    fn evaluate_var(
+        cs: ConstraintSystemRef<F>,
+        message: &[FpVar<F>],
+        point: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let point = &point[0];
+        let degree = message.len();
+        let powers = subgroup_powers(degree);
+
+        // The denominators point - omega^i multiply to point^degree - 1, so inverting that one
+        // value shows none of them is zero, and each term is then fixed by a single
+        // multiplication instead of needing its own inverse.
+        let vanishing = point.pow_by_constant([degree as u64])? - F::one();
+        vanishing.inverse()?;
+
+        // The prover computes the terms with one native batch inversion.
+        let terms = if cs.is_in_setup_mode() {
+            Err(SynthesisError::AssignmentMissing)
+        } else {
+            let point = point.value()?;
+            let mut denominators: Vec<F> = powers.iter().map(|w| point - w).collect();
+            batch_inversion(&mut denominators);
+            message
+                .iter()
+                .zip(&powers)
+                .zip(&denominators)
+                .map(|((evaluation, w), inverse)| Ok(evaluation.value()? * w * inverse))
+                .collect::<Result<Vec<F>, SynthesisError>>()
+        };
+
+        let mut sum = FpVar::zero();
+        for (i, (evaluation, w)) in message.iter().zip(&powers).enumerate() {
+            let term = FpVar::new_witness(cs.clone(), || {
+                terms
+                    .as_ref()
+                    .map(|terms| terms[i])
+                    .map_err(|_| SynthesisError::AssignmentMissing)
+            })?;
+            term.mul_equals(&(point - *w), &(evaluation * *w))?;
+            sum += &term;
+        }
+
+        let front_quantity = vanishing * FpVar::constant(F::from(degree as u64).inverse().unwrap());
+        Ok(front_quantity * sum)
+    }
+}
+
+/// The first `len` entries of the eq table of `point`: entry `i` is the multilinear Lagrange
+/// basis polynomial of `i` at `point`, with bit `k` of `i` standing for variable `k`.
+pub fn eq_table_var(point: &[FpVar<F>], len: usize) -> Vec<FpVar<F>> {
+    let mut table = vec![FpVar::one()];
+    // Progressively build up eq polynomial evaluations. The first `len` entries of each table only
+    // depend on the first `len` of the one before, so nothing past them is ever computed.
+    for p in point {
+        let high: Vec<_> = table
+            .iter()
+            .take(len.saturating_sub(table.len()))
+            .map(|e| p * e)
+            .collect();
+        let low: Vec<_> = table
+            .iter()
+            .enumerate()
+            .map(|(i, e)| match high.get(i) {
+                // e * (1 - p) without another multiplication.
+                Some(h) => e - h,
+                None => e - p * e,
+            })
+            .collect();
+        table = low.into_iter().chain(high).collect();
+    }
+    table.truncate(len);
+    table
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Multilinear;
+
+impl EvaluationGadget for Multilinear {
+    type Pcs = MultilinearKzg;
+
+    fn point_len(message_len: usize) -> usize {
+        log2(message_len) as usize
+    }
+
+    fn evaluate(message: &[F], point: &[F]) -> F {
+        let mut evaluations = message.to_vec();
+        evaluations.resize(1 << point.len(), F::zero());
+        let mle = DenseMultilinearExtension::from_evaluations_vec(point.len(), evaluations);
+        Polynomial::evaluate(&mle, &point.to_vec())
+    }
+
+    fn evaluate_var(
+        _cs: ConstraintSystemRef<F>,
+        message: &[FpVar<F>],
+        point: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        Ok(eq_table_var(point, message.len())
+            .iter()
+            .zip(message)
+            .fold(FpVar::zero(), |acc, (x, y)| acc + x * y))
+    }
+}
@@ -0,0 +1,21 @@
+// Commit-and-prove Groth16 circuits that evaluate a signed message as a polynomial.
+//
+// Every circuit here has the same two stages: stage 0 witnesses the message and is committed to
+// by CP-Groth16, and stage 1 proves that the message, read as a polynomial, evaluates to a public
+// value at a public point derived from that commitment by Fiat-Shamir. A polynomial commitment to
+// the same message is opened at the same point outside the circuit. The strategies only differ in
+// how the message is read as a polynomial, so each one is an `EvaluationGadget` paired with the
+// `MessagePcs` that commits to that polynomial, and `driver` runs the rest for any of them.
+// A message can also be given as pixel bytes, which stage 0 packs into field elements, see
+// `packing`.
+
+use ark_bls12_381::{Bls12_381, Fr};
+
+pub mod driver;
+pub mod gadgets;
+pub mod packing;
+pub mod pcs;
+pub mod transcript;
+
+pub type E = Bls12_381;
+pub type F = Fr;
@@ -0,0 +1,75 @@
+// Packing pixel bytes into field elements, natively and in-circuit.
+//
+// A BLS12-381 scalar holds 31 whole bytes, so every 31 bytes of a frame become one element, little
+// endian. In-circuit, each byte is witnessed as eight booleans, which is the range check, and an
+// element is built as the linear combination of its bits rather than witnessed and constrained to
+// equal it, so packing costs eight constraints per byte and nothing per element.
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    fields::fp::FpVar,
+    prelude::{FieldVar, ToBitsGadget, UInt8},
+};
+use ark_relations::r1cs::SynthesisError;
+use ark_std::rand::Rng;
+
+pub const BYTES_PER_ELEMENT: usize = 31;
+
+/// Bytes in an RGB frame of 1280x720 pixels.
+pub const FRAME_720P_BYTES: usize = 1280 * 720 * 3;
+
+/// Number of elements `num_bytes` bytes pack into.
+pub fn packed_len(num_bytes: usize) -> usize {
+    num_bytes.div_ceil(BYTES_PER_ELEMENT)
+}
+
+pub fn pack<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    debug_assert!(8 * BYTES_PER_ELEMENT < F::MODULUS_BIT_SIZE as usize);
+    bytes
+        .chunks(BYTES_PER_ELEMENT)
+        .map(F::from_le_bytes_mod_order)
+        .collect()
+}
+
+/// Packs witnessed bytes the same way as `pack`.
+pub fn pack_var<F: PrimeField>(bytes: &[UInt8<F>]) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    bytes
+        .chunks(BYTES_PER_ELEMENT)
+        .map(|chunk| {
+            let mut element = FpVar::zero();
+            let mut power = F::one();
+            for bit in chunk.to_bits_le()? {
+                element += FpVar::from(bit) * power;
+                power.double_in_place();
+            }
+            Ok(element)
+        })
+        .collect()
+}
+
+/// A frame of random pixels. The constraint counts only depend on the number of bytes.
+pub fn random_720p_frame<R: Rng>(rng: &mut R) -> Vec<u8> {
+    (0..FRAME_720P_BYTES).map(|_| rng.gen()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn packing_in_circuit_adds_no_constraints() {
+        // 100 bytes pack into four elements, the last one partly filled.
+        let bytes: Vec<u8> = (0..100).map(|i| (7 * i + 3) as u8).collect();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let bytes_var = UInt8::new_witness_vec(cs.clone(), &bytes).unwrap();
+        assert_eq!(cs.num_constraints(), 8 * bytes.len());
+
+        let packed = pack_var(&bytes_var).unwrap();
+        assert_eq!(cs.num_constraints(), 8 * bytes.len());
+        assert_eq!(packed.value().unwrap(), pack::<Fr>(&bytes));
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,249 @@
+// Polynomial commitments to a message, opened outside the circuit.
+//
+// The univariate commitments are Marlin-style KZG over coefficients. A message given as
+// evaluations over a power-of-two subgroup is interpolated first, so it is committed as the same
+// polynomial the barycentric gadget evaluates. Multilinear messages use the multilinear KZG of
+// ark-poly-commit over the boolean hypercube, padded with zeros to fill it.
+
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly::{
+    DenseMultilinearExtension, DenseUVPolynomial, EvaluationDomain, Evaluations,
+    Radix2EvaluationDomain,
+};
+use ark_poly_commit::multilinear_pc::data_structures::{
+    Commitment as MultilinearCommitment, CommitterKey as MultilinearCommitterKey,
+    Proof as MultilinearProof, VerifierKey as MultilinearVerifierKey,
+};
+use ark_poly_commit::multilinear_pc::MultilinearPC;
+use ark_poly_commit::{
+    challenge::ChallengeGenerator, marlin::marlin_pc::MarlinKZG10, LabeledCommitment,
+    LabeledPolynomial, PolynomialCommitment,
+};
+use ark_serialize::CanonicalSerialize;
+use ark_std::log2;
+use ark_std::rand::RngCore;
+use ark_std::{UniformRand, Zero};
+
+use crate::{E, F};
+
+pub type Kzg = MarlinKZG10<E, DensePolynomial<F>, PoseidonSponge<F>>;
+
+type KzgCommitment =
+    <Kzg as PolynomialCommitment<F, DensePolynomial<F>, PoseidonSponge<F>>>::Commitment;
+type KzgRandomness =
+    <Kzg as PolynomialCommitment<F, DensePolynomial<F>, PoseidonSponge<F>>>::Randomness;
+type KzgProof = <Kzg as PolynomialCommitment<F, DensePolynomial<F>, PoseidonSponge<F>>>::Proof;
+type KzgCommitterKey =
+    <Kzg as PolynomialCommitment<F, DensePolynomial<F>, PoseidonSponge<F>>>::CommitterKey;
+type KzgVerifierKey =
+    <Kzg as PolynomialCommitment<F, DensePolynomial<F>, PoseidonSponge<F>>>::VerifierKey;
+
+/// A commitment scheme for messages of a fixed length, opened at the point the circuit
+/// evaluates them at.
+pub trait MessagePcs: Sized {
+    type Commitment: CanonicalSerialize;
+    /// What the committer keeps to open later.
+    type State;
+    type Proof;
+
+    /// Sets up for messages of `message_len` field elements.
+    fn setup<R: RngCore>(message_len: usize, rng: &mut R) -> Self;
+
+    fn commit<R: RngCore>(&self, message: &[F], rng: &mut R) -> (Self::Commitment, Self::State);
+
+    fn open(&self, commitment: &Self::Commitment, state: &Self::State, point: &[F]) -> Self::Proof;
+
+    /// Checks that the committed message evaluates to `value` at `point`.
+    fn check<R: RngCore>(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[F],
+        value: F,
+        proof: &Self::Proof,
+        rng: &mut R,
+    ) -> bool;
+}
+
+/// KZG commitment to the polynomial whose coefficients are the message.
+pub struct KzgCoefficients {
+    ck: KzgCommitterKey,
+    vk: KzgVerifierKey,
+    // Seeds the challenges for batched openings. We only ever open one polynomial, so it does not
+    // need to come from a transcript.
+    challenge_seed: F,
+}
+
+impl KzgCoefficients {
+    fn labeled(commitment: &KzgCommitment) -> LabeledCommitment<KzgCommitment> {
+        LabeledCommitment::new("message".to_string(), commitment.clone(), None)
+    }
+
+    fn challenges(&self) -> ChallengeGenerator<F, PoseidonSponge<F>> {
+        ChallengeGenerator::Univariate(self.challenge_seed, self.challenge_seed)
+    }
+
+    fn commit_polynomial<R: RngCore>(
+        &self,
+        polynomial: DensePolynomial<F>,
+        rng: &mut R,
+    ) -> (
+        KzgCommitment,
+        (LabeledPolynomial<F, DensePolynomial<F>>, KzgRandomness),
+    ) {
+        let labeled = LabeledPolynomial::new("message".to_string(), polynomial, None, None);
+        let (mut commitments, mut rands) =
+            Kzg::commit(&self.ck, [&labeled], Some(rng)).expect("message exceeds the KZG setup");
+        let commitment = commitments.pop().unwrap().commitment().clone();
+        (commitment, (labeled, rands.pop().unwrap()))
+    }
+}
+
+impl MessagePcs for KzgCoefficients {
+    type Commitment = KzgCommitment;
+    type State = (LabeledPolynomial<F, DensePolynomial<F>>, KzgRandomness);
+    type Proof = KzgProof;
+
+    fn setup<R: RngCore>(message_len: usize, rng: &mut R) -> Self {
+        let degree = message_len - 1;
+        let pp = Kzg::setup(degree, None, rng).unwrap();
+        let (ck, vk) = Kzg::trim(&pp, degree, 1, None).unwrap();
+        Self {
+            ck,
+            vk,
+            challenge_seed: F::rand(rng),
+        }
+    }
+
+    fn commit<R: RngCore>(&self, message: &[F], rng: &mut R) -> (Self::Commitment, Self::State) {
+        self.commit_polynomial(DensePolynomial::from_coefficients_slice(message), rng)
+    }
+
+    fn open(
+        &self,
+        commitment: &Self::Commitment,
+        (polynomial, randomness): &Self::State,
+        point: &[F],
+    ) -> Self::Proof {
+        Kzg::open(
+            &self.ck,
+            [polynomial],
+            &[Self::labeled(commitment)],
+            &point[0],
+            &mut self.challenges(),
+            [randomness],
+            None,
+        )
+        .expect("message exceeds the KZG setup")
+    }
+
+    fn check<R: RngCore>(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[F],
+        value: F,
+        proof: &Self::Proof,
+        rng: &mut R,
+    ) -> bool {
+        Kzg::check(
+            &self.vk,
+            &[Self::labeled(commitment)],
+            &point[0],
+            std::iter::once(value),
+            proof,
+            &mut self.challenges(),
+            Some(rng),
+        )
+        .unwrap_or(false)
+    }
+}
+
+/// KZG commitment to the polynomial that takes the message as its values over the subgroup of
+/// `message_len`-th roots of unity, which must be a power of two.
+pub struct KzgEvaluations {
+    kzg: KzgCoefficients,
+    domain: Radix2EvaluationDomain<F>,
+}
+
+impl MessagePcs for KzgEvaluations {
+    type Commitment = KzgCommitment;
+    type State = (LabeledPolynomial<F, DensePolynomial<F>>, KzgRandomness);
+    type Proof = KzgProof;
+
+    fn setup<R: RngCore>(message_len: usize, rng: &mut R) -> Self {
+        assert!(
+            message_len.is_power_of_two(),
+            "messages given as evaluations must have a power-of-two length"
+        );
+        Self {
+            kzg: KzgCoefficients::setup(message_len, rng),
+            domain: Radix2EvaluationDomain::new(message_len).unwrap(),
+        }
+    }
+
+    fn commit<R: RngCore>(&self, message: &[F], rng: &mut R) -> (Self::Commitment, Self::State) {
+        // Interpolate via IFFT to get the coefficient-form polynomial for KZG.
+        let polynomial =
+            Evaluations::from_vec_and_domain(message.to_vec(), self.domain).interpolate();
+        self.kzg.commit_polynomial(polynomial, rng)
+    }
+
+    fn open(&self, commitment: &Self::Commitment, state: &Self::State, point: &[F]) -> Self::Proof {
+        self.kzg.open(commitment, state, point)
+    }
+
+    fn check<R: RngCore>(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[F],
+        value: F,
+        proof: &Self::Proof,
+        rng: &mut R,
+    ) -> bool {
+        self.kzg.check(commitment, point, value, proof, rng)
+    }
+}
+
+/// Multilinear KZG commitment to the multilinear extension of the message over the boolean
+/// hypercube, whose dimension is the log of the message length rounded up. The message is padded
+/// with zeros, as the multilinear gadget assumes.
+pub struct MultilinearKzg {
+    ck: MultilinearCommitterKey<E>,
+    vk: MultilinearVerifierKey<E>,
+    num_vars: usize,
+}
+
+impl MessagePcs for MultilinearKzg {
+    type Commitment = MultilinearCommitment<E>;
+    type State = DenseMultilinearExtension<F>;
+    type Proof = MultilinearProof<E>;
+
+    fn setup<R: RngCore>(message_len: usize, rng: &mut R) -> Self {
+        let num_vars = log2(message_len) as usize;
+        let params = MultilinearPC::<E>::setup(num_vars, rng);
+        let (ck, vk) = MultilinearPC::<E>::trim(&params, num_vars);
+        Self { ck, vk, num_vars }
+    }
+
+    fn commit<R: RngCore>(&self, message: &[F], _rng: &mut R) -> (Self::Commitment, Self::State) {
+        let mut evaluations = message.to_vec();
+        evaluations.resize(1 << self.num_vars, F::zero());
+        let mle = DenseMultilinearExtension::from_evaluations_vec(self.num_vars, evaluations);
+        (MultilinearPC::<E>::commit(&self.ck, &mle), mle)
+    }
+
+    fn open(&self, _commitment: &Self::Commitment, mle: &Self::State, point: &[F]) -> Self::Proof {
+        MultilinearPC::<E>::open(&self.ck, mle, point)
+    }
+
+    fn check<R: RngCore>(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[F],
+        value: F,
+        proof: &Self::Proof,
+        _rng: &mut R,
+    ) -> bool {
+        MultilinearPC::<E>::check(&self.vk, commitment, point, value, proof)
+    }
+}
@@ -0,0 +1,113 @@
+// Fiat-Shamir derivation of the evaluation point.
+//
+// The in-circuit check only means something if the prover could not choose the point after
+// fixing the message, so the point is a hash of everything that fixes the message: the CP-Groth16
+// commitment to stage 0, which carries the witnessed message and ends up in the proof as `ds[0]`,
+// and the PCS commitment that is signed. The verifier recomputes the point from the proof itself
+// and uses it as the public input, so a proof made at any other point does not verify.
+//
+// The transcript is a running SHA-256 over length-prefixed, labelled items. Each coordinate of
+// the point is reduced from 64 bytes of output, so its bias is negligible.
+
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use sha2::{Digest, Sha256};
+
+use crate::F;
+
+// Absorbed first, so points can't be shared with another protocol using the same commitments.
+const TRANSCRIPT_DOMAIN: &[u8] = b"video-signature-verification evaluation point";
+
+#[derive(Clone)]
+pub struct Transcript {
+    hasher: Sha256,
+}
+
+impl Default for Transcript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transcript {
+    pub fn new() -> Self {
+        let mut transcript = Self {
+            hasher: Sha256::new(),
+        };
+        transcript.append_bytes(b"domain", TRANSCRIPT_DOMAIN);
+        transcript
+    }
+
+    pub fn append_bytes(&mut self, label: &[u8], bytes: &[u8]) {
+        for item in [label, bytes] {
+            self.hasher.update((item.len() as u64).to_le_bytes());
+            self.hasher.update(item);
+        }
+    }
+
+    /// Absorbs the compressed serialization of `item`.
+    pub fn append_serializable(&mut self, label: &[u8], item: &impl CanonicalSerialize) {
+        let mut buf = Vec::new();
+        item.serialize_compressed(&mut buf).unwrap();
+        self.append_bytes(label, &buf);
+    }
+
+    /// Squeezes a point with `len` coordinates.
+    pub fn challenge_point(mut self, len: usize) -> Vec<F> {
+        self.append_bytes(b"point length", &(len as u64).to_le_bytes());
+        let state = self.hasher.finalize();
+        (0..len as u64)
+            .map(|i| {
+                let mut bytes = Vec::with_capacity(64);
+                for half in 0u8..2 {
+                    let mut hasher = Sha256::new();
+                    hasher.update(state);
+                    hasher.update(i.to_le_bytes());
+                    hasher.update([half]);
+                    bytes.extend(hasher.finalize());
+                }
+                F::from_le_bytes_mod_order(&bytes)
+            })
+            .collect()
+    }
+}
+
+/// The point a message committed to by `stage_0_commitment` in the circuit and by
+/// `pcs_commitment` outside it is evaluated at. Binding the point to both commitments prevents a
+/// prover from using different messages for the two.
+pub fn evaluation_point(
+    stage_0_commitment: &impl CanonicalSerialize,
+    pcs_commitment: &impl CanonicalSerialize,
+    len: usize,
+) -> Vec<F> {
+    let mut transcript = Transcript::new();
+    transcript.append_serializable(b"stage 0 commitment", stage_0_commitment);
+    transcript.append_serializable(b"pcs commitment", pcs_commitment);
+    transcript.challenge_point(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_depends_on_both_commitments() {
+        let point = evaluation_point(&F::from(1u64), &F::from(2u64), 3);
+        assert_eq!(point.len(), 3);
+        assert_eq!(point, evaluation_point(&F::from(1u64), &F::from(2u64), 3));
+        assert_ne!(point, evaluation_point(&F::from(3u64), &F::from(2u64), 3));
+        assert_ne!(point, evaluation_point(&F::from(1u64), &F::from(3u64), 3));
+        // Swapping the commitments must not give the same point either.
+        assert_ne!(point, evaluation_point(&F::from(2u64), &F::from(1u64), 3));
+    }
+
+    #[test]
+    fn coordinates_are_distinct() {
+        let point = evaluation_point(&F::from(1u64), &F::from(2u64), 4);
+        for i in 0..point.len() {
+            for j in i + 1..point.len() {
+                assert_ne!(point[i], point[j]);
+            }
+        }
+    }
+}
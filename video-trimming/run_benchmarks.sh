@@ -22,4 +22,7 @@ echo -e "\033[0;32m===== Running univariate one-frame benchmarks =====\033[0m"
 cargo run --release --bin video_trimming_demo -- --use-long-segment
 
 echo -e "\033[0;32m===== Running Merkle Tree benchmarks =====\033[0m"
-cargo run --release --bin merkle_tree_trimming
\ No newline at end of file
+cargo run --release --bin merkle_tree_trimming
+
+echo -e "\033[0;32m===== Running end-to-end backend comparison =====\033[0m"
+cargo run --release --bin redactable_signature_benchmarks -- --json results.json --csv results.csv
@@ -0,0 +1,203 @@
+// Barycentric evaluation of a video over many frames and many points at once.
+//
+// The video bytes v_0..v_{len-1}, zero-padded to N = len.next_power_of_two(), are the evaluations
+// of a polynomial f on the subgroup generated by omega. Using the formula at the bottom of
+// https://hackmd.io/@vbuterin/barycentric_evaluation,
+//   f(z) = (z^N - 1) / N * sum_i v_i * omega^i / (z - omega^i)
+// Frames are streamed into fixed-size chunks; once every thread has a chunk, the chunks are summed
+// in parallel, each with one Montgomery batch inversion per point. Only one batch of chunks is
+// ever held in memory, and the zero padding contributes nothing so it is never materialised.
+
+use p3_field::{AbstractField, Field, TwoAdicField};
+use rayon::prelude::*;
+
+use crate::EF;
+
+// Bytes per chunk, and so the length of each batch inversion.
+const CHUNK_LEN: usize = 1 << 14;
+
+/// Evaluates the video made of `frames`, `len` bytes in total, at every point of `points`. The
+/// points must lie outside the evaluation domain, which random extension field challenges do.
+pub fn evaluate_frames<'a>(
+    frames: impl Iterator<Item = &'a [u8]>,
+    len: usize,
+    points: &[EF],
+) -> Vec<EF> {
+    evaluate_frames_in_chunks(frames, len, points, CHUNK_LEN)
+}
+
+fn evaluate_frames_in_chunks<'a>(
+    frames: impl Iterator<Item = &'a [u8]>,
+    len: usize,
+    points: &[EF],
+    chunk_len: usize,
+) -> Vec<EF> {
+    let degree = len.next_power_of_two();
+    let omega = EF::two_adic_generator(degree.trailing_zeros() as usize);
+    let front_quantities: Vec<EF> = points
+        .iter()
+        .map(|z| {
+            let vanishing = z.exp_u64(degree as u64) - EF::one();
+            assert!(
+                !vanishing.is_zero(),
+                "evaluation point lies in the evaluation domain"
+            );
+            vanishing / EF::from_canonical_u64(degree as u64)
+        })
+        .collect();
+
+    let batch_len = chunk_len * rayon::current_num_threads();
+    let mut sums = vec![EF::zero(); points.len()];
+    let mut batch = Vec::with_capacity(batch_len);
+    // Index of the first byte of `batch` in the video.
+    let mut batch_start = 0;
+    let mut add_batch = |batch: &[u8], batch_start: usize| {
+        let batch_sums = batch
+            .par_chunks(chunk_len)
+            .enumerate()
+            .map(|(i, chunk)| chunk_sums(chunk, batch_start + i * chunk_len, omega, points))
+            .reduce(
+                || vec![EF::zero(); points.len()],
+                |a, b| a.iter().zip(b).map(|(a, b)| *a + b).collect(),
+            );
+        for (sum, batch_sum) in sums.iter_mut().zip(batch_sums) {
+            *sum += batch_sum;
+        }
+    };
+
+    for mut frame in frames {
+        while !frame.is_empty() {
+            let take = (batch_len - batch.len()).min(frame.len());
+            batch.extend_from_slice(&frame[..take]);
+            frame = &frame[take..];
+            if batch.len() == batch_len {
+                add_batch(&batch, batch_start);
+                batch_start += batch_len;
+                batch.clear();
+            }
+        }
+    }
+    add_batch(&batch, batch_start);
+    assert!(
+        batch_start + batch.len() <= len,
+        "frames hold more than {} bytes",
+        len
+    );
+
+    sums.iter()
+        .zip(front_quantities)
+        .map(|(sum, front_quantity)| front_quantity * *sum)
+        .collect()
+}
+
+// sum_i v_i * omega^i / (z - omega^i) over one chunk starting at byte `start`, for every z.
+fn chunk_sums(chunk: &[u8], start: usize, omega: EF, points: &[EF]) -> Vec<EF> {
+    let start_power = omega.exp_u64(start as u64);
+    let mut numerators = Vec::with_capacity(chunk.len());
+    let mut powers = Vec::with_capacity(chunk.len());
+    let mut omega_acc = start_power;
+    for byte in chunk {
+        numerators.push(EF::from_canonical_u8(*byte) * omega_acc);
+        powers.push(omega_acc);
+        omega_acc *= omega;
+    }
+
+    let mut denominators = vec![EF::zero(); chunk.len()];
+    let mut partial_prods = vec![EF::zero(); chunk.len()];
+    points
+        .iter()
+        .map(|z| {
+            if chunk.is_empty() {
+                return EF::zero();
+            }
+            for (j, power) in powers.iter().enumerate() {
+                denominators[j] = *z - *power;
+                partial_prods[j] = if j == 0 {
+                    denominators[j]
+                } else {
+                    denominators[j] * partial_prods[j - 1]
+                };
+            }
+
+            let mut sum = EF::zero();
+            let mut last_inverse = partial_prods[chunk.len() - 1].inverse();
+            for j in (1..chunk.len()).rev() {
+                sum += partial_prods[j - 1] * last_inverse * numerators[j];
+                last_inverse *= denominators[j];
+            }
+            sum + last_inverse * numerators[0]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_felt;
+
+    // Interpolates the padded video with an inverse DFT and evaluates the coefficients directly.
+    fn naive_evaluation(video: &[u8], z: EF) -> EF {
+        let degree = video.len().next_power_of_two();
+        let omega_inv = EF::two_adic_generator(degree.trailing_zeros() as usize).inverse();
+        let degree_inv = EF::from_canonical_u64(degree as u64).inverse();
+        let mut result = EF::zero();
+        let mut z_power = EF::one();
+        for k in 0..degree {
+            let step = omega_inv.exp_u64(k as u64);
+            let mut coeff = EF::zero();
+            let mut power = EF::one();
+            for byte in video {
+                coeff += EF::from_canonical_u8(*byte) * power;
+                power *= step;
+            }
+            result += coeff * degree_inv * z_power;
+            z_power *= z;
+        }
+        result
+    }
+
+    fn test_video(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 37 + 11) as u8).collect()
+    }
+
+    #[test]
+    fn ragged_frames_match_naive_evaluation() {
+        let video = test_video(1000);
+        let points = [random_felt(3, 5), random_felt(7, 11), random_felt(13, 17)];
+        // Uneven frames, including an empty one, and a chunk length that divides neither.
+        let frames = [
+            &video[..300],
+            &video[300..300],
+            &video[300..777],
+            &video[777..],
+        ];
+        let evals = evaluate_frames_in_chunks(frames.into_iter(), video.len(), &points, 64);
+        for (eval, z) in evals.iter().zip(points) {
+            assert_eq!(*eval, naive_evaluation(&video, z));
+        }
+    }
+
+    #[test]
+    fn chunking_does_not_change_the_result() {
+        let video = test_video(3000);
+        let points = [random_felt(19, 23)];
+        let expected = evaluate_frames(std::iter::once(&video[..]), video.len(), &points);
+        for chunk_len in [1, 7, 256, 4096] {
+            let frames = video.chunks(333);
+            assert_eq!(
+                evaluate_frames_in_chunks(frames, video.len(), &points, chunk_len),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn length_that_is_not_a_multiple_of_the_chunk_is_handled() {
+        let video = test_video(257);
+        let z = random_felt(29, 31);
+        assert_eq!(
+            crate::barycentric_evaluation(video.clone(), z),
+            naive_evaluation(&video, z)
+        );
+    }
+}
@@ -0,0 +1,282 @@
+// End-to-end benchmark of every redactable-signature backend on a real video.
+//
+// For each backend this signs the video once, then for every requested segment length trims a
+// segment out of the middle of the video and verifies it. Each phase is timed and its peak heap
+// usage is measured with a counting allocator, and every trim records the serialized proof size.
+// Results go to stdout and optionally to JSON and CSV files. A backend that can't be set up for
+// the video, such as FRI when no parameters reach the default security level, is reported on
+// stderr and left out.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use clap::Parser;
+use serde::Serialize;
+
+use video_trimming::frames::VideoSource;
+use video_trimming::scheme::{
+    BasefoldBlocksScheme, FriScheme, LigeroScheme, MerkleScheme, RedactableSignatureScheme,
+    SubcubeScheme, SumcheckScheme, VideoShape,
+};
+use video_trimming::signing::{SecretKey, SignatureAlgorithm, VideoSigner};
+
+// Tracks live and peak heap bytes so each phase can report its own high-water mark.
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// Runs `f` and returns its result, wall-clock seconds and the heap it used on top of what was
+// already live.
+fn measure<R>(f: impl FnOnce() -> R) -> (R, f64, usize) {
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    let secs = start.elapsed().as_secs_f64();
+    (result, secs, PEAK.load(Ordering::Relaxed) - baseline)
+}
+
+const BACKENDS: [&str; 6] = [
+    MerkleScheme::NAME,
+    FriScheme::NAME,
+    LigeroScheme::NAME,
+    SumcheckScheme::NAME,
+    SubcubeScheme::NAME,
+    BasefoldBlocksScheme::NAME,
+];
+
+#[derive(Parser, Debug)]
+#[command(about = "Benchmarks every redactable-signature backend end to end", long_about = None)]
+struct Opt {
+    /// Directory of `<prefix>_<frame>_<B|G|R>.txt` matrix files to sign.
+    #[arg(long, default_value = "../demo/decomposed_frames")]
+    frames_dir: PathBuf,
+
+    /// Read `.ppm`/`.pgm` frames from this directory instead of matrix files.
+    #[arg(long)]
+    pnm_dir: Option<PathBuf>,
+
+    /// Number of frames of the video to sign (10s at 25 fps by default).
+    #[arg(long, default_value_t = 250)]
+    num_frames: usize,
+
+    /// Lengths, in frames, of the segments to trim.
+    #[arg(long, value_delimiter = ',', default_value = "1,25,125,250")]
+    segment_lengths: Vec<usize>,
+
+    /// Backends to run.
+    #[arg(long, value_delimiter = ',', default_values_t = BACKENDS.map(String::from))]
+    backends: Vec<String>,
+
+    /// Algorithm the commitments are signed with.
+    #[arg(long, default_value_t = SignatureAlgorithm::Ed25519)]
+    algorithm: SignatureAlgorithm,
+
+    /// Write the results as JSON to this file.
+    #[arg(long)]
+    json: Option<PathBuf>,
+
+    /// Write the results as CSV to this file.
+    #[arg(long)]
+    csv: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct BenchmarkResult {
+    backend: &'static str,
+    algorithm: SignatureAlgorithm,
+    num_frames: usize,
+    frame_len: usize,
+    segment_frames: usize,
+    // All times are in seconds and all sizes in bytes. Signing is done once per backend, so
+    // its numbers repeat across that backend's rows.
+    sign_time: f64,
+    sign_peak_memory: usize,
+    trim_time: f64,
+    trim_peak_memory: usize,
+    verify_time: f64,
+    verify_peak_memory: usize,
+    proof_size: usize,
+}
+
+impl BenchmarkResult {
+    const CSV_HEADER: &'static str =
+        "backend,algorithm,num_frames,frame_len,segment_frames,sign_time,\
+        sign_peak_memory,trim_time,trim_peak_memory,verify_time,verify_peak_memory,proof_size";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.backend,
+            self.algorithm,
+            self.num_frames,
+            self.frame_len,
+            self.segment_frames,
+            self.sign_time,
+            self.sign_peak_memory,
+            self.trim_time,
+            self.trim_peak_memory,
+            self.verify_time,
+            self.verify_peak_memory,
+            self.proof_size
+        )
+    }
+}
+
+fn run<S: RedactableSignatureScheme>(
+    video: &[u8],
+    shape: VideoShape,
+    algorithm: SignatureAlgorithm,
+    segment_lengths: &[usize],
+) -> io::Result<Vec<BenchmarkResult>> {
+    let scheme = match S::setup(shape) {
+        Ok(scheme) => scheme,
+        Err(e) => {
+            eprintln!("skipping {}: {}", S::NAME, e);
+            return Ok(Vec::new());
+        }
+    };
+    let key = SecretKey::generate(algorithm);
+    let ((signature, state), sign_time, sign_peak_memory) = measure(|| scheme.sign(&key, video));
+
+    let mut results = Vec::new();
+    for &length in segment_lengths {
+        let start = (shape.num_frames - length) / 2;
+        let kept = start..start + length;
+
+        let (proof, trim_time, trim_peak_memory) = measure(|| scheme.redact(&state, kept.clone()));
+        let proof = proof
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}: {}", S::NAME, e)))?;
+
+        let published = &video[kept.start * shape.frame_len..kept.end * shape.frame_len];
+        let (verified, verify_time, verify_peak_memory) = measure(|| {
+            scheme.verify(
+                &key.public_key(),
+                &signature,
+                kept.clone(),
+                &proof,
+                published,
+            )
+        });
+        verified
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}: {}", S::NAME, e)))?;
+
+        let result = BenchmarkResult {
+            backend: S::NAME,
+            algorithm,
+            num_frames: shape.num_frames,
+            frame_len: shape.frame_len,
+            segment_frames: length,
+            sign_time,
+            sign_peak_memory,
+            trim_time,
+            trim_peak_memory,
+            verify_time,
+            verify_peak_memory,
+            proof_size: S::proof_size(&proof),
+        };
+        println!("{}", result.csv_row());
+        results.push(result);
+    }
+    Ok(results)
+}
+
+fn main() -> io::Result<()> {
+    let opt = Opt::parse();
+
+    let source = match &opt.pnm_dir {
+        Some(dir) => VideoSource::from_pnm_dir(dir)?,
+        None => VideoSource::from_matrix_dir(&opt.frames_dir)?,
+    };
+    let num_frames = opt.num_frames.min(source.num_frames());
+    let source = source.slice(0..num_frames);
+    let video = source.read_all()?;
+    let shape = VideoShape {
+        frame_len: source.frame_len(),
+        num_frames,
+    };
+
+    if let Some(length) = opt
+        .segment_lengths
+        .iter()
+        .find(|&&length| length == 0 || length > num_frames)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "segment length {} is not between 1 and {}",
+                length, num_frames
+            ),
+        ));
+    }
+
+    println!("{}", BenchmarkResult::CSV_HEADER);
+    let mut results = Vec::new();
+    for backend in &opt.backends {
+        let backend_results = match backend.as_str() {
+            "merkle" => run::<MerkleScheme>(&video, shape, opt.algorithm, &opt.segment_lengths)?,
+            "fri" => run::<FriScheme>(&video, shape, opt.algorithm, &opt.segment_lengths)?,
+            "ligero" => run::<LigeroScheme>(&video, shape, opt.algorithm, &opt.segment_lengths)?,
+            "sumcheck" => {
+                run::<SumcheckScheme>(&video, shape, opt.algorithm, &opt.segment_lengths)?
+            }
+            "sumcheck-subcubes" => {
+                run::<SubcubeScheme>(&video, shape, opt.algorithm, &opt.segment_lengths)?
+            }
+            "basefold-blocks" => {
+                run::<BasefoldBlocksScheme>(&video, shape, opt.algorithm, &opt.segment_lengths)?
+            }
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "unknown backend {}, expected one of {}",
+                        other,
+                        BACKENDS.join(", ")
+                    ),
+                ))
+            }
+        };
+        results.extend(backend_results);
+    }
+
+    if let Some(path) = &opt.json {
+        let json = serde_json::to_string_pretty(&results)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        fs::write(path, json)?;
+    }
+    if let Some(path) = &opt.csv {
+        let mut csv = BenchmarkResult::CSV_HEADER.to_string();
+        for result in &results {
+            csv.push('\n');
+            csv.push_str(&result.csv_row());
+        }
+        csv.push('\n');
+        fs::write(path, csv)?;
+    }
+    Ok(())
+}
@@ -1,144 +1,50 @@
-use std::collections::BTreeMap;
-use std::fs;
-use std::io::{self, BufRead};
-use std::marker::PhantomData;
-use std::path::Path;
+use clap::Parser;
 
-use ark_std::log2;
-use either::Either;
-use itertools::{izip, Itertools};
-use multilinear_extensions::{
-    mle::{ArcMultilinearExtension, Point},
-    monomial::Term,
-    virtual_poly::{build_eq_x_r, VPAuxInfo, VirtualPolynomial},
-    virtual_polys::VirtualPolynomials,
-};
-use p3_matrix_git::dense::RowMajorMatrix as P3RowMajorMatrix;
-// use rand::distributions::{Distribution, Standard};
-use rand::{Rng, RngCore, SeedableRng};
-use rand_chacha::ChaCha20Rng;
-use sumcheck::structs::{IOPProverState, IOPVerifierState};
-use witness::{InstancePaddingStrategy, RowMajorMatrix};
+use std::io;
+use std::path::PathBuf;
 
-use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
-use rand::rngs::OsRng;
-use std::time::Duration;
 use std::time::Instant;
 
-use ff_ext::GoldilocksExt2;
-use mpcs::{
-    test_util::{
-        commit_polys_individually, get_point_from_challenge, get_points_from_challenge, setup_pcs,
-    },
-    Basefold, BasefoldRSParams, Evaluation, PolynomialCommitmentScheme, SecurityLevel,
-};
-use p3_field_git::{extension::BinomialExtensionField, PrimeCharacteristicRing};
-use p3_goldilocks_git::Goldilocks as CenoGoldilocks;
-use transcript::{BasicTranscript, Transcript};
-use video_trimming::{compute_video_mle_evaluations_vec, to_binary_vec};
-
-type PcsGoldilocksRSCode = Basefold<GoldilocksExt2, BasefoldRSParams>;
-// type PcsGoldilocksBasecode = Basefold<GoldilocksExt2, BasefoldBasecodeParams>;
-type T = BasicTranscript<GoldilocksExt2>;
-type E = GoldilocksExt2;
-type Pcs = PcsGoldilocksRSCode;
-
-fn parse_filename(file_name: &str) -> Option<(u32, char)> {
-    let parts: Vec<&str> = file_name.trim_end_matches(".txt").split('_').collect();
-    if parts.len() == 3 {
-        if let Ok(frame_number) = parts[1].parse::<u32>() {
-            if let Some(channel) = parts[2].chars().next() {
-                return Some((frame_number, channel));
-            }
-        }
-    }
-    None
-}
-
-fn read_file_as_vec<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
-    let file = fs::File::open(path)?;
-    let reader = io::BufReader::new(file);
-    let mut values = Vec::new();
-
-    for line in reader.lines() {
-        let line = line?;
-        values.extend(
-            line.split_whitespace()
-                .filter_map(|num| num.parse::<u8>().ok()),
-        );
-    }
-
-    Ok(values)
-}
-
-fn seeded_rng() -> impl Rng {
-    ChaCha20Rng::seed_from_u64(18)
+use video_trimming::frames::VideoSource;
+use video_trimming::packing::{CenoGoldilocksPacker, Packer};
+use video_trimming::redact::multilinear::MultilinearSetup;
+use video_trimming::redact::VideoLayout;
+
+#[derive(Parser, Debug)]
+#[command(about = "Sumcheck based interval redaction benchmark", long_about = None)]
+struct Opt {
+    /// Directory of `<prefix>_<frame>_<B|G|R>.txt` matrix files to sign.
+    #[arg(
+        long,
+        default_value = "../noir-video-editing/video_decompose_script/outputs/matrix_files"
+    )]
+    frames_dir: PathBuf,
+
+    /// Number of frames of the video to sign (10s at 25 fps by default).
+    #[arg(long, default_value_t = 250)]
+    num_frames: usize,
 }
 
 fn main() -> io::Result<()> {
+    let opt = Opt::parse();
     let file_io_start = Instant::now();
-    // Define image vector/properties.
-    let frame_size: usize = (720 * 1280);
-    let num_frames = 25 * 10; // 10 frames at 25 fps
-    let video_size = num_frames * frame_size / 2;
-    let num_vars = log2(video_size) as usize; // goldilocks can store 2 pixels per felt!
-    let poly_size = 1 << num_vars;
-
-    // Python style indexing where end isn't inclusive. Also FFMPEG decomposes frames in a
-    // 1-indexed way.
-    let segment_start: usize = 2 - 1;
-    let segment_end: usize = 240 - 1;
 
     // Read full video for the signature part.
-    let dir_path = "../noir-video-editing/video_decompose_script/outputs/matrix_files";
-    let mut signer_frames: BTreeMap<u32, Vec<Vec<u8>>> = BTreeMap::new();
-
-    let entries = fs::read_dir(dir_path)?;
-
-    for entry in entries {
-        let entry = entry?;
-        let file_name = entry.file_name().into_string().unwrap();
-
-        if let Some((frame_number, channel)) = parse_filename(&file_name) {
-            if (frame_number as usize) <= num_frames && (frame_number as usize) > 0 {
-                let file_path = entry.path();
-                let content = read_file_as_vec(&file_path)?;
+    let source = VideoSource::from_matrix_dir(&opt.frames_dir)?;
+    let num_frames = opt.num_frames.min(source.num_frames());
+    let source = source.slice(0..num_frames);
 
-                let frame = signer_frames
-                    .entry(frame_number)
-                    .or_insert_with(|| vec![vec![], vec![], vec![]]);
-                match channel {
-                    'B' => frame[0] = content,
-                    'G' => frame[1] = content,
-                    'R' => frame[2] = content,
-                    _ => (),
-                }
-            }
-        }
-    }
+    // Python style indexing where end isn't inclusive, over 0-based frame indices.
+    let segment_start: usize = 2 - 1;
+    let segment_end: usize = 240 - 1;
 
-    // Note: BTreeMap is already sorted by frame count.
-    let sorted_frames = signer_frames
-        .into_values()
-        .flatten()
-        .flatten()
-        .collect::<Vec<_>>();
-    let mut video_evaluations = sorted_frames
-        .chunks_exact(6)
-        .map(|chunk| {
-            (chunk[0] as u64)
-                | ((chunk[1] as u64) << 8)
-                | ((chunk[2] as u64) << 16)
-                | ((chunk[3] as u64) << 24)
-                | ((chunk[4] as u64) << 32)
-                | ((chunk[5] as u64) << 40)
-        })
-        .map(|x| CenoGoldilocks::from_u64(x))
-        .collect::<Vec<CenoGoldilocks>>();
-    let mut pixel_rmm_inner = P3RowMajorMatrix::new_col(video_evaluations.clone());
-    let mut pixel_rmm =
-        RowMajorMatrix::new_by_inner_matrix(pixel_rmm_inner, InstancePaddingStrategy::Default);
-    let rmms = BTreeMap::from([(0, pixel_rmm.clone())]);
+    // goldilocks can store 2 pixels per felt!
+    let packer = CenoGoldilocksPacker::new(6, true);
+    let layout = VideoLayout {
+        frame_size: packer.elements_per_frame(source.frame_len()),
+        num_frames,
+    };
+    let video_evaluations = packer.pack_source(&source)?;
 
     let file_io_duration = file_io_start.elapsed();
     println!(
@@ -146,174 +52,26 @@ fn main() -> io::Result<()> {
         file_io_duration
     );
 
-    let (pp, vp) = {
-        let param = Pcs::setup(poly_size, SecurityLevel::default()).unwrap();
-        Pcs::trim(param, poly_size).unwrap()
-    };
+    let setup = MultilinearSetup::new(layout);
 
     let commit_start = Instant::now();
-    let poly: ArcMultilinearExtension<_> = pixel_rmm.to_mles::<E>().remove(0).into();
-    let mut pcs_transcript = T::new(b"BaseFold");
-
-    let comm = Pcs::batch_commit_and_write(&pp, rmms, &mut pcs_transcript).unwrap();
+    let committed = setup.commit_video(&video_evaluations);
     let commit_duration = commit_start.elapsed();
     println!("Gen commitment took: {:?}", commit_duration);
 
     let opening_proof_start = Instant::now();
-
-    let mut point = get_point_from_challenge(num_vars, &mut pcs_transcript);
-    let eq_x_r = build_eq_x_r(&point);
-
-    let start_index = segment_start * (frame_size / 2);
-    let end_index = segment_end * (frame_size / 2);
-    let mask_poly_vec = (0..video_size)
-        .map(|i| {
-            if i >= start_index && i < end_index {
-                CenoGoldilocks::ONE
-            } else {
-                CenoGoldilocks::ZERO
-            }
-        })
-        .collect::<Vec<_>>();
-    let mut mask_rmm_inner = P3RowMajorMatrix::new_col(mask_poly_vec);
-    let mut mask_rmm =
-        RowMajorMatrix::new_by_inner_matrix(mask_rmm_inner, InstancePaddingStrategy::Default);
-    let mask_poly: ArcMultilinearExtension<_> = mask_rmm.to_mles::<E>().remove(0).into();
-
-    // We are sumchecking (m(x)*v(x) - r(x))*eq(r,x) = 0.
-    // What they call virtual polys is really just a sum of different products of multilinear polynomials.
-    let revealed_poly_vec = (0..video_size)
-        .map(|i| {
-            if i >= start_index && i < end_index {
-                CenoGoldilocks::from(video_evaluations[i])
-            } else {
-                CenoGoldilocks::ZERO
-            }
-        })
-        .collect::<Vec<_>>();
-    let mut revealed_rmm_inner = P3RowMajorMatrix::new_col(revealed_poly_vec);
-    let mut revealed_rmm =
-        RowMajorMatrix::new_by_inner_matrix(revealed_rmm_inner, InstancePaddingStrategy::Default);
-    let revealed_poly: ArcMultilinearExtension<_> = revealed_rmm.to_mles::<E>().remove(0).into();
-    // let sumcheck_vp_2 =
-    //     VirtualPolynomial::<E>::new_from_product(vec![revealed_poly, eq_x_r], -E::ONE);
-    let sumcheck_poly = VirtualPolynomials::<E>::new_from_monimials(
-        8,
-        num_vars,
-        vec![
-            Term {
-                scalar: either::Right(E::ONE),
-                product: vec![
-                    either::Left(&poly),
-                    either::Left(&mask_poly),
-                    either::Left(&eq_x_r),
-                ],
-            },
-            Term {
-                scalar: either::Right(-E::ONE),
-                product: vec![either::Left(&revealed_poly), either::Left(&eq_x_r)],
-            },
-        ],
-    );
-
-    let num_instances = vec![(0, 1 << num_vars)];
-    let circuit_num_polys = vec![(1, 0)]; // batch size of 1 at the moment
-    let mut sumcheck_transcript = T::new(b"BaseFold");
-    let (proof, _) = IOPProverState::<E>::prove(sumcheck_poly.as_view(), &mut sumcheck_transcript);
-    let eval = poly.evaluate(&proof.point);
-
-    let pcs_proof = Pcs::batch_open(
-        &pp,
-        &num_instances,
-        None,
-        &comm,
-        &[proof.point.clone()], // as vec
-        &[vec![eval.clone()]],  // as vec
-        &circuit_num_polys,
-        &mut pcs_transcript,
-    )
-    .unwrap();
-
+    let proof = setup
+        .prove_interval(&committed, segment_start, segment_end)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
     let opening_proof_duration = opening_proof_start.elapsed();
     println!("Gen opening proof took: {:?}", opening_proof_duration);
 
     // Verify
+    let published = packer.pack_source(&source.slice(segment_start..segment_end))?;
     let verify_start = Instant::now();
-    let mut pcs_transcript = T::new(b"BaseFold");
-    let comm = Pcs::get_pure_commitment(&comm);
-    Pcs::write_commitment(&comm, &mut pcs_transcript).unwrap();
-    let mut point = get_point_from_challenge(num_vars, &mut pcs_transcript);
-
-    let mut sumcheck_transcript = T::new(b"BaseFold");
-    let subclaim = IOPVerifierState::<E>::verify(
-        E::ZERO,
-        &proof,
-        &VPAuxInfo {
-            max_degree: 3,
-            max_num_variables: num_vars,
-            ..Default::default()
-        },
-        &mut sumcheck_transcript,
-    );
-    let sumcheck_r: Point<E> = subclaim
-        .point
-        .iter()
-        .map(|c| c.elements)
-        .collect::<Vec<_>>();
-
-    Pcs::batch_verify(
-        &vp,
-        &num_instances,
-        &[sumcheck_r.clone()],
-        None,
-        &comm,
-        &[vec![eval]],
-        &pcs_proof,
-        &circuit_num_polys,
-        &mut pcs_transcript,
-    );
-
-    let eq_x_r = build_eq_x_r(&point);
-    let eq_x_r_eval = eq_x_r.evaluate(&sumcheck_r);
-
-    let start_index = segment_start * (frame_size / 2);
-    let end_index = segment_end * (frame_size / 2);
-    let mask_poly_vec = (0..video_size)
-        .map(|i| {
-            if i >= start_index && i < end_index {
-                CenoGoldilocks::ONE
-            } else {
-                CenoGoldilocks::ZERO
-            }
-        })
-        .collect::<Vec<_>>();
-    let mut mask_rmm_inner = P3RowMajorMatrix::new_col(mask_poly_vec);
-    let mut mask_rmm =
-        RowMajorMatrix::new_by_inner_matrix(mask_rmm_inner, InstancePaddingStrategy::Default);
-    let mask_poly: ArcMultilinearExtension<_> = mask_rmm.to_mles::<E>().remove(0).into();
-    let mask_poly_eval = mask_poly.evaluate(&sumcheck_r);
-
-    let revealed_poly_vec = (0..video_size)
-        .map(|i| {
-            if i >= start_index && i < end_index {
-                CenoGoldilocks::from(video_evaluations[i])
-            } else {
-                CenoGoldilocks::ZERO
-            }
-        })
-        .collect::<Vec<_>>();
-    let mut revealed_rmm_inner = P3RowMajorMatrix::new_col(revealed_poly_vec);
-    let mut revealed_rmm =
-        RowMajorMatrix::new_by_inner_matrix(revealed_rmm_inner, InstancePaddingStrategy::Default);
-    let revealed_poly: ArcMultilinearExtension<_> = revealed_rmm.to_mles::<E>().remove(0).into();
-    let revealed_poly_eval = revealed_poly.evaluate(&sumcheck_r);
-
-    // println!(
-    //     "Inferred eval {:?}",
-    //     eval * mask_poly_eval * eq_x_r_eval - revealed_poly_eval * eq_x_r_eval
-    // );
-
+    let result = setup.verify_interval(&committed.commitment(), &proof, &published);
     let verify_duration = verify_start.elapsed();
     println!("Verification of opening took: {:?}", verify_duration);
-    Ok(())
+
+    result.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
 }
@@ -0,0 +1,673 @@
+// Frame ingestion shared by the trimming binaries.
+//
+// A `VideoSource` describes the geometry of a video (width, height, channel layout, frame count)
+// and hands out frames one at a time, so callers never need to know whether the frames came from
+// the whitespace-separated matrix files produced by `png_to_matrix.py`, a raw planar dump or a
+// PPM/PGM sequence. Frames are always returned in planar layout: every channel plane is stored
+// row-major and the planes follow each other in `ChannelOrder` order.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelOrder {
+    /// Three full-resolution planes in B, G, R order (what the matrix files decompose into).
+    Bgr,
+    /// Three full-resolution planes in R, G, B order.
+    Rgb,
+    /// A full-resolution Y plane followed by quarter-resolution U and V planes.
+    Yuv420,
+    /// A single full-resolution plane.
+    Gray,
+}
+
+impl ChannelOrder {
+    /// Number of bytes one frame of `width` x `height` occupies in this layout.
+    pub fn frame_len(self, width: usize, height: usize) -> usize {
+        match self {
+            ChannelOrder::Bgr | ChannelOrder::Rgb => 3 * width * height,
+            ChannelOrder::Yuv420 => width * height + 2 * width.div_ceil(2) * height.div_ceil(2),
+            ChannelOrder::Gray => width * height,
+        }
+    }
+
+    /// Number of planes, if every plane has the full frame resolution.
+    pub fn full_resolution_planes(self) -> Option<usize> {
+        match self {
+            ChannelOrder::Bgr | ChannelOrder::Rgb => Some(3),
+            ChannelOrder::Yuv420 => None,
+            ChannelOrder::Gray => Some(1),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Backing {
+    // One file per channel of each frame, already in `ChannelOrder::Bgr` order.
+    MatrixFiles(Vec<[PathBuf; 3]>),
+    RawPlanar(PathBuf),
+    Pnm(Vec<PathBuf>),
+    Memory(Vec<Vec<u8>>),
+}
+
+#[derive(Clone, Debug)]
+pub struct VideoSource {
+    width: usize,
+    height: usize,
+    channel_order: ChannelOrder,
+    backing: Backing,
+    // Window of backing frames exposed by this source.
+    frames: Range<usize>,
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Matrix files are named `<prefix>_<frame>_<channel>.txt`, e.g. `output_0001_R.txt`.
+fn parse_filename(file_name: &str) -> Option<(u32, char)> {
+    let parts: Vec<&str> = file_name.trim_end_matches(".txt").split('_').collect();
+    if parts.len() == 3 {
+        if let Ok(frame_number) = parts[1].parse::<u32>() {
+            if let Some(channel) = parts[2].chars().next() {
+                return Some((frame_number, channel));
+            }
+        }
+    }
+    None
+}
+
+// Reads a whitespace separated matrix, returning its values and its (width, height).
+fn read_matrix_file<P: AsRef<Path>>(path: P) -> io::Result<(Vec<u8>, usize, usize)> {
+    let file = fs::File::open(path)?;
+    let reader = io::BufReader::new(file);
+    let mut values = Vec::new();
+    let mut width = 0;
+    let mut height = 0;
+
+    for line in reader.lines() {
+        let line = line?;
+        let before = values.len();
+        values.extend(
+            line.split_whitespace()
+                .filter_map(|num| num.parse::<u8>().ok()),
+        );
+        let row_len = values.len() - before;
+        if row_len > 0 {
+            width = row_len;
+            height += 1;
+        }
+    }
+
+    Ok((values, width, height))
+}
+
+// Trailing decimal digits of a file stem, used to order frame sequences like `frame_12.ppm`.
+fn trailing_number(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    let digits_start = stem
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    stem[digits_start..].parse().ok()
+}
+
+// Parses a binary PPM (P6) or PGM (P5) image, returning (width, height, channels, samples).
+fn read_pnm<P: AsRef<Path>>(path: P) -> io::Result<(usize, usize, usize, Vec<u8>)> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+
+    // The header is four whitespace separated tokens; `#` starts a comment running to the end of
+    // the line. Exactly one whitespace byte separates the last token from the raster.
+    let mut tokens = Vec::with_capacity(4);
+    let mut pos = 0;
+    while tokens.len() < 4 {
+        while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+            if bytes[pos] == b'#' {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            } else {
+                pos += 1;
+            }
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(invalid_data(format!(
+                "{}: truncated header",
+                path.display()
+            )));
+        }
+        tokens.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+    }
+    pos += 1;
+
+    let channels = match tokens[0].as_str() {
+        "P5" => 1,
+        "P6" => 3,
+        magic => {
+            return Err(invalid_data(format!(
+                "{}: unsupported magic {:?}, only binary P5/P6 are supported",
+                path.display(),
+                magic
+            )))
+        }
+    };
+    let parse = |token: &str| {
+        token
+            .parse::<usize>()
+            .map_err(|_| invalid_data(format!("{}: bad header field {:?}", path.display(), token)))
+    };
+    let width = parse(&tokens[1])?;
+    let height = parse(&tokens[2])?;
+    let max_value = parse(&tokens[3])?;
+    if max_value == 0 {
+        return Err(invalid_data(format!(
+            "{}: maxval must be positive",
+            path.display()
+        )));
+    }
+    if max_value > 255 {
+        return Err(invalid_data(format!(
+            "{}: 16-bit samples are not supported",
+            path.display()
+        )));
+    }
+
+    let raster_len = width * height * channels;
+    if bytes.len() < pos + raster_len {
+        return Err(invalid_data(format!(
+            "{}: truncated raster",
+            path.display()
+        )));
+    }
+    // Samples are signed as they are, not rescaled, so a smaller maxval is fine as long as the
+    // raster respects it.
+    let samples = bytes[pos..pos + raster_len].to_vec();
+    if samples.iter().any(|&sample| sample as usize > max_value) {
+        return Err(invalid_data(format!(
+            "{}: sample above maxval {}",
+            path.display(),
+            max_value
+        )));
+    }
+    Ok((width, height, channels, samples))
+}
+
+impl VideoSource {
+    /// Wraps frames that are already in memory. Every frame must be `channel_order.frame_len`
+    /// bytes long.
+    pub fn from_frames(
+        width: usize,
+        height: usize,
+        channel_order: ChannelOrder,
+        frames: Vec<Vec<u8>>,
+    ) -> io::Result<Self> {
+        let frame_len = channel_order.frame_len(width, height);
+        if let Some(i) = frames.iter().position(|f| f.len() != frame_len) {
+            return Err(invalid_data(format!(
+                "frame {} has {} bytes, expected {}",
+                i,
+                frames[i].len(),
+                frame_len
+            )));
+        }
+        let num_frames = frames.len();
+        Ok(Self {
+            width,
+            height,
+            channel_order,
+            backing: Backing::Memory(frames),
+            frames: 0..num_frames,
+        })
+    }
+
+    /// Opens a directory of `<prefix>_<frame>_<B|G|R>.txt` matrix files. Frames are ordered by
+    /// frame number and the geometry is taken from the first matrix.
+    pub fn from_matrix_dir<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut channel_files: BTreeMap<u32, [Option<PathBuf>; 3]> = BTreeMap::new();
+        for entry in fs::read_dir(dir.as_ref())? {
+            let entry = entry?;
+            let file_name = entry.file_name().into_string().unwrap_or_default();
+
+            if let Some((frame_number, channel)) = parse_filename(&file_name) {
+                let plane = match channel {
+                    'B' => 0,
+                    'G' => 1,
+                    'R' => 2,
+                    _ => continue,
+                };
+                channel_files.entry(frame_number).or_default()[plane] = Some(entry.path());
+            }
+        }
+
+        let mut frames = Vec::with_capacity(channel_files.len());
+        for (frame_number, [b, g, r]) in channel_files {
+            match (b, g, r) {
+                (Some(b), Some(g), Some(r)) => frames.push([b, g, r]),
+                _ => {
+                    return Err(invalid_data(format!(
+                        "frame {} is missing a colour channel",
+                        frame_number
+                    )))
+                }
+            }
+        }
+        let (width, height) = match frames.first() {
+            Some([b, _, _]) => {
+                let (_, width, height) = read_matrix_file(b)?;
+                (width, height)
+            }
+            None => {
+                return Err(invalid_data(format!(
+                    "no matrix files found in {}",
+                    dir.as_ref().display()
+                )))
+            }
+        };
+
+        let num_frames = frames.len();
+        Ok(Self {
+            width,
+            height,
+            channel_order: ChannelOrder::Bgr,
+            backing: Backing::MatrixFiles(frames),
+            frames: 0..num_frames,
+        })
+    }
+
+    /// Opens a raw dump of back-to-back planar frames (e.g. `ffmpeg -pix_fmt yuv420p -f rawvideo`
+    /// or `-pix_fmt gbrp`). The frame count is inferred from the file size.
+    pub fn from_raw_planar<P: AsRef<Path>>(
+        path: P,
+        width: usize,
+        height: usize,
+        channel_order: ChannelOrder,
+    ) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file_len = fs::metadata(&path)?.len() as usize;
+        let frame_len = channel_order.frame_len(width, height);
+        if frame_len == 0 || file_len % frame_len != 0 {
+            return Err(invalid_data(format!(
+                "{} is {} bytes, which is not a whole number of {}-byte frames",
+                path.display(),
+                file_len,
+                frame_len
+            )));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            channel_order,
+            backing: Backing::RawPlanar(path),
+            frames: 0..file_len / frame_len,
+        })
+    }
+
+    /// Opens every `.ppm`/`.pgm` file in `dir`, ordered by the number at the end of the file
+    /// name. PPM frames are de-interleaved into planar RGB.
+    pub fn from_pnm_dir<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir.as_ref())? {
+            let path = entry?.path();
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("ppm") | Some("pgm") => paths.push(path),
+                _ => (),
+            }
+        }
+        paths.sort_by(|a, b| (trailing_number(a), a).cmp(&(trailing_number(b), b)));
+        Self::from_pnm_files(paths)
+    }
+
+    /// Opens an explicit, already ordered list of PPM/PGM frames.
+    pub fn from_pnm_files(paths: Vec<PathBuf>) -> io::Result<Self> {
+        let first = paths
+            .first()
+            .ok_or_else(|| invalid_data("empty PPM/PGM sequence".to_string()))?;
+        let (width, height, channels, _) = read_pnm(first)?;
+        let channel_order = if channels == 3 {
+            ChannelOrder::Rgb
+        } else {
+            ChannelOrder::Gray
+        };
+
+        let num_frames = paths.len();
+        Ok(Self {
+            width,
+            height,
+            channel_order,
+            backing: Backing::Pnm(paths),
+            frames: 0..num_frames,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn channel_order(&self) -> ChannelOrder {
+        self.channel_order
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Bytes per frame.
+    pub fn frame_len(&self) -> usize {
+        self.channel_order.frame_len(self.width, self.height)
+    }
+
+    /// A source restricted to frames `range` (relative to this source).
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        assert!(
+            range.start <= range.end && range.end <= self.num_frames(),
+            "frame range {:?} out of bounds for {} frames",
+            range,
+            self.num_frames()
+        );
+        Self {
+            frames: self.frames.start + range.start..self.frames.start + range.end,
+            ..self.clone()
+        }
+    }
+
+    /// Reads frame `index` (relative to this source) in planar layout.
+    pub fn frame(&self, index: usize) -> io::Result<Vec<u8>> {
+        assert!(index < self.num_frames(), "frame {} out of bounds", index);
+        let index = self.frames.start + index;
+        let frame_len = self.frame_len();
+
+        let frame = match &self.backing {
+            Backing::Memory(frames) => frames[index].clone(),
+            Backing::MatrixFiles(frames) => {
+                let mut frame = Vec::with_capacity(frame_len);
+                for path in &frames[index] {
+                    frame.extend(read_matrix_file(path)?.0);
+                }
+                frame
+            }
+            Backing::RawPlanar(path) => {
+                let mut file = fs::File::open(path)?;
+                file.seek(SeekFrom::Start((index * frame_len) as u64))?;
+                let mut frame = vec![0u8; frame_len];
+                file.read_exact(&mut frame)?;
+                frame
+            }
+            Backing::Pnm(paths) => {
+                let (width, height, channels, samples) = read_pnm(&paths[index])?;
+                if (width, height) != (self.width, self.height) {
+                    return Err(invalid_data(format!(
+                        "{} is {}x{}, expected {}x{}",
+                        paths[index].display(),
+                        width,
+                        height,
+                        self.width,
+                        self.height
+                    )));
+                }
+                // De-interleave RGBRGB... into three planes.
+                let mut frame = Vec::with_capacity(samples.len());
+                for c in 0..channels {
+                    frame.extend(samples.iter().skip(c).step_by(channels));
+                }
+                frame
+            }
+        };
+
+        if frame.len() != frame_len {
+            return Err(invalid_data(format!(
+                "frame {} has {} bytes, expected {}",
+                index,
+                frame.len(),
+                frame_len
+            )));
+        }
+        Ok(frame)
+    }
+
+    /// Iterates over the frames of this source, reading each one lazily.
+    pub fn frames(&self) -> impl Iterator<Item = io::Result<Vec<u8>>> + '_ {
+        (0..self.num_frames()).map(move |i| self.frame(i))
+    }
+
+    /// Reads every frame and concatenates them, in frame order.
+    pub fn read_all(&self) -> io::Result<Vec<u8>> {
+        let mut video = Vec::with_capacity(self.num_frames() * self.frame_len());
+        for frame in self.frames() {
+            video.extend(frame?);
+        }
+        Ok(video)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory for one test's fixtures; removed again by the test.
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "video-trimming-frames-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn pnm(magic: &str, width: usize, height: usize, max_value: usize, raster: &[u8]) -> Vec<u8> {
+        let mut bytes = format!(
+            "{}\n# comment\n{} {}\n{}\n",
+            magic, width, height, max_value
+        )
+        .into_bytes();
+        bytes.extend_from_slice(raster);
+        bytes
+    }
+
+    fn write_matrix(path: &Path, rows: &[&[u8]]) {
+        let text: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        fs::write(path, text.join("\n")).unwrap();
+    }
+
+    #[test]
+    fn frame_len_matches_layout() {
+        assert_eq!(ChannelOrder::Rgb.frame_len(4, 2), 24);
+        assert_eq!(ChannelOrder::Gray.frame_len(4, 2), 8);
+        // Chroma planes round odd dimensions up.
+        assert_eq!(ChannelOrder::Yuv420.frame_len(3, 3), 9 + 2 * 4);
+    }
+
+    #[test]
+    fn memory_frames_slice_and_read() {
+        let frames: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 6]).collect();
+        let source = VideoSource::from_frames(2, 1, ChannelOrder::Rgb, frames).unwrap();
+        assert_eq!(source.num_frames(), 5);
+        assert_eq!(source.frame(3).unwrap(), vec![3; 6]);
+
+        let sliced = source.slice(1..4).slice(1..3);
+        assert_eq!(sliced.num_frames(), 2);
+        assert_eq!(sliced.frame(0).unwrap(), vec![2; 6]);
+        assert_eq!(
+            sliced.read_all().unwrap(),
+            [vec![2; 6], vec![3; 6]].concat()
+        );
+        assert_eq!(source.slice(2..2).num_frames(), 0);
+    }
+
+    #[test]
+    fn mismatched_memory_frame_is_rejected() {
+        let frames = vec![vec![0; 6], vec![0; 5]];
+        assert!(VideoSource::from_frames(2, 1, ChannelOrder::Rgb, frames).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_out_of_bounds_panics() {
+        let source = VideoSource::from_frames(1, 1, ChannelOrder::Gray, vec![vec![0]; 3]).unwrap();
+        source.slice(1..4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn frame_out_of_bounds_panics() {
+        let source = VideoSource::from_frames(1, 1, ChannelOrder::Gray, vec![vec![0]; 3]).unwrap();
+        let _ = source.slice(1..3).frame(2);
+    }
+
+    #[test]
+    fn pnm_frames_are_ordered_and_planar() {
+        let dir = fixture_dir("pnm");
+        // Written out of order; frame_10 must sort after frame_2.
+        fs::write(
+            dir.join("frame_10.ppm"),
+            pnm("P6", 2, 1, 255, &[1, 2, 3, 4, 5, 6]),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("frame_2.ppm"),
+            pnm("P6", 2, 1, 255, &[7, 8, 9, 10, 11, 12]),
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let source = VideoSource::from_pnm_dir(&dir).unwrap();
+        assert_eq!(source.channel_order(), ChannelOrder::Rgb);
+        assert_eq!((source.width(), source.height()), (2, 1));
+        assert_eq!(source.frame(0).unwrap(), vec![7, 10, 8, 11, 9, 12]);
+        assert_eq!(source.frame(1).unwrap(), vec![1, 4, 2, 5, 3, 6]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pgm_with_smaller_maxval_is_read_unscaled() {
+        let dir = fixture_dir("pgm-maxval");
+        let path = dir.join("frame_0.pgm");
+        fs::write(&path, pnm("P5", 2, 2, 15, &[0, 5, 10, 15])).unwrap();
+        let source = VideoSource::from_pnm_files(vec![path.clone()]).unwrap();
+        assert_eq!(source.channel_order(), ChannelOrder::Gray);
+        assert_eq!(source.frame(0).unwrap(), vec![0, 5, 10, 15]);
+
+        fs::write(&path, pnm("P5", 2, 2, 15, &[0, 5, 16, 15])).unwrap();
+        assert!(source.frame(0).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn malformed_pnm_headers_are_rejected() {
+        let dir = fixture_dir("pnm-malformed");
+        let cases: [(&str, Vec<u8>); 7] = [
+            ("ascii", pnm("P3", 1, 1, 255, b"0 0 0")),
+            ("sixteen_bit", pnm("P5", 1, 1, 65535, &[0, 0])),
+            ("zero_maxval", pnm("P5", 1, 1, 0, &[0])),
+            ("bad_width", b"P5 x 1 255 \0".to_vec()),
+            ("truncated_header", b"P5 1 1".to_vec()),
+            ("truncated_raster", pnm("P6", 2, 2, 255, &[0; 11])),
+            ("empty", Vec::new()),
+        ];
+        for (name, bytes) in cases {
+            let path = dir.join(format!("{}.pgm", name));
+            fs::write(&path, bytes).unwrap();
+            assert!(
+                VideoSource::from_pnm_files(vec![path]).is_err(),
+                "{} was accepted",
+                name
+            );
+        }
+        assert!(VideoSource::from_pnm_files(Vec::new()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pnm_frame_of_another_size_is_rejected() {
+        let dir = fixture_dir("pnm-sizes");
+        let first = dir.join("frame_0.pgm");
+        let second = dir.join("frame_1.pgm");
+        fs::write(&first, pnm("P5", 2, 1, 255, &[1, 2])).unwrap();
+        fs::write(&second, pnm("P5", 1, 2, 255, &[3, 4])).unwrap();
+        let source = VideoSource::from_pnm_files(vec![first, second]).unwrap();
+        assert!(source.frame(0).is_ok());
+        assert!(source.frame(1).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn matrix_dir_is_read_in_bgr_order() {
+        let dir = fixture_dir("matrix");
+        for (frame, base) in [(1u8, 10u8), (0, 0)] {
+            for (channel, offset) in [('B', 0u8), ('G', 1), ('R', 2)] {
+                let v = base + 3 * offset;
+                write_matrix(
+                    &dir.join(format!("output_{:04}_{}.txt", frame, channel)),
+                    &[&[v, v + 1], &[v + 2, v]],
+                );
+            }
+        }
+        let source = VideoSource::from_matrix_dir(&dir).unwrap();
+        assert_eq!(source.channel_order(), ChannelOrder::Bgr);
+        assert_eq!(
+            (source.width(), source.height(), source.num_frames()),
+            (2, 2, 2)
+        );
+        assert_eq!(
+            source.frame(0).unwrap(),
+            vec![0, 1, 2, 0, 3, 4, 5, 3, 6, 7, 8, 6]
+        );
+        assert_eq!(source.frame(1).unwrap()[0], 10);
+
+        // A channel matrix of another size makes the frame the wrong length.
+        write_matrix(&dir.join("output_0001_G.txt"), &[&[1, 2, 3]]);
+        assert!(source.frame(1).is_err());
+
+        fs::remove_file(dir.join("output_0001_R.txt")).unwrap();
+        assert!(VideoSource::from_matrix_dir(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn empty_matrix_dir_is_rejected() {
+        let dir = fixture_dir("matrix-empty");
+        assert!(VideoSource::from_matrix_dir(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn raw_planar_frames_are_read_by_offset() {
+        let dir = fixture_dir("raw");
+        let path = dir.join("video.yuv");
+        // 4x2 yuv420p frames are 8 + 2 * 2 = 12 bytes.
+        let video: Vec<u8> = (0..36).collect();
+        fs::write(&path, &video).unwrap();
+        let source = VideoSource::from_raw_planar(&path, 4, 2, ChannelOrder::Yuv420).unwrap();
+        assert_eq!(source.num_frames(), 3);
+        assert_eq!(source.frame(1).unwrap(), video[12..24].to_vec());
+        assert_eq!(source.slice(2..3).read_all().unwrap(), video[24..].to_vec());
+
+        fs::write(&path, &video[..35]).unwrap();
+        assert!(VideoSource::from_raw_planar(&path, 4, 2, ChannelOrder::Yuv420).is_err());
+        assert!(VideoSource::from_raw_planar(&path, 0, 2, ChannelOrder::Gray).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
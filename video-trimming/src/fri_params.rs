@@ -0,0 +1,350 @@
+// FRI parameters for a target security level.
+//
+// The security of a FRI opening is the smaller of what the commit phase and the query phase
+// give, and never more than the collision resistance of the Merkle hash. Under the conjecture
+// used in practice (ethSTARK), each query gives log_blowup bits and the commit phase
+// log|F| - log|D| bits, where F is the challenge field and D the evaluation domain. The proven
+// bounds follow the Johnson-bound analysis of the ethSTARK paper with proximity parameter m:
+// each query gives -log2(sqrt(rho) * (1 + 1/2m)) bits for rate rho, and the commit phase
+//   -log2((m + 1/2)^7 / (3 rho^(3/2)) * |D|^2 / |F|)
+// bits. In both models grinding adds proof_of_work_bits to the query phase.
+//
+// The proven commit-phase bound loses 2 log|D| bits, so over a quadratic extension of Goldilocks
+// it is out of reach for full-length videos; selection reports that rather than returning
+// parameters that fall short.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::univariate::FriParameters;
+
+/// Security level used when none is asked for. Conjectured security over Goldilocks stops just
+/// short of 100 bits for a 2^27-coefficient video at the smallest blowup, so the default stays
+/// below that.
+pub const DEFAULT_SECURITY_BITS: usize = 96;
+
+// Grinding the prover always does. 2^16 hashes are negligible next to committing to a video.
+const PROOF_OF_WORK_BITS: usize = 16;
+
+// Committing costs time and memory linear in the blowup, and videos are large, so blowups past
+// 8 are not considered even when they would give smaller proofs.
+const MAX_LOG_BLOWUP: usize = 3;
+
+// Proximity parameter of the proven (Johnson bound) analysis.
+const JOHNSON_M: f64 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundnessModel {
+    /// The ethSTARK conjecture on the soundness of FRI.
+    Conjectured,
+    /// Provable bounds in the list-decoding regime.
+    Proven,
+}
+
+impl fmt::Display for SoundnessModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoundnessModel::Conjectured => write!(f, "conjectured"),
+            SoundnessModel::Proven => write!(f, "proven"),
+        }
+    }
+}
+
+impl FromStr for SoundnessModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "conjectured" => Ok(SoundnessModel::Conjectured),
+            "proven" => Ok(SoundnessModel::Proven),
+            _ => Err(format!(
+                "unknown soundness model {}, expected conjectured or proven",
+                s
+            )),
+        }
+    }
+}
+
+/// Field and hash a FRI PCS runs over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FriField {
+    /// Goldilocks with challenges in its quadratic extension and 4-element Poseidon2 digests, as
+    /// in `crate::univariate`.
+    GoldilocksExt2,
+    /// BabyBear with challenges in its quartic extension and 8-element Poseidon2 digests.
+    BabyBearExt4,
+}
+
+impl FriField {
+    fn base_bits(self) -> f64 {
+        match self {
+            FriField::GoldilocksExt2 => ((1u128 << 64) - (1u128 << 32) + 1) as f64,
+            FriField::BabyBearExt4 => ((1u64 << 31) - (1u64 << 27) + 1) as f64,
+        }
+        .log2()
+    }
+
+    fn extension_degree(self) -> usize {
+        match self {
+            FriField::GoldilocksExt2 => 2,
+            FriField::BabyBearExt4 => 4,
+        }
+    }
+
+    fn digest_elems(self) -> usize {
+        match self {
+            FriField::GoldilocksExt2 => 4,
+            FriField::BabyBearExt4 => 8,
+        }
+    }
+
+    fn base_bytes(self) -> usize {
+        match self {
+            FriField::GoldilocksExt2 => 8,
+            FriField::BabyBearExt4 => 4,
+        }
+    }
+
+    /// log2 of the size of the challenge field.
+    pub fn challenge_bits(self) -> f64 {
+        self.base_bits() * self.extension_degree() as f64
+    }
+
+    /// Collision resistance of the Merkle hash in bits.
+    pub fn hash_bits(self) -> f64 {
+        self.base_bits() * self.digest_elems() as f64 / 2.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParameterError {
+    /// No parameters reach the target; `best_bits` is the most any of them give.
+    Unreachable { best_bits: f64 },
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Rounded down, so a shortfall is never printed as the target.
+            ParameterError::Unreachable { best_bits } => write!(
+                f,
+                "target security is out of reach, the best parameters give {:.1} bits",
+                (best_bits * 10.0).floor() / 10.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+/// Parameters chosen for a target, with what they give.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FriSelection {
+    pub params: FriParameters,
+    pub security_bits: f64,
+    /// Estimated size in bytes of an opening proof for one polynomial.
+    pub proof_size: usize,
+}
+
+/// Bits of security of opening a polynomial with `1 << log_degree` coefficients with `params`.
+pub fn security_bits(
+    params: &FriParameters,
+    field: FriField,
+    log_degree: usize,
+    soundness: SoundnessModel,
+) -> f64 {
+    let log_blowup = params.log_blowup as f64;
+    let log_domain = (log_degree + params.log_blowup) as f64;
+    let (commit_bits, bits_per_query) = match soundness {
+        SoundnessModel::Conjectured => (field.challenge_bits() - log_domain, log_blowup),
+        SoundnessModel::Proven => {
+            let commit_bits =
+                field.challenge_bits() - 2.0 * log_domain - 7.0 * (JOHNSON_M + 0.5).log2()
+                    + 3f64.log2()
+                    - 1.5 * log_blowup;
+            let bits_per_query = log_blowup / 2.0 - (1.0 + 1.0 / (2.0 * JOHNSON_M)).log2();
+            (commit_bits, bits_per_query)
+        }
+    };
+    let query_bits = params.num_queries as f64 * bits_per_query + params.proof_of_work_bits as f64;
+    commit_bits.min(query_bits).min(field.hash_bits())
+}
+
+/// Estimated size in bytes of a FRI proof opening one polynomial with `1 << log_degree`
+/// coefficients at one point: a Merkle root per folding round, and per query the opened leaf
+/// and every round's sibling, each with its authentication path.
+pub fn proof_size(params: &FriParameters, field: FriField, log_degree: usize) -> usize {
+    let digest = field.digest_elems() * field.base_bytes();
+    let challenge = field.extension_degree() * field.base_bytes();
+    let height = log_degree + params.log_blowup;
+
+    let commit_phase = log_degree * digest + challenge + field.base_bytes();
+    let input_opening = field.base_bytes() + height * digest;
+    let folding_openings: usize = (0..log_degree)
+        .map(|round| challenge + (height - round - 1) * digest)
+        .sum();
+    commit_phase + params.num_queries * (input_opening + folding_openings)
+}
+
+/// The parameters with the smallest proofs that reach `target_bits` for a polynomial with
+/// `1 << log_degree` coefficients.
+pub fn select(
+    target_bits: usize,
+    soundness: SoundnessModel,
+    field: FriField,
+    log_degree: usize,
+) -> Result<FriSelection, ParameterError> {
+    let target = target_bits as f64;
+    let mut best: Option<FriSelection> = None;
+    let mut best_bits = f64::NEG_INFINITY;
+    for log_blowup in 1..=MAX_LOG_BLOWUP {
+        let proof_of_work_bits = PROOF_OF_WORK_BITS.min(target_bits);
+        // One query at a time, since the proven bits per query are fractional.
+        let mut params = FriParameters {
+            log_blowup,
+            num_queries: 1,
+            proof_of_work_bits,
+        };
+        let mut bits = security_bits(&params, field, log_degree, soundness);
+        let mut previous = f64::NEG_INFINITY;
+        while bits < target && bits > previous {
+            params.num_queries += 1;
+            previous = bits;
+            bits = security_bits(&params, field, log_degree, soundness);
+        }
+        best_bits = best_bits.max(bits);
+        if bits < target {
+            continue;
+        }
+        let selection = FriSelection {
+            params,
+            security_bits: bits,
+            proof_size: proof_size(&params, field, log_degree),
+        };
+        if !best.is_some_and(|best| best.proof_size <= selection.proof_size) {
+            best = Some(selection);
+        }
+    }
+    best.ok_or(ParameterError::Unreachable { best_bits })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_parameters_are_far_below_100_bits() {
+        let bits = security_bits(
+            &FriParameters::default(),
+            FriField::GoldilocksExt2,
+            26,
+            SoundnessModel::Conjectured,
+        );
+        assert_eq!(bits, 18.0);
+    }
+
+    #[test]
+    fn selected_parameters_reach_the_target() {
+        for target in [80, 100, 120] {
+            let selection = select(
+                target,
+                SoundnessModel::Conjectured,
+                FriField::GoldilocksExt2,
+                20,
+            )
+            .unwrap();
+            assert!(selection.security_bits >= target as f64);
+            assert_eq!(
+                security_bits(
+                    &selection.params,
+                    FriField::GoldilocksExt2,
+                    20,
+                    SoundnessModel::Conjectured
+                ),
+                selection.security_bits
+            );
+            // One query fewer falls short.
+            let mut fewer = selection.params;
+            fewer.num_queries -= 1;
+            assert!(
+                security_bits(
+                    &fewer,
+                    FriField::GoldilocksExt2,
+                    20,
+                    SoundnessModel::Conjectured
+                ) < target as f64
+            );
+        }
+    }
+
+    #[test]
+    fn higher_targets_give_larger_proofs() {
+        let sizes: Vec<_> = [80, 100]
+            .into_iter()
+            .map(|target| {
+                select(
+                    target,
+                    SoundnessModel::Conjectured,
+                    FriField::GoldilocksExt2,
+                    20,
+                )
+                .unwrap()
+                .proof_size
+            })
+            .collect();
+        assert!(sizes[0] < sizes[1]);
+    }
+
+    #[test]
+    fn proven_security_needs_more_queries() {
+        let conjectured = select(
+            80,
+            SoundnessModel::Conjectured,
+            FriField::GoldilocksExt2,
+            10,
+        )
+        .unwrap();
+        let proven = select(80, SoundnessModel::Proven, FriField::GoldilocksExt2, 10).unwrap();
+        assert!(proven.proof_size > conjectured.proof_size);
+    }
+
+    #[test]
+    fn default_target_is_reachable_for_the_reference_video() {
+        let selection = select(
+            DEFAULT_SECURITY_BITS,
+            SoundnessModel::Conjectured,
+            FriField::GoldilocksExt2,
+            27,
+        )
+        .unwrap();
+        assert!(selection.security_bits >= DEFAULT_SECURITY_BITS as f64);
+    }
+
+    #[test]
+    fn unreachable_targets_are_reported() {
+        // Four-element Goldilocks digests cap every model just below 128 bits.
+        assert!(matches!(
+            select(
+                128,
+                SoundnessModel::Conjectured,
+                FriField::GoldilocksExt2,
+                20
+            ),
+            Err(ParameterError::Unreachable { .. })
+        ));
+        // The proven commit-phase bound rules out long videos over a quadratic extension.
+        assert!(matches!(
+            select(100, SoundnessModel::Proven, FriField::GoldilocksExt2, 26),
+            Err(ParameterError::Unreachable { .. })
+        ));
+        // Just short of the target is not shown as reaching it.
+        let err = select(
+            100,
+            SoundnessModel::Conjectured,
+            FriField::GoldilocksExt2,
+            27,
+        )
+        .unwrap_err();
+        assert!(err.to_string().ends_with("give 99.9 bits"));
+    }
+}
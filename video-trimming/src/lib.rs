@@ -1,9 +1,26 @@
+pub mod barycentric;
+pub mod frames;
+pub mod fri_params;
+pub mod ligero;
+pub mod live;
+pub mod manifest;
+pub mod merkle;
+pub mod packing;
+pub mod provenance;
+pub mod redact;
+pub mod scheme;
+pub mod selection;
+pub mod signing;
+pub mod timecode;
+pub mod univariate;
+
+use std::ops::Range;
+
 use ff_ext::GoldilocksExt2;
 use p3_field_git::PrimeCharacteristicRing;
 use p3_goldilocks_git::Goldilocks as CenoGoldilocks;
 
-use ark_std::log2;
-use p3_field::{extension::BinomialExtensionField, AbstractField, Field, TwoAdicField};
+use p3_field::{extension::BinomialExtensionField, AbstractField};
 use p3_goldilocks::Goldilocks;
 use rayon::prelude::*;
 use rs_merkle::{algorithms::Sha256, Hasher, MerkleProof, MerkleTree};
@@ -12,47 +29,8 @@ type F = Goldilocks;
 type E = GoldilocksExt2;
 type EF = BinomialExtensionField<F, 2>;
 
-pub fn barycentric_evaluation(mut image: Vec<u8>, eval_point: EF) -> EF {
-    // Using formula at the bottom of this page: https://hackmd.io/@vbuterin/barycentric_evaluation
-    // Degree is N in that formula
-    let degree = image.len().next_power_of_two();
-    // while image.len() < degree {
-    //     image.push(0_u8);
-    // }
-    let degree_bits = log2(degree);
-    let omega = EF::two_adic_generator(degree_bits as usize);
-    // Will contain powers of omega needed for sum
-    let mut omega_acc = EF::one();
-    // Running sum:
-    let mut sum = EF::zero();
-    static MONTGOMERY_STEP_SIZE: usize = 256;
-    let mut numerators = vec![EF::zero(); MONTGOMERY_STEP_SIZE];
-    let mut denominators = vec![EF::zero(); MONTGOMERY_STEP_SIZE];
-    let mut montgomery_partial_prods = vec![EF::zero(); MONTGOMERY_STEP_SIZE];
-    for i in (0..image.len()).step_by(MONTGOMERY_STEP_SIZE) {
-        for j in 0..MONTGOMERY_STEP_SIZE {
-            numerators[j] = EF::from_canonical_u8(image[i + j]) * omega_acc;
-            denominators[j] = eval_point - omega_acc;
-            montgomery_partial_prods[j] = if j == 0 {
-                denominators[j]
-            } else {
-                denominators[j] * montgomery_partial_prods[j - 1]
-            };
-            omega_acc *= omega;
-        }
-
-        let mut last_inverse = montgomery_partial_prods[MONTGOMERY_STEP_SIZE - 1].inverse();
-        for j in (1..MONTGOMERY_STEP_SIZE).rev() {
-            let inverted_denominator = montgomery_partial_prods[j - 1] * last_inverse;
-            sum += inverted_denominator * numerators[j];
-            last_inverse *= denominators[j];
-        }
-        sum += last_inverse * numerators[0];
-    }
-    let front_quantity =
-        (eval_point.exp_u64(degree as u64) - EF::one()) / EF::from_canonical_u64(degree as u64);
-
-    front_quantity * sum
+pub fn barycentric_evaluation(image: Vec<u8>, eval_point: EF) -> EF {
+    barycentric::evaluate_frames(std::iter::once(&image[..]), image.len(), &[eval_point])[0]
 }
 
 pub fn horners_evaluation(mut image: Vec<u8>, eval_point: EF) -> EF {
@@ -115,6 +93,22 @@ pub fn compute_video_mle_evaluations_vec(
     evaluations
 }
 
+/// Lays `pixels`, frames back to back without per-frame padding, out over `cubes`, consecutive
+/// element ranges that cover it, zero-filling past the end of the video.
+pub fn compute_video_subcube_evaluations(
+    pixels: &[u64],
+    cubes: &[Range<usize>],
+) -> Vec<Vec<CenoGoldilocks>> {
+    cubes
+        .iter()
+        .map(|cube| {
+            cube.clone()
+                .map(|i| CenoGoldilocks::from_u64(pixels.get(i).copied().unwrap_or(0)))
+                .collect()
+        })
+        .collect()
+}
+
 pub fn to_binary_vec(frame_num: usize, n: usize) -> Vec<u64> {
     (0..n)
         .rev()
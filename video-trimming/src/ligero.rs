@@ -0,0 +1,701 @@
+// Ligero video trimming over the BLS12-381 scalar field.
+//
+// The packed video is committed as the coefficients of P(x) with the univariate Ligero PCS from
+// arkworks, which is transparent and only needs hashing. Keeping the frames [start, end) is
+// proven as in `crate::univariate`: the prover commits to the prefix polynomial R and suffix
+// polynomial Q and opens P, R and Q at a common point z, so the verifier can check
+//   P(z) = R(z) + z^(start * m) * F(z) + z^(end * m) * Q(z)
+// where F is the polynomial of the published frames and m the number of coefficients per frame.
+//
+// Ligero bounds the degree of a polynomial by the shape of the matrix it is committed as, so every
+// polynomial is committed with a power-of-two number of coefficients and the verifier checks the
+// shape of the prefix and suffix commitments. The prefix is committed as x^pad * R(x) and the
+// padding power divided out again, so its padding can only reach below x^0 and the suffix's only
+// past the end of the video. P has no coefficients there, so neither can absorb a change to the
+// published frames.
+//
+// z is squeezed from a Poseidon sponge that has absorbed the signed commitment, the segment, a
+// SHA-256 digest of the published frames and the commitments to R and Q, and the same sponge then
+// drives the Ligero openings. Over Fr, z lies in the field the frames are packed into, so an
+// editor who knew z before fixing the frames could add delta * (z - x) to F without changing
+// F(z). The sponge is the x^5 Poseidon permutation of width 3 with 8 full and 57 partial rounds,
+// the 128-bit parameters for a 255-bit field, with round constants and MDS matrix from the
+// reference Grain LFSR.
+
+use std::ops::Range;
+
+use ark_bls12_381::Fr;
+use ark_crypto_primitives::crh::{sha256::Sha256, CRHScheme, TwoToOneCRHScheme};
+use ark_crypto_primitives::merkle_tree::{ByteDigestConverter, Config};
+use ark_crypto_primitives::sponge::poseidon::{
+    find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge,
+};
+use ark_crypto_primitives::sponge::{CryptographicSponge, FieldBasedCryptographicSponge};
+use ark_ff::{Field, PrimeField, Zero};
+use ark_pcs_bench_templates::{FieldToBytesColHasher, LeafIdentityHasher};
+use ark_poly::univariate::DensePolynomial;
+use ark_poly_commit::linear_codes::{LigeroPCParams, LinearCodePCS, UnivariateLigero};
+use ark_poly_commit::{LabeledCommitment, LabeledPolynomial, PolynomialCommitment};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::SeedableRng;
+use blake2::Blake2s256;
+use sha2::Digest;
+
+use crate::univariate::TrimError;
+
+type LeafHash = LeafIdentityHasher;
+type CompressHash = Sha256;
+type ColHash = FieldToBytesColHasher<Fr, Blake2s256>;
+
+pub struct MerkleConfig;
+
+impl Config for MerkleConfig {
+    type Leaf = Vec<u8>;
+
+    type LeafDigest = <LeafHash as CRHScheme>::Output;
+    type LeafInnerDigestConverter = ByteDigestConverter<Self::LeafDigest>;
+    type InnerDigest = <CompressHash as TwoToOneCRHScheme>::Output;
+
+    type LeafHash = LeafHash;
+    type TwoToOneHash = CompressHash;
+}
+
+type Poly = DensePolynomial<Fr>;
+
+pub type LigeroPcs = LinearCodePCS<
+    UnivariateLigero<Fr, MerkleConfig, Poly, ColHash>,
+    Fr,
+    Poly,
+    MerkleConfig,
+    ColHash,
+>;
+
+pub type Commitment = <LigeroPcs as PolynomialCommitment<Fr, Poly>>::Commitment;
+type CommitmentState = <LigeroPcs as PolynomialCommitment<Fr, Poly>>::CommitmentState;
+type CommitterKey = <LigeroPcs as PolynomialCommitment<Fr, Poly>>::CommitterKey;
+type VerifierKey = <LigeroPcs as PolynomialCommitment<Fr, Poly>>::VerifierKey;
+pub type PcsProof = <LigeroPcs as PolynomialCommitment<Fr, Poly>>::Proof;
+
+/// Security parameter Ligero picks its number of column queries for.
+pub const SECURITY_BITS: usize = 128;
+
+/// Inverse rate of the Reed-Solomon code the matrix rows are encoded with.
+pub const RHO_INV: usize = 4;
+
+// Ligero opens about 190 columns at 128 bits and rate 1/4, so polynomials are committed with at
+// least this many coefficients for their codewords to have more columns than that.
+const MIN_COEFFS: usize = 1 << 12;
+
+const POSEIDON_FULL_ROUNDS: usize = 8;
+const POSEIDON_PARTIAL_ROUNDS: usize = 57;
+const POSEIDON_ALPHA: u64 = 5;
+const POSEIDON_RATE: usize = 2;
+
+// Absorbed before anything else, so the opening point can't be shared with another protocol.
+const TRANSCRIPT_DOMAIN: &[u8] = b"video-trimming ligero trim";
+
+/// The Poseidon parameters the trimming transcript is built with.
+pub fn poseidon_config() -> PoseidonConfig<Fr> {
+    let (ark, mds) = find_poseidon_ark_and_mds::<Fr>(
+        Fr::MODULUS_BIT_SIZE as u64,
+        POSEIDON_RATE,
+        POSEIDON_FULL_ROUNDS as u64,
+        POSEIDON_PARTIAL_ROUNDS as u64,
+        0,
+    );
+    PoseidonConfig::new(
+        POSEIDON_FULL_ROUNDS,
+        POSEIDON_PARTIAL_ROUNDS,
+        POSEIDON_ALPHA,
+        mds,
+        ark,
+        POSEIDON_RATE,
+        1,
+    )
+}
+
+/// Canonical byte encoding of a commitment: its compressed arkworks serialization.
+pub fn commitment_to_bytes(commitment: &Commitment) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    commitment.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+// Number of coefficients a polynomial with `len` coefficients is committed with.
+fn committed_len(len: usize) -> usize {
+    len.max(MIN_COEFFS).next_power_of_two()
+}
+
+// (rows, columns, codeword length) of the matrix arkworks commits `committed_len` coefficients
+// as: the smallest power-of-two number of columns that is at least the square root.
+fn matrix_shape(committed_len: usize) -> [usize; 3] {
+    let log_cols = (committed_len.trailing_zeros() as usize).div_ceil(2);
+    [
+        committed_len >> log_cols,
+        1 << log_cols,
+        RHO_INV << log_cols,
+    ]
+}
+
+// arkworks keeps the matrix shape of a commitment private, but serializes it first as three u64s.
+fn commitment_shape(commitment: &Commitment) -> Option<[usize; 3]> {
+    let bytes = commitment_to_bytes(commitment);
+    if bytes.len() < 24 {
+        return None;
+    }
+    let mut shape = [0; 3];
+    for (dim, chunk) in shape.iter_mut().zip(bytes.chunks_exact(8)) {
+        *dim = u64::from_le_bytes(chunk.try_into().unwrap()) as usize;
+    }
+    Some(shape)
+}
+
+// The polynomial with exactly these coefficients. `from_coefficients_vec` drops leading zeros,
+// which would change the shape the polynomial is committed as.
+fn labeled(label: &str, coeffs: Vec<Fr>) -> LabeledPolynomial<Fr, Poly> {
+    LabeledPolynomial::new(label.to_string(), DensePolynomial { coeffs }, None, None)
+}
+
+// SHA-256 of the compressed serializations of the published coefficients.
+fn published_digest(coeffs: &[Fr]) -> Vec<u8> {
+    let mut hasher = sha2::Sha256::new();
+    let mut bytes = Vec::new();
+    for coeff in coeffs {
+        bytes.clear();
+        coeff.serialize_compressed(&mut bytes).unwrap();
+        hasher.update(&bytes);
+    }
+    hasher.finalize().to_vec()
+}
+
+// Evaluates the polynomial with coefficients `coeffs` at `point` with Horner's rule.
+fn evaluate_coeffs(coeffs: &[Fr], point: Fr) -> Fr {
+    coeffs
+        .iter()
+        .rev()
+        .fold(Fr::zero(), |eval, coeff| eval * point + coeff)
+}
+
+/// The signer's view of a committed video.
+pub struct VideoCommitment {
+    pub commitment: Commitment,
+    /// Number of packed elements in the video, before padding.
+    pub num_coeffs: usize,
+    polynomial: LabeledPolynomial<Fr, Poly>,
+    state: CommitmentState,
+}
+
+/// Proof that the published frames are frames `start..end` of a committed video.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct TrimProof {
+    pub start: usize,
+    pub end: usize,
+    /// Commitment to x^pad * R(x), absent if the kept frames start the video.
+    pub prefix_commitment: Option<Commitment>,
+    /// Commitment to Q(x), absent if the kept frames end the video.
+    pub suffix_commitment: Option<Commitment>,
+    /// The values at z of P, then of the prefix and suffix polynomials that are present.
+    pub values: Vec<Fr>,
+    pub proof: PcsProof,
+}
+
+impl TrimProof {
+    pub fn size_in_bytes(&self) -> usize {
+        self.compressed_size()
+    }
+}
+
+pub struct LigeroSetup {
+    ck: CommitterKey,
+    vk: VerifierKey,
+    sponge: PoseidonSponge<Fr>,
+}
+
+impl Default for LigeroSetup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LigeroSetup {
+    pub fn new() -> Self {
+        // None of the hashes take parameters, the rng is only there to satisfy the interface.
+        let mut rng = StdRng::seed_from_u64(0);
+        let leaf_hash_param = <LeafHash as CRHScheme>::setup(&mut rng).unwrap();
+        let two_to_one_hash_param = <CompressHash as TwoToOneCRHScheme>::setup(&mut rng).unwrap();
+        let col_hash_params = <ColHash as CRHScheme>::setup(&mut rng).unwrap();
+        let pp = LigeroPCParams::new(
+            SECURITY_BITS,
+            RHO_INV,
+            true,
+            leaf_hash_param,
+            two_to_one_hash_param,
+            col_hash_params,
+        );
+        let (ck, vk) = LigeroPcs::trim(&pp, 0, 0, None).unwrap();
+
+        Self {
+            ck,
+            vk,
+            sponge: PoseidonSponge::new(&poseidon_config()),
+        }
+    }
+
+    fn commit(&self, polynomial: &LabeledPolynomial<Fr, Poly>) -> (Commitment, CommitmentState) {
+        let (mut commitments, mut states) =
+            LigeroPcs::commit(&self.ck, [polynomial], None).unwrap();
+        let commitment = commitments.pop().unwrap().commitment().clone();
+        (commitment, states.pop().unwrap())
+    }
+
+    // Sponge for a trimming proof, with everything the opening point must depend on already
+    // absorbed.
+    fn trim_sponge(
+        &self,
+        commitment: &Commitment,
+        num_coeffs: usize,
+        frame_size: usize,
+        kept: &Range<usize>,
+        published: &[u8],
+        gaps: [&Option<Commitment>; 2],
+    ) -> PoseidonSponge<Fr> {
+        let mut sponge = self.sponge.clone();
+        sponge.absorb(&TRANSCRIPT_DOMAIN.to_vec());
+        sponge.absorb(&commitment_to_bytes(commitment));
+        for value in [num_coeffs, frame_size, kept.start, kept.end] {
+            sponge.absorb(&(value as u64));
+        }
+        sponge.absorb(&published.to_vec());
+        for gap in gaps {
+            sponge.absorb(&(gap.is_some() as u8));
+            if let Some(gap) = gap {
+                sponge.absorb(&commitment_to_bytes(gap));
+            }
+        }
+        sponge
+    }
+
+    /// Commits to the polynomial whose coefficients are the packed video.
+    pub fn commit_video(&self, mut coeffs: Vec<Fr>) -> VideoCommitment {
+        let num_coeffs = coeffs.len();
+        coeffs.resize(committed_len(num_coeffs), Fr::zero());
+        let polynomial = labeled("video", coeffs);
+        let (commitment, state) = self.commit(&polynomial);
+
+        VideoCommitment {
+            commitment,
+            num_coeffs,
+            polynomial,
+            state,
+        }
+    }
+
+    /// Proves that frames `kept` (of `frame_size` coefficients each) are a contiguous part of the
+    /// committed video.
+    pub fn prove_trim(
+        &self,
+        video: &VideoCommitment,
+        frame_size: usize,
+        kept: Range<usize>,
+    ) -> Result<TrimProof, TrimError> {
+        let start = frame_size
+            .checked_mul(kept.start)
+            .ok_or(TrimError::InvalidSegments)?;
+        let end = frame_size
+            .checked_mul(kept.end)
+            .ok_or(TrimError::InvalidSegments)?;
+        if start > end || end > video.num_coeffs {
+            return Err(TrimError::InvalidSegments);
+        }
+        let coeffs = &video.polynomial.polynomial().coeffs;
+
+        let prefix = (start > 0).then(|| {
+            let mut shifted = vec![Fr::zero(); committed_len(start) - start];
+            shifted.extend_from_slice(&coeffs[..start]);
+            shifted
+        });
+        let suffix = (end < video.num_coeffs).then(|| {
+            let mut suffix = coeffs[end..video.num_coeffs].to_vec();
+            suffix.resize(committed_len(suffix.len()), Fr::zero());
+            suffix
+        });
+        self.prove_trim_with(video, frame_size, kept, prefix, suffix)
+    }
+
+    // Proves the trim with the given (padded) prefix and suffix coefficients. Only honest when
+    // they are the actual prefix and suffix of `video`; split out so tests can build forged
+    // proofs.
+    fn prove_trim_with(
+        &self,
+        video: &VideoCommitment,
+        frame_size: usize,
+        kept: Range<usize>,
+        prefix: Option<Vec<Fr>>,
+        suffix: Option<Vec<Fr>>,
+    ) -> Result<TrimProof, TrimError> {
+        let prefix = prefix.map(|coeffs| labeled("prefix", coeffs));
+        let suffix = suffix.map(|coeffs| labeled("suffix", coeffs));
+        let prefix_committed = prefix.as_ref().map(|prefix| self.commit(prefix));
+        let suffix_committed = suffix.as_ref().map(|suffix| self.commit(suffix));
+        let prefix_commitment = prefix_committed.as_ref().map(|(c, _)| c.clone());
+        let suffix_commitment = suffix_committed.as_ref().map(|(c, _)| c.clone());
+
+        let coeffs = &video.polynomial.polynomial().coeffs;
+        let published = published_digest(&coeffs[frame_size * kept.start..frame_size * kept.end]);
+        let mut sponge = self.trim_sponge(
+            &video.commitment,
+            video.num_coeffs,
+            frame_size,
+            &kept,
+            &published,
+            [&prefix_commitment, &suffix_commitment],
+        );
+        let z: Fr = sponge.squeeze_native_field_elements(1)[0];
+
+        let mut polynomials = vec![&video.polynomial];
+        let mut commitments = vec![LabeledCommitment::new(
+            "video".to_string(),
+            video.commitment.clone(),
+            None,
+        )];
+        let mut states = vec![&video.state];
+        for (polynomial, (commitment, state)) in prefix
+            .iter()
+            .zip(&prefix_committed)
+            .chain(suffix.iter().zip(&suffix_committed))
+        {
+            polynomials.push(polynomial);
+            commitments.push(LabeledCommitment::new(
+                polynomial.label().clone(),
+                commitment.clone(),
+                None,
+            ));
+            states.push(state);
+        }
+
+        let values = polynomials
+            .iter()
+            .map(|polynomial| polynomial.evaluate(&z))
+            .collect();
+        let proof = LigeroPcs::open(
+            &self.ck,
+            polynomials,
+            &commitments,
+            &z,
+            &mut sponge,
+            states,
+            None,
+        )
+        .map_err(|e| TrimError::Pcs(format!("{:?}", e)))?;
+
+        Ok(TrimProof {
+            start: kept.start,
+            end: kept.end,
+            prefix_commitment,
+            suffix_commitment,
+            values,
+            proof,
+        })
+    }
+
+    /// Verifies `proof` against the signed `commitment` to a video of `num_coeffs` packed
+    /// elements and the published `segment_coeffs`, the packed kept frames.
+    pub fn verify_trim(
+        &self,
+        commitment: &Commitment,
+        num_coeffs: usize,
+        frame_size: usize,
+        proof: &TrimProof,
+        segment_coeffs: &[Fr],
+    ) -> Result<(), TrimError> {
+        let start = frame_size
+            .checked_mul(proof.start)
+            .ok_or(TrimError::MalformedProof)?;
+        let end = frame_size
+            .checked_mul(proof.end)
+            .ok_or(TrimError::MalformedProof)?;
+        if start > end || end > num_coeffs || segment_coeffs.len() != end - start {
+            return Err(TrimError::MalformedProof);
+        }
+
+        // The prefix and suffix must be present exactly when they are non-empty, and committed
+        // with the shape that bounds them to their length.
+        let gaps = [
+            (&proof.prefix_commitment, "prefix", start),
+            (&proof.suffix_commitment, "suffix", num_coeffs - end),
+        ];
+        let mut commitments = vec![LabeledCommitment::new(
+            "video".to_string(),
+            commitment.clone(),
+            None,
+        )];
+        for (gap, label, len) in gaps {
+            if gap.is_some() != (len > 0) {
+                return Err(TrimError::MalformedProof);
+            }
+            if let Some(gap) = gap {
+                if commitment_shape(gap) != Some(matrix_shape(committed_len(len))) {
+                    return Err(TrimError::MalformedProof);
+                }
+                commitments.push(LabeledCommitment::new(label.to_string(), gap.clone(), None));
+            }
+        }
+        if proof.values.len() != commitments.len() {
+            return Err(TrimError::MalformedProof);
+        }
+
+        let mut sponge = self.trim_sponge(
+            commitment,
+            num_coeffs,
+            frame_size,
+            &(proof.start..proof.end),
+            &published_digest(segment_coeffs),
+            [&proof.prefix_commitment, &proof.suffix_commitment],
+        );
+        let z: Fr = sponge.squeeze_native_field_elements(1)[0];
+
+        let valid = LigeroPcs::check(
+            &self.vk,
+            &commitments,
+            &z,
+            proof.values.iter().copied(),
+            &proof.proof,
+            &mut sponge,
+            None,
+        )
+        .map_err(|e| TrimError::Pcs(format!("{:?}", e)))?;
+        if !valid {
+            return Err(TrimError::Pcs(
+                "opening does not match the values".to_string(),
+            ));
+        }
+
+        let mut values = proof.values.iter();
+        let p_value = *values.next().unwrap();
+        let mut inferred_eval_value = z.pow([start as u64]) * evaluate_coeffs(segment_coeffs, z);
+        if proof.prefix_commitment.is_some() {
+            // Remove the padding exponent the prefix was committed with.
+            let padding = committed_len(start) - start;
+            let unshift = z
+                .pow([padding as u64])
+                .inverse()
+                .ok_or(TrimError::EvaluationMismatch)?;
+            inferred_eval_value += *values.next().unwrap() * unshift;
+        }
+        if proof.suffix_commitment.is_some() {
+            inferred_eval_value += *values.next().unwrap() * z.pow([end as u64]);
+        }
+
+        if inferred_eval_value != p_value {
+            return Err(TrimError::EvaluationMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_SIZE: usize = 4;
+    const NUM_FRAMES: usize = 8;
+
+    fn test_video(seed: u64) -> Vec<Fr> {
+        (0..(FRAME_SIZE * NUM_FRAMES) as u64)
+            .map(|i| Fr::from(i * 1_000_003 + seed))
+            .collect()
+    }
+
+    fn segment_coeffs(video: &[Fr], segment: &Range<usize>) -> Vec<Fr> {
+        video[FRAME_SIZE * segment.start..FRAME_SIZE * segment.end].to_vec()
+    }
+
+    fn verify(
+        setup: &LigeroSetup,
+        video: &VideoCommitment,
+        proof: &TrimProof,
+        published: &[Fr],
+    ) -> Result<(), TrimError> {
+        setup.verify_trim(
+            &video.commitment,
+            video.num_coeffs,
+            FRAME_SIZE,
+            proof,
+            published,
+        )
+    }
+
+    #[test]
+    fn honest_trim_verifies() {
+        let setup = LigeroSetup::new();
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        for segment in [1..2, 2..7, 0..3, 5..8, 0..8, 3..3] {
+            let proof = setup
+                .prove_trim(&video, FRAME_SIZE, segment.clone())
+                .unwrap();
+            assert!(proof.size_in_bytes() > 0);
+            verify(&setup, &video, &proof, &segment_coeffs(&coeffs, &segment)).unwrap();
+        }
+    }
+
+    #[test]
+    fn black_prefix_and_suffix_verify() {
+        // Zero coefficients at the top of the prefix or suffix must not change their shape.
+        let setup = LigeroSetup::new();
+        let mut coeffs = test_video(1);
+        for coeff in &mut coeffs[..FRAME_SIZE * 2] {
+            *coeff = Fr::zero();
+        }
+        let video = setup.commit_video(coeffs.clone());
+        let proof = setup.prove_trim(&video, FRAME_SIZE, 2..5).unwrap();
+        verify(&setup, &video, &proof, &segment_coeffs(&coeffs, &(2..5))).unwrap();
+    }
+
+    #[test]
+    fn tampered_segment_is_rejected() {
+        let setup = LigeroSetup::new();
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let segment = 2..5;
+        let proof = setup
+            .prove_trim(&video, FRAME_SIZE, segment.clone())
+            .unwrap();
+
+        let mut published = segment_coeffs(&coeffs, &segment);
+        published[5] += Fr::from(1u64);
+        assert!(matches!(
+            verify(&setup, &video, &proof, &published),
+            Err(TrimError::EvaluationMismatch)
+        ));
+    }
+
+    #[test]
+    fn published_frames_are_bound_to_the_challenge() {
+        let setup = LigeroSetup::new();
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let segment = 2..5;
+        let proof = setup
+            .prove_trim(&video, FRAME_SIZE, segment.clone())
+            .unwrap();
+        let honest = segment_coeffs(&coeffs, &segment);
+
+        // The point the proof was opened at.
+        let z: Fr = setup
+            .trim_sponge(
+                &video.commitment,
+                video.num_coeffs,
+                FRAME_SIZE,
+                &segment,
+                &published_digest(&honest),
+                [&proof.prefix_commitment, &proof.suffix_commitment],
+            )
+            .squeeze_native_field_elements(1)[0];
+
+        // Adding delta * (z - x) to the published frames does not change their value at z.
+        let delta = Fr::from(7u64);
+        let mut forged = honest.clone();
+        forged[0] += delta * z;
+        forged[1] -= delta;
+        assert_eq!(evaluate_coeffs(&forged, z), evaluate_coeffs(&honest, z));
+
+        assert!(verify(&setup, &video, &proof, &forged).is_err());
+    }
+
+    #[test]
+    fn swapped_prefix_is_rejected() {
+        let setup = LigeroSetup::new();
+        let coeffs = test_video(1);
+        let other = test_video(2);
+        let video = setup.commit_video(coeffs.clone());
+        let segment = 2..5;
+
+        // Commit to the prefix of a different video but open the signed one.
+        let start = FRAME_SIZE * segment.start;
+        let mut prefix = vec![Fr::zero(); committed_len(start) - start];
+        prefix.extend_from_slice(&other[..start]);
+        let mut suffix = segment_coeffs(&coeffs, &(segment.end..NUM_FRAMES));
+        suffix.resize(committed_len(suffix.len()), Fr::zero());
+        let forged = setup
+            .prove_trim_with(
+                &video,
+                FRAME_SIZE,
+                segment.clone(),
+                Some(prefix),
+                Some(suffix),
+            )
+            .unwrap();
+        assert!(verify(&setup, &video, &forged, &segment_coeffs(&coeffs, &segment)).is_err());
+    }
+
+    #[test]
+    fn oversized_prefix_is_rejected() {
+        let setup = LigeroSetup::new();
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let segment = 2..5;
+
+        // A prefix committed with more coefficients than it may have could reach into the kept
+        // frames.
+        let start = FRAME_SIZE * segment.start;
+        let mut prefix = vec![Fr::zero(); 2 * committed_len(start) - start];
+        prefix.extend_from_slice(&coeffs[..start]);
+        let mut suffix = coeffs[FRAME_SIZE * segment.end..].to_vec();
+        suffix.resize(committed_len(suffix.len()), Fr::zero());
+        let forged = setup
+            .prove_trim_with(
+                &video,
+                FRAME_SIZE,
+                segment.clone(),
+                Some(prefix),
+                Some(suffix),
+            )
+            .unwrap();
+        assert!(matches!(
+            verify(&setup, &video, &forged, &segment_coeffs(&coeffs, &segment)),
+            Err(TrimError::MalformedProof)
+        ));
+    }
+
+    #[test]
+    fn shifted_boundary_is_rejected() {
+        let setup = LigeroSetup::new();
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let proof = setup.prove_trim(&video, FRAME_SIZE, 2..5).unwrap();
+
+        for shifted in [3..6, 1..4, 2..6, 3..5] {
+            let mut moved = proof.clone();
+            moved.start = shifted.start;
+            moved.end = shifted.end;
+            assert!(verify(&setup, &video, &moved, &segment_coeffs(&coeffs, &shifted)).is_err());
+        }
+    }
+
+    #[test]
+    fn proof_for_other_video_is_rejected() {
+        let setup = LigeroSetup::new();
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let other = setup.commit_video(test_video(2));
+        let segment = 2..5;
+        let proof = setup
+            .prove_trim(&video, FRAME_SIZE, segment.clone())
+            .unwrap();
+        assert!(verify(&setup, &other, &proof, &segment_coeffs(&coeffs, &segment)).is_err());
+    }
+
+    #[test]
+    fn invalid_segments_are_an_error() {
+        let setup = LigeroSetup::new();
+        let video = setup.commit_video(test_video(1));
+        for segment in [4..2, 2..9] {
+            assert!(matches!(
+                setup.prove_trim(&video, FRAME_SIZE, segment),
+                Err(TrimError::InvalidSegments)
+            ));
+        }
+    }
+}
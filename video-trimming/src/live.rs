@@ -0,0 +1,449 @@
+// Append-only signing for live capture.
+//
+// A body camera cannot wait for the end of a recording before it signs. Instead every chunk (a GOP
+// or a fixed number of frames) is committed as soon as it is captured: the chunk's frames get a
+// `merkle::FrameTree`, and its root together with its frame count becomes the next leaf of a Merkle
+// mountain range. After each chunk the signer signs a checkpoint over the bagged peaks of the
+// range, so a recording that is cut off still carries a signature over everything captured.
+//
+// A span of whole chunks is later proved against any checkpoint that covers it: the verifier
+// recomputes the revealed chunk roots from the published frames, each peak the span touches is
+// opened with a multiproof, and the remaining peaks are sent as bare digests.
+
+use std::fmt;
+use std::ops::Range;
+
+use p3_baby_bear::BabyBear;
+use p3_field::{AbstractField, PrimeField32};
+use serde::{Deserialize, Serialize};
+
+use crate::merkle::{Digest, FrameTree, MerkleHasher, MultiProof, DIGEST_ELEMS};
+use crate::packing::{BabyBearPacker, Packer};
+use crate::signing::{PublicKey, SigningError, VideoSigner};
+
+// Prepended to everything that is signed, so a checkpoint signature can't be replayed as a
+// signature over some other message format.
+const SIGNING_DOMAIN: &[u8] = b"video-trimming live checkpoint";
+
+#[derive(Debug)]
+pub enum LiveError {
+    /// The checkpoint signature did not verify.
+    Signature(SigningError),
+    /// The requested chunks are empty or not covered by the checkpoint.
+    InvalidChunks,
+    /// The proof or the published frames do not have the expected shape.
+    MalformedProof,
+    /// The recomputed range differs from the signed checkpoint.
+    RootMismatch,
+}
+
+impl fmt::Display for LiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiveError::Signature(e) => write!(f, "checkpoint signature rejected: {}", e),
+            LiveError::InvalidChunks => write!(f, "chunks are not covered by the checkpoint"),
+            LiveError::MalformedProof => write!(f, "malformed span proof"),
+            LiveError::RootMismatch => {
+                write!(f, "published frames do not match the signed checkpoint")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LiveError {}
+
+/// A signature over the first `num_chunks` chunks of a recording.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub num_chunks: usize,
+    pub root: Digest,
+    pub signature: Vec<u8>,
+}
+
+impl Checkpoint {
+    fn signing_payload(num_chunks: usize, frame_len: usize, root: &Digest) -> Vec<u8> {
+        let mut payload = SIGNING_DOMAIN.to_vec();
+        payload.extend((num_chunks as u64).to_le_bytes());
+        payload.extend((frame_len as u64).to_le_bytes());
+        payload.extend(root.iter().flat_map(|x| x.as_canonical_u32().to_le_bytes()));
+        payload
+    }
+}
+
+// The mountain range leaf of a chunk binds its frame count as well as its frames.
+fn chunk_leaf(hasher: &MerkleHasher, chunk_root: Digest, num_frames: usize) -> Digest {
+    let mut count = [BabyBear::zero(); DIGEST_ELEMS];
+    count[0] = BabyBear::from_canonical_usize(num_frames);
+    hasher.compress(chunk_root, count)
+}
+
+// Leaf ranges of the peaks of a range of `num_leaves` leaves, largest first.
+fn peaks(num_leaves: usize) -> Vec<Range<usize>> {
+    let mut peaks = Vec::new();
+    let mut offset = 0;
+    for bit in (0..usize::BITS).rev() {
+        let size = 1usize << bit;
+        if num_leaves & size != 0 {
+            peaks.push(offset..offset + size);
+            offset += size;
+        }
+    }
+    peaks
+}
+
+// Folds the peak digests from the right into one root.
+fn bag_peaks(hasher: &MerkleHasher, peaks: &[Digest]) -> Digest {
+    let (last, rest) = peaks.split_last().unwrap();
+    rest.iter()
+        .rev()
+        .fold(*last, |acc, peak| hasher.compress(*peak, acc))
+}
+
+// The part of `chunks` inside `peak`, relative to the start of the peak.
+fn overlap(peak: &Range<usize>, chunks: &Range<usize>) -> Option<Range<usize>> {
+    let start = peak.start.max(chunks.start);
+    let end = peak.end.min(chunks.end);
+    (start < end).then(|| start - peak.start..end - peak.start)
+}
+
+/// The camera side: commits and signs chunks as they are captured.
+pub struct LiveSigner<S> {
+    hasher: MerkleHasher,
+    packer: BabyBearPacker,
+    frame_len: usize,
+    signer: S,
+    // One mountain range leaf per chunk.
+    leaves: Vec<Digest>,
+    chunk_frames: Vec<usize>,
+    // Roots of the current peaks, largest first.
+    peak_roots: Vec<Digest>,
+}
+
+impl<S: VideoSigner> LiveSigner<S> {
+    /// A signer for frames of `frame_len` bytes.
+    pub fn new(signer: S, frame_len: usize) -> Self {
+        Self {
+            hasher: MerkleHasher::new(18),
+            packer: BabyBearPacker::new(3, true),
+            frame_len,
+            signer,
+            leaves: Vec::new(),
+            chunk_frames: Vec::new(),
+            peak_roots: Vec::new(),
+        }
+    }
+
+    pub fn num_chunks(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Commits to the next chunk, its frames back to back, and signs a checkpoint over every
+    /// chunk so far.
+    pub fn push_chunk(&mut self, frames: &[u8]) -> Checkpoint {
+        assert!(
+            !frames.is_empty() && frames.len() % self.frame_len == 0,
+            "chunk is not a whole number of frames"
+        );
+        let num_frames = frames.len() / self.frame_len;
+        let packed = self.packer.pack(frames, self.frame_len);
+        let frame_size = self.packer.elements_per_frame(self.frame_len);
+        let chunk_root = FrameTree::new(&self.hasher, &packed, frame_size).root();
+        let leaf = chunk_leaf(&self.hasher, chunk_root, num_frames);
+        self.leaves.push(leaf);
+        self.chunk_frames.push(num_frames);
+
+        // The new leaf is a peak of height 0. Each trailing zero bit of the new chunk count marks
+        // a peak of equal height to its left that it merges with, as in a binary counter.
+        let num_chunks = self.leaves.len();
+        let mut node = leaf;
+        for _ in 0..num_chunks.trailing_zeros() {
+            let left = self.peak_roots.pop().unwrap();
+            node = self.hasher.compress(left, node);
+        }
+        self.peak_roots.push(node);
+        let root = bag_peaks(&self.hasher, &self.peak_roots);
+        let payload = Checkpoint::signing_payload(num_chunks, self.frame_len, &root);
+        Checkpoint {
+            num_chunks,
+            root,
+            signature: self.signer.sign(&payload),
+        }
+    }
+
+    fn peak_tree(&self, peak: &Range<usize>) -> FrameTree {
+        FrameTree::from_leaves(&self.hasher, self.leaves[peak.clone()].to_vec())
+    }
+
+    /// Proves that `chunks` are part of the recording signed by the checkpoint over the first
+    /// `num_chunks` chunks.
+    pub fn prove_span(
+        &self,
+        num_chunks: usize,
+        chunks: Range<usize>,
+    ) -> Result<SpanProof, LiveError> {
+        if chunks.start >= chunks.end || chunks.end > num_chunks || num_chunks > self.num_chunks() {
+            return Err(LiveError::InvalidChunks);
+        }
+        let peaks = peaks(num_chunks)
+            .iter()
+            .map(|peak| {
+                let tree = self.peak_tree(peak);
+                match overlap(peak, &chunks) {
+                    Some(opened) => PeakProof::Opened(tree.prove(&[opened]).unwrap()),
+                    None => PeakProof::Digest(tree.root()),
+                }
+            })
+            .collect();
+        Ok(SpanProof {
+            chunk_frames: self.chunk_frames[chunks.clone()].to_vec(),
+            chunks,
+            peaks,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum PeakProof {
+    /// A peak the span does not touch.
+    Digest(Digest),
+    /// A peak containing revealed chunks.
+    Opened(MultiProof),
+}
+
+/// Proof that a span of whole chunks is part of a signed recording.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanProof {
+    pub chunks: Range<usize>,
+    /// Number of frames in each revealed chunk.
+    pub chunk_frames: Vec<usize>,
+    peaks: Vec<PeakProof>,
+}
+
+/// The verifier side, for frames of `frame_len` bytes.
+pub struct LiveVerifier {
+    hasher: MerkleHasher,
+    packer: BabyBearPacker,
+    frame_len: usize,
+}
+
+impl LiveVerifier {
+    pub fn new(frame_len: usize) -> Self {
+        Self {
+            hasher: MerkleHasher::new(18),
+            packer: BabyBearPacker::new(3, true),
+            frame_len,
+        }
+    }
+
+    pub fn verify_checkpoint(
+        &self,
+        public_key: &PublicKey,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), LiveError> {
+        let payload =
+            Checkpoint::signing_payload(checkpoint.num_chunks, self.frame_len, &checkpoint.root);
+        public_key
+            .verify(&payload, &checkpoint.signature)
+            .map_err(LiveError::Signature)
+    }
+
+    /// Checks that `published`, the frames of `proof.chunks` back to back, were signed by
+    /// `public_key` in `checkpoint`.
+    pub fn verify_span(
+        &self,
+        public_key: &PublicKey,
+        checkpoint: &Checkpoint,
+        proof: &SpanProof,
+        published: &[u8],
+    ) -> Result<(), LiveError> {
+        self.verify_checkpoint(public_key, checkpoint)?;
+        let chunks = &proof.chunks;
+        if chunks.start >= chunks.end || chunks.end > checkpoint.num_chunks {
+            return Err(LiveError::InvalidChunks);
+        }
+        let num_frames: usize = proof.chunk_frames.iter().sum();
+        if proof.chunk_frames.len() != chunks.len()
+            || proof.chunk_frames.contains(&0)
+            || published.len() != num_frames * self.frame_len
+        {
+            return Err(LiveError::MalformedProof);
+        }
+
+        let frame_size = self.packer.elements_per_frame(self.frame_len);
+        let mut rest = published;
+        let mut leaves = Vec::with_capacity(chunks.len());
+        for &chunk_frames in &proof.chunk_frames {
+            let (frames, tail) = rest.split_at(chunk_frames * self.frame_len);
+            let packed = self.packer.pack(frames, self.frame_len);
+            let chunk_root = FrameTree::new(&self.hasher, &packed, frame_size).root();
+            leaves.push(chunk_leaf(&self.hasher, chunk_root, chunk_frames));
+            rest = tail;
+        }
+
+        let peak_ranges = peaks(checkpoint.num_chunks);
+        if proof.peaks.len() != peak_ranges.len() {
+            return Err(LiveError::MalformedProof);
+        }
+        let mut peak_roots = Vec::with_capacity(peak_ranges.len());
+        for (peak, peak_proof) in peak_ranges.iter().zip(&proof.peaks) {
+            let root = match (overlap(peak, chunks), peak_proof) {
+                (None, PeakProof::Digest(digest)) => *digest,
+                (Some(opened), PeakProof::Opened(multiproof))
+                    if multiproof.ranges == [opened.clone()] =>
+                {
+                    let start = peak.start.max(chunks.start) - chunks.start;
+                    let digests = leaves[start..start + multiproof.ranges[0].len()].to_vec();
+                    multiproof
+                        .root_from_leaves(&self.hasher, peak.len(), digests)
+                        .map_err(|_| LiveError::MalformedProof)?
+                }
+                _ => return Err(LiveError::MalformedProof),
+            };
+            peak_roots.push(root);
+        }
+
+        if bag_peaks(&self.hasher, &peak_roots) == checkpoint.root {
+            Ok(())
+        } else {
+            Err(LiveError::RootMismatch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::{SecretKey, SignatureAlgorithm};
+
+    const FRAME_LEN: usize = 12;
+    // Chunks of uneven length, like GOPs.
+    const CHUNK_FRAMES: [usize; 7] = [3, 2, 4, 1, 3, 3, 2];
+
+    fn key(seed: u8) -> SecretKey {
+        SecretKey::from_seed(SignatureAlgorithm::Ed25519, [seed; 32])
+    }
+
+    fn chunk(index: usize) -> Vec<u8> {
+        (0..CHUNK_FRAMES[index] * FRAME_LEN)
+            .map(|i| (index * 97 + i * 31) as u8)
+            .collect()
+    }
+
+    fn record() -> (LiveSigner<SecretKey>, Vec<Checkpoint>) {
+        let mut signer = LiveSigner::new(key(1), FRAME_LEN);
+        let checkpoints = (0..CHUNK_FRAMES.len())
+            .map(|i| signer.push_chunk(&chunk(i)))
+            .collect();
+        (signer, checkpoints)
+    }
+
+    fn published(chunks: Range<usize>) -> Vec<u8> {
+        chunks.flat_map(chunk).collect()
+    }
+
+    #[test]
+    fn spans_verify_against_every_covering_checkpoint() {
+        let (signer, checkpoints) = record();
+        let verifier = LiveVerifier::new(FRAME_LEN);
+        let public_key = key(1).public_key();
+        for checkpoint in &checkpoints {
+            verifier.verify_checkpoint(&public_key, checkpoint).unwrap();
+            let n = checkpoint.num_chunks;
+            for chunks in [0..n, n - 1..n, 0..1, (n / 2)..n] {
+                let proof = signer.prove_span(n, chunks.clone()).unwrap();
+                verifier
+                    .verify_span(&public_key, checkpoint, &proof, &published(chunks))
+                    .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn peaks_are_kept_incrementally() {
+        let (signer, _) = record();
+        let rebuilt: Vec<Digest> = peaks(signer.num_chunks())
+            .iter()
+            .map(|peak| signer.peak_tree(peak).root())
+            .collect();
+        assert_eq!(signer.peak_roots, rebuilt);
+    }
+
+    #[test]
+    fn checkpoints_and_proofs_round_trip_through_bincode() {
+        let (signer, checkpoints) = record();
+        let proof = signer.prove_span(6, 2..5).unwrap();
+        let checkpoint: Checkpoint =
+            bincode::deserialize(&bincode::serialize(&checkpoints[5]).unwrap()).unwrap();
+        let decoded: SpanProof =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(checkpoint, checkpoints[5]);
+        assert_eq!(decoded, proof);
+        LiveVerifier::new(FRAME_LEN)
+            .verify_span(
+                &key(1).public_key(),
+                &checkpoint,
+                &decoded,
+                &published(2..5),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn tampered_frame_is_rejected() {
+        let (signer, checkpoints) = record();
+        let proof = signer.prove_span(7, 2..5).unwrap();
+        let mut frames = published(2..5);
+        frames[30] ^= 1;
+        assert!(matches!(
+            LiveVerifier::new(FRAME_LEN).verify_span(
+                &key(1).public_key(),
+                &checkpoints[6],
+                &proof,
+                &frames
+            ),
+            Err(LiveError::RootMismatch)
+        ));
+    }
+
+    #[test]
+    fn proof_against_another_checkpoint_is_rejected() {
+        let (signer, checkpoints) = record();
+        let proof = signer.prove_span(5, 1..3).unwrap();
+        assert!(LiveVerifier::new(FRAME_LEN)
+            .verify_span(
+                &key(1).public_key(),
+                &checkpoints[6],
+                &proof,
+                &published(1..3)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn other_signer_is_rejected() {
+        let (signer, checkpoints) = record();
+        let proof = signer.prove_span(7, 1..3).unwrap();
+        assert!(matches!(
+            LiveVerifier::new(FRAME_LEN).verify_span(
+                &key(2).public_key(),
+                &checkpoints[6],
+                &proof,
+                &published(1..3)
+            ),
+            Err(LiveError::Signature(_))
+        ));
+    }
+
+    #[test]
+    fn spans_beyond_the_checkpoint_are_an_error() {
+        let (signer, _) = record();
+        assert!(matches!(
+            signer.prove_span(4, 3..5),
+            Err(LiveError::InvalidChunks)
+        ));
+        assert!(matches!(
+            signer.prove_span(8, 0..1),
+            Err(LiveError::InvalidChunks)
+        ));
+    }
+}
@@ -0,0 +1,396 @@
+// On-disk formats for signed videos and trimming proofs.
+//
+// A camera-side signer writes a `SignedManifest` (JSON) next to the video: it records everything a
+// verifier needs to recompute the commitment (field, PCS and FRI parameters, frame geometry and
+// packing), the commitment itself and a signature over all of it in any algorithm of
+// `crate::signing`. An editor that trims the video ships the trimmed frames together with a
+// `TrimProofFile` (bincode), which a verifier checks against the manifest without ever seeing the
+// frames that were cut.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::frames::ChannelOrder;
+use crate::packing::{GoldilocksPacker, PackableField, Packer};
+use crate::signing::{PublicKey, SignatureAlgorithm, VideoSigner};
+use crate::timecode::FrameRate;
+use crate::univariate::{
+    commitment_from_bytes, commitment_to_bytes, Commitment, FriParameters, TrimProof, Val,
+    POSEIDON_SEED,
+};
+
+pub const MANIFEST_VERSION: u32 = 3;
+pub const TRIM_PROOF_VERSION: u32 = 3;
+
+// Prepended to everything that is signed, so a manifest signature can't be replayed as a
+// signature over some other message format.
+const SIGNING_DOMAIN: &[u8] = b"video-trimming signed manifest";
+
+fn invalid_data<E: ToString>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+// Serializes byte strings as lowercase hex.
+pub(crate) mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcsParameters {
+    pub field: String,
+    pub extension_degree: usize,
+    pub pcs: String,
+    pub hash: String,
+    /// Seed the Poseidon2 round constants are sampled from.
+    pub hash_seed: u64,
+    pub fri: FriParameters,
+    /// The committed polynomial has `1 << log_degree` coefficients.
+    pub log_degree: usize,
+}
+
+impl PcsParameters {
+    /// Parameters of the Goldilocks/FRI commitment in `crate::univariate`.
+    pub fn univariate(fri: FriParameters, log_degree: usize) -> Self {
+        Self {
+            field: "goldilocks".to_string(),
+            extension_degree: 2,
+            pcs: "two-adic-fri".to_string(),
+            hash: "poseidon2".to_string(),
+            hash_seed: POSEIDON_SEED,
+            fri,
+            log_degree,
+        }
+    }
+}
+
+/// Geometry and packing of a signed video. Deserializing checks that the packing is one
+/// `GoldilocksPacker` supports, so a hostile manifest can't make `packer` panic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedGeometry")]
+pub struct VideoGeometry {
+    pub width: usize,
+    pub height: usize,
+    pub channel_order: ChannelOrder,
+    pub num_frames: usize,
+    pub frame_rate: FrameRate,
+    pub bytes_per_element: usize,
+    pub frame_aligned: bool,
+}
+
+// `VideoGeometry` as it is read, before its packing is checked.
+#[derive(Deserialize)]
+struct UncheckedGeometry {
+    width: usize,
+    height: usize,
+    channel_order: ChannelOrder,
+    num_frames: usize,
+    frame_rate: FrameRate,
+    bytes_per_element: usize,
+    frame_aligned: bool,
+}
+
+impl TryFrom<UncheckedGeometry> for VideoGeometry {
+    type Error = String;
+
+    fn try_from(geometry: UncheckedGeometry) -> Result<Self, String> {
+        let geometry = VideoGeometry {
+            width: geometry.width,
+            height: geometry.height,
+            channel_order: geometry.channel_order,
+            num_frames: geometry.num_frames,
+            frame_rate: geometry.frame_rate,
+            bytes_per_element: geometry.bytes_per_element,
+            frame_aligned: geometry.frame_aligned,
+        };
+        geometry.validate()?;
+        Ok(geometry)
+    }
+}
+
+impl VideoGeometry {
+    /// Checks that frames are non-empty and that the packing is supported and maps every frame
+    /// to a whole number of elements.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=Val::MAX_BYTES).contains(&self.bytes_per_element) {
+            return Err(format!(
+                "bytes_per_element must be between 1 and {}, not {}",
+                Val::MAX_BYTES,
+                self.bytes_per_element
+            ));
+        }
+        let pixels = self.width.checked_mul(self.height);
+        if pixels.and_then(|pixels| pixels.checked_mul(3)).is_none() || pixels == Some(0) {
+            return Err(format!(
+                "unsupported frame size {}x{}",
+                self.width, self.height
+            ));
+        }
+        if !self.frame_aligned && self.frame_len() % self.bytes_per_element != 0 {
+            return Err(format!(
+                "unaligned packing needs frames of a multiple of {} bytes",
+                self.bytes_per_element
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn frame_len(&self) -> usize {
+        self.channel_order.frame_len(self.width, self.height)
+    }
+
+    pub fn packer(&self) -> GoldilocksPacker {
+        GoldilocksPacker::new(self.bytes_per_element, self.frame_aligned)
+    }
+
+    /// Number of coefficients each frame occupies in the committed polynomial.
+    pub fn frame_size(&self) -> usize {
+        self.packer().elements_per_frame(self.frame_len())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedManifest {
+    pub version: u32,
+    pub pcs: PcsParameters,
+    pub geometry: VideoGeometry,
+    #[serde(with = "hex_bytes")]
+    pub commitment: Vec<u8>,
+    pub algorithm: SignatureAlgorithm,
+    #[serde(with = "hex_bytes")]
+    pub public_key: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub signature: Vec<u8>,
+}
+
+impl SignedManifest {
+    /// Signs `commitment` together with the parameters needed to interpret it.
+    pub fn sign(
+        signer: &dyn VideoSigner,
+        pcs: PcsParameters,
+        geometry: VideoGeometry,
+        commitment: &Commitment,
+    ) -> Self {
+        let commitment = commitment_to_bytes(commitment);
+        let public_key = signer.public_key();
+        let payload = Self::signing_payload(
+            MANIFEST_VERSION,
+            public_key.algorithm,
+            &pcs,
+            &geometry,
+            &commitment,
+        );
+
+        Self {
+            version: MANIFEST_VERSION,
+            pcs,
+            geometry,
+            commitment,
+            algorithm: public_key.algorithm,
+            public_key: public_key.bytes,
+            signature: signer.sign(&payload),
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            algorithm: self.algorithm,
+            bytes: self.public_key.clone(),
+        }
+    }
+
+    fn signing_payload(
+        version: u32,
+        algorithm: SignatureAlgorithm,
+        pcs: &PcsParameters,
+        geometry: &VideoGeometry,
+        commitment: &[u8],
+    ) -> Vec<u8> {
+        let mut payload = SIGNING_DOMAIN.to_vec();
+        payload.extend(version.to_le_bytes());
+        // Struct fields serialize in declaration order, so this encoding is deterministic.
+        payload.extend(serde_json::to_vec(&(algorithm, pcs, geometry)).unwrap());
+        payload.extend(commitment);
+        payload
+    }
+
+    /// Checks the signature and returns the signed commitment. Does not decide whether the
+    /// public key is trusted; callers compare `public_key` against their own list.
+    pub fn verify_signature(&self) -> io::Result<Commitment> {
+        if self.version != MANIFEST_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported manifest version {}",
+                self.version
+            )));
+        }
+        if self.pcs != PcsParameters::univariate(self.pcs.fri, self.pcs.log_degree) {
+            return Err(invalid_data(
+                "manifest uses an unsupported commitment scheme",
+            ));
+        }
+
+        let payload = Self::signing_payload(
+            self.version,
+            self.algorithm,
+            &self.pcs,
+            &self.geometry,
+            &self.commitment,
+        );
+        self.public_key()
+            .verify(&payload, &self.signature)
+            .map_err(invalid_data)?;
+
+        commitment_from_bytes(&self.commitment).ok_or_else(|| invalid_data("malformed commitment"))
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        serde_json::from_slice(&fs::read(path)?).map_err(invalid_data)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(invalid_data)?;
+        fs::write(path, json)
+    }
+}
+
+/// A trimming proof as written by the editor.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrimProofFile {
+    pub version: u32,
+    pub proof: TrimProof,
+}
+
+impl TrimProofFile {
+    pub fn new(proof: TrimProof) -> Self {
+        Self {
+            version: TRIM_PROOF_VERSION,
+            proof,
+        }
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file: Self = bincode::deserialize(&fs::read(path)?).map_err(invalid_data)?;
+        if file.version != TRIM_PROOF_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported trimming proof version {}",
+                file.version
+            )));
+        }
+        Ok(file)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, bincode::serialize(self).map_err(invalid_data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::SecretKey;
+    use serde_json::json;
+
+    fn geometry() -> VideoGeometry {
+        VideoGeometry {
+            width: 4,
+            height: 3,
+            channel_order: ChannelOrder::Rgb,
+            num_frames: 5,
+            frame_rate: FrameRate::new(25, 1).unwrap(),
+            bytes_per_element: 6,
+            frame_aligned: true,
+        }
+    }
+
+    fn commitment() -> Commitment {
+        commitment_from_bytes(&(0..32).collect::<Vec<u8>>()).unwrap()
+    }
+
+    fn signed(key: &SecretKey) -> SignedManifest {
+        let pcs = PcsParameters::univariate(FriParameters::default(), 5);
+        SignedManifest::sign(key, pcs, geometry(), &commitment())
+    }
+
+    #[test]
+    fn signed_manifest_verifies() {
+        for algorithm in SignatureAlgorithm::ALL {
+            let manifest = signed(&SecretKey::generate(algorithm));
+            assert_eq!(manifest.verify_signature().unwrap(), commitment());
+        }
+    }
+
+    #[test]
+    fn json_round_trip_keeps_hex_fields() {
+        let manifest = signed(&SecretKey::generate(SignatureAlgorithm::Ed25519));
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["commitment"], hex::encode(&manifest.commitment));
+        assert_eq!(json["signature"], hex::encode(&manifest.signature));
+
+        let parsed: SignedManifest = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.commitment, manifest.commitment);
+        assert_eq!(parsed.public_key, manifest.public_key);
+        assert_eq!(parsed.geometry, manifest.geometry);
+        assert_eq!(parsed.verify_signature().unwrap(), commitment());
+
+        let mut json = serde_json::to_value(&manifest).unwrap();
+        json["signature"] = json!("not hex");
+        assert!(serde_json::from_value::<SignedManifest>(json).is_err());
+    }
+
+    #[test]
+    fn tampered_manifest_is_rejected() {
+        let manifest = signed(&SecretKey::generate(SignatureAlgorithm::Ed25519));
+        let tampered: [fn(&mut SignedManifest); 7] = [
+            |m| m.geometry.num_frames += 1,
+            |m| m.geometry.bytes_per_element = 5,
+            |m| m.pcs.log_degree += 1,
+            |m| m.pcs.fri.num_queries += 1,
+            |m| m.commitment[0] ^= 1,
+            |m| m.signature[0] ^= 1,
+            |m| m.version += 1,
+        ];
+        for tamper in tampered {
+            let mut manifest = manifest.clone();
+            tamper(&mut manifest);
+            assert!(manifest.verify_signature().is_err());
+        }
+
+        // Signed by one key, presented with another.
+        let mut manifest = manifest.clone();
+        manifest.public_key = SecretKey::generate(SignatureAlgorithm::Ed25519)
+            .public_key()
+            .bytes;
+        assert!(manifest.verify_signature().is_err());
+    }
+
+    #[test]
+    fn unsupported_packing_is_rejected_on_read() {
+        let manifest = signed(&SecretKey::generate(SignatureAlgorithm::Ed25519));
+        for (field, value) in [
+            ("bytes_per_element", json!(0)),
+            ("bytes_per_element", json!(8)),
+            ("width", json!(0)),
+        ] {
+            let mut json = serde_json::to_value(&manifest).unwrap();
+            json["geometry"][field] = value;
+            assert!(serde_json::from_value::<SignedManifest>(json).is_err());
+        }
+
+        // Unaligned packing needs whole elements per frame: 36-byte frames don't split into 5s.
+        let mut json = serde_json::to_value(&manifest).unwrap();
+        json["geometry"]["frame_aligned"] = json!(false);
+        json["geometry"]["bytes_per_element"] = json!(5);
+        assert!(serde_json::from_value::<SignedManifest>(json).is_err());
+    }
+}
@@ -0,0 +1,567 @@
+// Frame-level Merkle commitments with multiproofs for trimming.
+//
+// Each frame's packed BabyBear elements are hashed into one leaf with a Poseidon2 sponge, the
+// leaves are padded with zero digests to a power of two and compressed pairwise up to the root.
+// Revealing a set of frames then needs only the siblings that the verifier cannot recompute
+// itself: for a contiguous range of n frames that is at most two nodes per level, instead of the
+// n full authentication paths that opening every frame separately costs.
+//
+// Neither the tree nor the root needs the whole video in memory: `FrameTree::from_source` hashes
+// one frame at a time and keeps only the digests, and `RootBuilder` keeps O(log n) of them.
+
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::SeedableRng;
+use p3_baby_bear::{BabyBear, DiffusionMatrixBabyBear};
+use p3_field::{AbstractField, PrimeField32};
+use p3_poseidon2::{Poseidon2, Poseidon2ExternalMatrixGeneral};
+use p3_symmetric::{
+    CryptographicHasher, PaddingFreeSponge, PseudoCompressionFunction, TruncatedPermutation,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::frames::VideoSource;
+use crate::packing::Packer;
+
+type F = BabyBear;
+type Perm = Poseidon2<F, Poseidon2ExternalMatrixGeneral, DiffusionMatrixBabyBear, 16, 7>;
+type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+
+pub const DIGEST_ELEMS: usize = 8;
+pub type Digest = [F; DIGEST_ELEMS];
+
+// Bytes of one serialized field element.
+const ELEMENT_BYTES: usize = 4;
+
+#[derive(Debug)]
+pub enum MerkleError {
+    /// The requested frames are empty, unsorted or outside the video.
+    InvalidFrames,
+    /// The proof or the published frames do not have the expected shape.
+    MalformedProof,
+    /// The recomputed root differs from the signed one.
+    RootMismatch,
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::InvalidFrames => write!(f, "frames are not inside the video"),
+            MerkleError::MalformedProof => write!(f, "malformed Merkle multiproof"),
+            MerkleError::RootMismatch => {
+                write!(f, "published frames do not match the signed root")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MerkleError {}
+
+/// The leaf hash and node compression of a frame tree.
+#[derive(Clone)]
+pub struct MerkleHasher {
+    hash: MyHash,
+    compress: MyCompress,
+}
+
+impl MerkleHasher {
+    /// Samples the Poseidon2 round constants from `seed`.
+    pub fn new(seed: u64) -> Self {
+        let perm = Perm::new_from_rng_128(
+            Poseidon2ExternalMatrixGeneral,
+            DiffusionMatrixBabyBear::default(),
+            &mut StdRng::seed_from_u64(seed),
+        );
+        Self {
+            hash: MyHash::new(perm.clone()),
+            compress: MyCompress::new(perm),
+        }
+    }
+
+    pub fn hash_frame(&self, frame: &[F]) -> Digest {
+        self.hash.hash_slice(frame)
+    }
+
+    pub(crate) fn compress(&self, left: Digest, right: Digest) -> Digest {
+        self.compress.compress([left, right])
+    }
+}
+
+/// A Merkle tree with one leaf per frame.
+pub struct FrameTree {
+    num_frames: usize,
+    // layers[0] holds the padded leaves, the last layer the root.
+    layers: Vec<Vec<Digest>>,
+}
+
+impl FrameTree {
+    /// Builds the tree over `video`, the packed frames of `frame_size` elements back to back.
+    pub fn new(hasher: &MerkleHasher, video: &[F], frame_size: usize) -> Self {
+        assert!(
+            frame_size > 0 && !video.is_empty() && video.len() % frame_size == 0,
+            "video is not a whole number of frames"
+        );
+        let leaves = video
+            .chunks(frame_size)
+            .map(|frame| hasher.hash_frame(frame))
+            .collect();
+        Self::from_leaves(hasher, leaves)
+    }
+
+    /// Builds the same tree as `new` while reading one frame of `source` at a time, so only the
+    /// digests are ever held in memory.
+    pub fn from_source<P: Packer<Element = F>>(
+        hasher: &MerkleHasher,
+        source: &VideoSource,
+        packer: &P,
+    ) -> io::Result<Self> {
+        assert!(
+            packer.frame_aligned(),
+            "frames must be packed independently"
+        );
+        let mut leaves = Vec::with_capacity(source.num_frames());
+        for frame in source.frames() {
+            let frame = frame?;
+            leaves.push(hasher.hash_frame(&packer.pack(&frame, frame.len())));
+        }
+        Ok(Self::from_leaves(hasher, leaves))
+    }
+
+    /// Builds the tree over already hashed leaves.
+    pub(crate) fn from_leaves(hasher: &MerkleHasher, mut leaves: Vec<Digest>) -> Self {
+        assert!(!leaves.is_empty(), "video has no frames");
+        let num_frames = leaves.len();
+        leaves.resize(num_frames.next_power_of_two(), [F::zero(); DIGEST_ELEMS]);
+
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hasher.compress(pair[0], pair[1]))
+                .collect();
+            layers.push(next);
+        }
+        Self { num_frames, layers }
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// The commitment to sign.
+    pub fn root(&self) -> Digest {
+        self.layers.last().unwrap()[0]
+    }
+
+    /// Proves membership of the frames in `ranges`, which must be non-empty, sorted and disjoint.
+    pub fn prove(&self, ranges: &[Range<usize>]) -> Result<MultiProof, MerkleError> {
+        let leaves = leaf_indices(ranges, self.num_frames)?;
+        let mut siblings = Vec::new();
+        walk_up(leaves, self.layers.len() - 1, |level, index| {
+            siblings.push(self.layers[level][index]);
+        });
+        Ok(MultiProof {
+            ranges: ranges.to_vec(),
+            siblings,
+        })
+    }
+}
+
+/// Computes the root of a `FrameTree` from frames pushed one at a time, keeping one pending
+/// digest per level of the tree.
+pub struct RootBuilder<'a> {
+    hasher: &'a MerkleHasher,
+    num_frames: usize,
+    // pending[level] is the root of a complete subtree of 2^level leaves still waiting for its
+    // right sibling.
+    pending: Vec<Option<Digest>>,
+}
+
+impl<'a> RootBuilder<'a> {
+    pub fn new(hasher: &'a MerkleHasher) -> Self {
+        Self {
+            hasher,
+            num_frames: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: &[F]) {
+        let leaf = self.hasher.hash_frame(frame);
+        self.push_leaf(leaf);
+        self.num_frames += 1;
+    }
+
+    fn push_leaf(&mut self, mut node: Digest) {
+        for slot in self.pending.iter_mut() {
+            match slot.take() {
+                Some(left) => node = self.hasher.compress(left, node),
+                None => {
+                    *slot = Some(node);
+                    return;
+                }
+            }
+        }
+        self.pending.push(Some(node));
+    }
+
+    /// The root `FrameTree::new` would compute over the pushed frames.
+    pub fn finish(mut self) -> Digest {
+        assert!(self.num_frames > 0, "video has no frames");
+        for _ in self.num_frames..self.num_frames.next_power_of_two() {
+            self.push_leaf([F::zero(); DIGEST_ELEMS]);
+        }
+        self.pending.pop().unwrap().unwrap()
+    }
+}
+
+// Expands `ranges` into leaf indices, checking they are sorted, disjoint and inside the video.
+fn leaf_indices(ranges: &[Range<usize>], num_frames: usize) -> Result<Vec<usize>, MerkleError> {
+    let mut leaves = Vec::new();
+    let mut previous_end = 0;
+    for (i, range) in ranges.iter().enumerate() {
+        if range.start >= range.end
+            || range.end > num_frames
+            || (i > 0 && range.start < previous_end)
+        {
+            return Err(MerkleError::InvalidFrames);
+        }
+        leaves.extend(range.clone());
+        previous_end = range.end;
+    }
+    if leaves.is_empty() {
+        return Err(MerkleError::InvalidFrames);
+    }
+    Ok(leaves)
+}
+
+// Walks the sorted node indices `known` up `depth` levels, calling `sibling(level, index)` for
+// every sibling that is not itself known, in the order the proof stores them.
+fn walk_up(mut known: Vec<usize>, depth: usize, mut sibling: impl FnMut(usize, usize)) {
+    for level in 0..depth {
+        let mut parents = Vec::with_capacity(known.len().div_ceil(2));
+        let mut i = 0;
+        while i < known.len() {
+            let index = known[i];
+            if index % 2 == 0 && known.get(i + 1) == Some(&(index + 1)) {
+                i += 2;
+            } else {
+                sibling(level, index ^ 1);
+                i += 1;
+            }
+            parents.push(index / 2);
+        }
+        known = parents;
+    }
+}
+
+/// Membership proof for a set of frames, sharing every internal node between them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiProof {
+    pub ranges: Vec<Range<usize>>,
+    siblings: Vec<Digest>,
+}
+
+impl MultiProof {
+    /// Number of sibling digests in the proof.
+    pub fn num_siblings(&self) -> usize {
+        self.siblings.len()
+    }
+
+    /// Serializes the proof as little-endian u32s: the ranges and then the siblings, each list
+    /// prefixed by its length.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut words = vec![self.ranges.len() as u32];
+        for range in &self.ranges {
+            words.extend([range.start as u32, range.end as u32]);
+        }
+        words.push(self.siblings.len() as u32);
+        for digest in &self.siblings {
+            words.extend(digest.iter().map(|x| x.as_canonical_u32()));
+        }
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleError> {
+        if bytes.len() % ELEMENT_BYTES != 0 {
+            return Err(MerkleError::MalformedProof);
+        }
+        let mut words = bytes
+            .chunks(ELEMENT_BYTES)
+            .map(|w| u32::from_le_bytes(w.try_into().unwrap()));
+        let mut next = || words.next().ok_or(MerkleError::MalformedProof);
+
+        let num_ranges = next()? as usize;
+        let mut ranges = Vec::new();
+        for _ in 0..num_ranges {
+            ranges.push(next()? as usize..next()? as usize);
+        }
+        let num_siblings = next()? as usize;
+        let mut siblings = Vec::new();
+        for _ in 0..num_siblings {
+            let mut digest = [F::zero(); DIGEST_ELEMS];
+            for x in digest.iter_mut() {
+                let word = next()?;
+                if word >= F::ORDER_U32 {
+                    return Err(MerkleError::MalformedProof);
+                }
+                *x = F::from_canonical_u32(word);
+            }
+            siblings.push(digest);
+        }
+        if next().is_ok() {
+            return Err(MerkleError::MalformedProof);
+        }
+        Ok(Self { ranges, siblings })
+    }
+
+    /// Size of `to_bytes`, without serializing.
+    pub fn size_in_bytes(&self) -> usize {
+        ELEMENT_BYTES * (2 + 2 * self.ranges.len() + DIGEST_ELEMS * self.siblings.len())
+    }
+
+    /// Checks the proof against the signed `root` of a `num_frames` frame video and
+    /// `published`, the packed frames of `proof.ranges` back to back.
+    pub fn verify(
+        &self,
+        hasher: &MerkleHasher,
+        root: &Digest,
+        num_frames: usize,
+        published: &[F],
+        frame_size: usize,
+    ) -> Result<(), MerkleError> {
+        let leaves = leaf_indices(&self.ranges, num_frames)?;
+        if frame_size == 0 || published.len() != leaves.len() * frame_size {
+            return Err(MerkleError::MalformedProof);
+        }
+        let digests = published
+            .chunks(frame_size)
+            .map(|frame| hasher.hash_frame(frame))
+            .collect();
+        if self.root_from_leaves(hasher, num_frames, digests)? == *root {
+            Ok(())
+        } else {
+            Err(MerkleError::RootMismatch)
+        }
+    }
+
+    /// Recomputes the root of a tree of `num_leaves` leaves from the digests of the leaves in
+    /// `self.ranges`, in order.
+    pub(crate) fn root_from_leaves(
+        &self,
+        hasher: &MerkleHasher,
+        num_leaves: usize,
+        digests: Vec<Digest>,
+    ) -> Result<Digest, MerkleError> {
+        let leaves = leaf_indices(&self.ranges, num_leaves)?;
+        if digests.len() != leaves.len() {
+            return Err(MerkleError::MalformedProof);
+        }
+        let depth = num_leaves.next_power_of_two().trailing_zeros() as usize;
+
+        // Which siblings the proof must contain depends only on the leaf indices, so the
+        // prover's walk tells us how many to expect.
+        let mut expected_siblings = 0;
+        walk_up(leaves.clone(), depth, |_, _| expected_siblings += 1);
+        if expected_siblings != self.siblings.len() {
+            return Err(MerkleError::MalformedProof);
+        }
+
+        let mut nodes: Vec<(usize, Digest)> = leaves.into_iter().zip(digests).collect();
+        let mut siblings = self.siblings.iter();
+        for _ in 0..depth {
+            let mut parents = Vec::with_capacity(nodes.len().div_ceil(2));
+            let mut i = 0;
+            while i < nodes.len() {
+                let (index, digest) = nodes[i];
+                let sibling = match nodes.get(i + 1) {
+                    Some(&(next, next_digest)) if index % 2 == 0 && next == index + 1 => {
+                        i += 2;
+                        next_digest
+                    }
+                    _ => {
+                        i += 1;
+                        *siblings.next().unwrap()
+                    }
+                };
+                let parent = if index % 2 == 0 {
+                    hasher.compress(digest, sibling)
+                } else {
+                    hasher.compress(sibling, digest)
+                };
+                parents.push((index / 2, parent));
+            }
+            nodes = parents;
+        }
+        Ok(nodes[0].1)
+    }
+}
+
+/// Size in bytes of opening `num_opened` frames one authentication path at a time, for
+/// comparison with `MultiProof::size_in_bytes`.
+pub fn individual_paths_size(num_frames: usize, num_opened: usize) -> usize {
+    let depth = num_frames.next_power_of_two().trailing_zeros() as usize;
+    num_opened * depth * DIGEST_ELEMS * ELEMENT_BYTES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frames::ChannelOrder;
+    use crate::packing::BabyBearPacker;
+
+    const FRAME_SIZE: usize = 6;
+    const NUM_FRAMES: usize = 13;
+
+    fn test_video() -> Vec<F> {
+        (0..(FRAME_SIZE * NUM_FRAMES) as u32)
+            .map(|i| F::from_canonical_u32(i * 7919 + 3))
+            .collect()
+    }
+
+    fn published(video: &[F], ranges: &[Range<usize>]) -> Vec<F> {
+        ranges
+            .iter()
+            .flat_map(|r| video[r.start * FRAME_SIZE..r.end * FRAME_SIZE].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn contiguous_and_sparse_frames_verify() {
+        let hasher = MerkleHasher::new(18);
+        let video = test_video();
+        let tree = FrameTree::new(&hasher, &video, FRAME_SIZE);
+        for ranges in [
+            vec![0..1],
+            vec![12..13],
+            vec![3..9],
+            vec![0..13],
+            vec![1..2, 5..7, 11..13],
+        ] {
+            let proof = tree.prove(&ranges).unwrap();
+            proof
+                .verify(
+                    &hasher,
+                    &tree.root(),
+                    NUM_FRAMES,
+                    &published(&video, &ranges),
+                    FRAME_SIZE,
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn tampered_frame_is_rejected() {
+        let hasher = MerkleHasher::new(18);
+        let video = test_video();
+        let tree = FrameTree::new(&hasher, &video, FRAME_SIZE);
+        let proof = tree.prove(&[3..9]).unwrap();
+
+        let mut frames = published(&video, &[3..9]);
+        frames[10] += F::one();
+        assert!(matches!(
+            proof.verify(&hasher, &tree.root(), NUM_FRAMES, &frames, FRAME_SIZE),
+            Err(MerkleError::RootMismatch)
+        ));
+    }
+
+    #[test]
+    fn shifted_range_is_rejected() {
+        let hasher = MerkleHasher::new(18);
+        let video = test_video();
+        let tree = FrameTree::new(&hasher, &video, FRAME_SIZE);
+        let mut proof = tree.prove(&[3..9]).unwrap();
+
+        proof.ranges = vec![4..10];
+        let result = proof.verify(
+            &hasher,
+            &tree.root(),
+            NUM_FRAMES,
+            &published(&video, &[4..10]),
+            FRAME_SIZE,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn proofs_round_trip_through_bytes() {
+        let hasher = MerkleHasher::new(18);
+        let video = test_video();
+        let tree = FrameTree::new(&hasher, &video, FRAME_SIZE);
+        let proof = tree.prove(&[1..2, 5..7, 11..13]).unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), proof.size_in_bytes());
+        assert_eq!(MultiProof::from_bytes(&bytes).unwrap(), proof);
+        assert!(MultiProof::from_bytes(&bytes[..bytes.len() - 4]).is_err());
+    }
+
+    #[test]
+    fn ranges_share_internal_nodes() {
+        let hasher = MerkleHasher::new(18);
+        let tree = FrameTree::new(&hasher, &test_video(), FRAME_SIZE);
+        // Frames 4..8 are a whole subtree: only the siblings above it are needed.
+        assert_eq!(tree.prove(&[4..8]).unwrap().num_siblings(), 2);
+        let proof = tree.prove(&[3..9]).unwrap();
+        assert!(proof.size_in_bytes() < individual_paths_size(NUM_FRAMES, 6));
+    }
+
+    #[test]
+    fn streamed_root_matches_in_memory_tree() {
+        let hasher = MerkleHasher::new(18);
+        let video = test_video();
+        for num_frames in [1, 2, 5, 8, 13] {
+            let frames = &video[..num_frames * FRAME_SIZE];
+            let mut builder = RootBuilder::new(&hasher);
+            for frame in frames.chunks(FRAME_SIZE) {
+                builder.push_frame(frame);
+            }
+            assert_eq!(
+                builder.finish(),
+                FrameTree::new(&hasher, frames, FRAME_SIZE).root()
+            );
+        }
+    }
+
+    #[test]
+    fn tree_from_source_matches_in_memory_tree() {
+        let hasher = MerkleHasher::new(18);
+        let packer = BabyBearPacker::new(3, true);
+        // 5x2 gray frames: 10 bytes pack into 4 elements with a zero-padded last one.
+        let frames: Vec<Vec<u8>> = (0..7u8)
+            .map(|f| (0..10).map(|i| f * 31 + i).collect())
+            .collect();
+        let source = VideoSource::from_frames(5, 2, ChannelOrder::Gray, frames).unwrap();
+
+        let streamed = FrameTree::from_source(&hasher, &source, &packer).unwrap();
+        let video = packer.pack(&source.read_all().unwrap(), source.frame_len());
+        let in_memory = FrameTree::new(&hasher, &video, packer.elements_per_frame(10));
+        assert_eq!(streamed.root(), in_memory.root());
+        assert_eq!(streamed.num_frames(), 7);
+    }
+
+    #[test]
+    fn invalid_ranges_are_an_error() {
+        let hasher = MerkleHasher::new(18);
+        let tree = FrameTree::new(&hasher, &test_video(), FRAME_SIZE);
+        for ranges in [
+            vec![],
+            vec![3..3],
+            vec![10..14],
+            vec![5..7, 6..8],
+            vec![5..7, 1..2],
+        ] {
+            assert!(matches!(
+                tree.prove(&ranges),
+                Err(MerkleError::InvalidFrames)
+            ));
+        }
+    }
+}
@@ -0,0 +1,365 @@
+// Packing of raw pixel bytes into field elements.
+//
+// Every commitment in this crate is made to a vector of field elements obtained by packing the
+// planar frame bytes little-endian, `bytes_per_element` at a time. A commitment can only be
+// reproduced by another tool if both sides agree on the field, the number of bytes per element and
+// whether elements may straddle frame boundaries, so all of that lives in a `Packer`.
+
+use std::io;
+use std::marker::PhantomData;
+
+use ark_ff::{BigInteger, PrimeField};
+use p3_baby_bear::BabyBear;
+use p3_field::{AbstractField, PrimeField32, PrimeField64};
+use p3_field_git::{PrimeCharacteristicRing, PrimeField64 as CenoPrimeField64};
+use p3_goldilocks::Goldilocks;
+use p3_goldilocks_git::Goldilocks as CenoGoldilocks;
+use p3_mersenne_31::Mersenne31;
+
+use crate::frames::VideoSource;
+
+/// A field whose elements can hold a fixed number of little-endian bytes without reduction.
+pub trait PackableField: Copy {
+    /// Largest number of bytes that always fits below the modulus.
+    const MAX_BYTES: usize;
+
+    /// Interprets at most `MAX_BYTES` bytes as a little-endian integer.
+    fn from_le_bytes(bytes: &[u8]) -> Self;
+
+    /// Writes the low `out.len()` bytes of the canonical representative, little-endian.
+    fn write_le_bytes(&self, out: &mut [u8]);
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0u64, |acc, byte| (acc << 8) | *byte as u64)
+}
+
+impl PackableField for Goldilocks {
+    const MAX_BYTES: usize = 7;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Goldilocks::from_canonical_u64(le_u64(bytes))
+    }
+
+    fn write_le_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.as_canonical_u64().to_le_bytes()[..out.len()]);
+    }
+}
+
+impl PackableField for CenoGoldilocks {
+    const MAX_BYTES: usize = 7;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        CenoGoldilocks::from_u64(le_u64(bytes))
+    }
+
+    fn write_le_bytes(&self, out: &mut [u8]) {
+        let value = CenoPrimeField64::as_canonical_u64(self);
+        out.copy_from_slice(&value.to_le_bytes()[..out.len()]);
+    }
+}
+
+impl PackableField for BabyBear {
+    const MAX_BYTES: usize = 3;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        BabyBear::from_canonical_u32(le_u64(bytes) as u32)
+    }
+
+    fn write_le_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.as_canonical_u32().to_le_bytes()[..out.len()]);
+    }
+}
+
+impl PackableField for Mersenne31 {
+    const MAX_BYTES: usize = 3;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        Mersenne31::from_canonical_u32(le_u64(bytes) as u32)
+    }
+
+    fn write_le_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.as_canonical_u32().to_le_bytes()[..out.len()]);
+    }
+}
+
+impl PackableField for blstrs::Scalar {
+    const MAX_BYTES: usize = 31;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        let mut repr = [0u8; 32];
+        repr[..bytes.len()].copy_from_slice(bytes);
+        blstrs::Scalar::from_bytes_le(&repr).unwrap()
+    }
+
+    fn write_le_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_bytes_le()[..out.len()]);
+    }
+}
+
+impl PackableField for ark_bls12_381::Fr {
+    const MAX_BYTES: usize = 31;
+
+    fn from_le_bytes(bytes: &[u8]) -> Self {
+        ark_bls12_381::Fr::from_le_bytes_mod_order(bytes)
+    }
+
+    fn write_le_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.into_bigint().to_bytes_le()[..out.len()]);
+    }
+}
+
+/// Converts between planar frame bytes and the field elements a commitment is made to.
+pub trait Packer {
+    type Element: Copy;
+
+    fn bytes_per_element(&self) -> usize;
+
+    /// If set, every frame starts a fresh element and the last element of each frame is
+    /// zero-padded, so no element straddles two frames.
+    fn frame_aligned(&self) -> bool;
+
+    fn pack_element(&self, bytes: &[u8]) -> Self::Element;
+
+    fn unpack_element(&self, element: &Self::Element, out: &mut [u8]);
+
+    /// Number of elements a single frame of `frame_len` bytes packs into. For unaligned packers
+    /// this is only exact when `frame_len` is a multiple of `bytes_per_element`.
+    fn elements_per_frame(&self, frame_len: usize) -> usize {
+        frame_len.div_ceil(self.bytes_per_element())
+    }
+
+    /// Packs `video`, a concatenation of frames of `frame_len` bytes each.
+    fn pack(&self, video: &[u8], frame_len: usize) -> Vec<Self::Element> {
+        let bytes_per_element = self.bytes_per_element();
+        if self.frame_aligned() {
+            video
+                .chunks(frame_len)
+                .flat_map(|frame| frame.chunks(bytes_per_element))
+                .map(|chunk| self.pack_element(chunk))
+                .collect()
+        } else {
+            video
+                .chunks(bytes_per_element)
+                .map(|chunk| self.pack_element(chunk))
+                .collect()
+        }
+    }
+
+    /// Packs every frame of `source`, reading one frame at a time. Produces the same elements as
+    /// `pack` on the concatenated video.
+    fn pack_source(&self, source: &VideoSource) -> io::Result<Vec<Self::Element>> {
+        let bytes_per_element = self.bytes_per_element();
+        let mut elements =
+            Vec::with_capacity(source.num_frames() * self.elements_per_frame(source.frame_len()));
+        // Bytes of the previous frame that did not fill a whole element (unaligned only).
+        let mut carry = Vec::with_capacity(bytes_per_element);
+
+        for frame in source.frames() {
+            let frame = frame?;
+            if self.frame_aligned() {
+                elements.extend(self.pack(&frame, frame.len()));
+                continue;
+            }
+
+            let mut rest = &frame[..];
+            if !carry.is_empty() {
+                let take = (bytes_per_element - carry.len()).min(rest.len());
+                carry.extend_from_slice(&rest[..take]);
+                rest = &rest[take..];
+                if carry.len() < bytes_per_element {
+                    continue;
+                }
+                elements.push(self.pack_element(&carry));
+                carry.clear();
+            }
+            let whole = rest.len() - rest.len() % bytes_per_element;
+            elements.extend(
+                rest[..whole]
+                    .chunks_exact(bytes_per_element)
+                    .map(|chunk| self.pack_element(chunk)),
+            );
+            carry.extend_from_slice(&rest[whole..]);
+        }
+        if !carry.is_empty() {
+            elements.push(self.pack_element(&carry));
+        }
+
+        Ok(elements)
+    }
+
+    /// Inverse of `pack`: recovers the `num_frames * frame_len` video bytes.
+    fn unpack(&self, elements: &[Self::Element], frame_len: usize, num_frames: usize) -> Vec<u8> {
+        let bytes_per_element = self.bytes_per_element();
+        let mut video = Vec::with_capacity(frame_len * num_frames);
+        let mut buf = vec![0u8; bytes_per_element];
+
+        if self.frame_aligned() {
+            let elements_per_frame = self.elements_per_frame(frame_len);
+            for frame in elements.chunks(elements_per_frame).take(num_frames) {
+                let start = video.len();
+                for element in frame {
+                    self.unpack_element(element, &mut buf);
+                    video.extend_from_slice(&buf);
+                }
+                video.truncate(start + frame_len);
+            }
+        } else {
+            for element in elements {
+                self.unpack_element(element, &mut buf);
+                video.extend_from_slice(&buf);
+            }
+            video.truncate(frame_len * num_frames);
+        }
+
+        video
+    }
+}
+
+/// Packs `bytes_per_element` little-endian bytes into each element of `F`.
+#[derive(Clone, Copy, Debug)]
+pub struct FieldPacker<F> {
+    bytes_per_element: usize,
+    frame_aligned: bool,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PackableField> FieldPacker<F> {
+    pub fn new(bytes_per_element: usize, frame_aligned: bool) -> Self {
+        assert!(
+            bytes_per_element >= 1 && bytes_per_element <= F::MAX_BYTES,
+            "this field holds between 1 and {} bytes per element, got {}",
+            F::MAX_BYTES,
+            bytes_per_element
+        );
+        Self {
+            bytes_per_element,
+            frame_aligned,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PackableField> Packer for FieldPacker<F> {
+    type Element = F;
+
+    fn bytes_per_element(&self) -> usize {
+        self.bytes_per_element
+    }
+
+    fn frame_aligned(&self) -> bool {
+        self.frame_aligned
+    }
+
+    fn pack_element(&self, bytes: &[u8]) -> F {
+        F::from_le_bytes(bytes)
+    }
+
+    fn unpack_element(&self, element: &F, out: &mut [u8]) {
+        element.write_le_bytes(out)
+    }
+}
+
+pub type GoldilocksPacker = FieldPacker<Goldilocks>;
+pub type CenoGoldilocksPacker = FieldPacker<CenoGoldilocks>;
+pub type BabyBearPacker = FieldPacker<BabyBear>;
+pub type Mersenne31Packer = FieldPacker<Mersenne31>;
+pub type Bls12381Packer = FieldPacker<blstrs::Scalar>;
+pub type ArkBls12381Packer = FieldPacker<ark_bls12_381::Fr>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frames::ChannelOrder;
+
+    // Deterministic bytes that exercise every byte value, including runs of 0xff.
+    fn test_video(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| match i % 7 {
+                0 => 0xff,
+                _ => (i * 131 + 7) as u8,
+            })
+            .collect()
+    }
+
+    fn check_round_trips<F: PackableField + PartialEq + std::fmt::Debug>() {
+        for bytes_per_element in 1..=F::MAX_BYTES {
+            for frame_aligned in [false, true] {
+                let packer = FieldPacker::<F>::new(bytes_per_element, frame_aligned);
+                for frame_len in [1, 5, 6, 31, 62, 97] {
+                    for num_frames in [1, 3] {
+                        let video = test_video(frame_len * num_frames);
+                        let elements = packer.pack(&video, frame_len);
+                        if frame_aligned {
+                            assert_eq!(
+                                elements.len(),
+                                num_frames * packer.elements_per_frame(frame_len)
+                            );
+                        } else {
+                            assert_eq!(elements.len(), video.len().div_ceil(bytes_per_element));
+                        }
+                        assert_eq!(packer.unpack(&elements, frame_len, num_frames), video);
+
+                        let frames = video.chunks(frame_len).map(|f| f.to_vec()).collect();
+                        let source =
+                            VideoSource::from_frames(frame_len, 1, ChannelOrder::Gray, frames)
+                                .unwrap();
+                        assert_eq!(packer.pack_source(&source).unwrap(), elements);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn goldilocks_round_trip() {
+        check_round_trips::<Goldilocks>();
+        check_round_trips::<CenoGoldilocks>();
+    }
+
+    #[test]
+    fn baby_bear_round_trip() {
+        check_round_trips::<BabyBear>();
+    }
+
+    #[test]
+    fn mersenne31_round_trip() {
+        check_round_trips::<Mersenne31>();
+    }
+
+    #[test]
+    fn bls12_381_round_trip() {
+        check_round_trips::<blstrs::Scalar>();
+        check_round_trips::<ark_bls12_381::Fr>();
+    }
+
+    #[test]
+    fn matches_inline_packing() {
+        // The 6 bytes per Goldilocks / 3 bytes per BabyBear packing the demos used to hand-roll.
+        let video = test_video(720 * 3 * 4);
+        let goldilocks = GoldilocksPacker::new(6, false).pack(&video, 720 * 3);
+        for (chunk, element) in video.chunks_exact(6).zip(&goldilocks) {
+            let expected = (0..6).fold(0u64, |acc, i| acc | ((chunk[i] as u64) << (8 * i)));
+            assert_eq!(element.as_canonical_u64(), expected);
+        }
+
+        let baby_bear = BabyBearPacker::new(3, false).pack(&video, 720 * 3);
+        for (chunk, element) in video.chunks_exact(3).zip(&baby_bear) {
+            let expected = (chunk[0] as u32) | ((chunk[1] as u32) << 8) | ((chunk[2] as u32) << 16);
+            assert_eq!(*element, BabyBear::new(expected));
+        }
+    }
+
+    #[test]
+    fn aligned_packing_does_not_straddle_frames() {
+        let packer = GoldilocksPacker::new(6, true);
+        let video = test_video(2 * 7);
+        let elements = packer.pack(&video, 7);
+        assert_eq!(elements.len(), 4);
+        assert_eq!(elements[1].as_canonical_u64(), video[6] as u64);
+        assert_eq!(elements[3].as_canonical_u64(), video[13] as u64);
+    }
+}
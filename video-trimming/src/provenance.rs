@@ -0,0 +1,597 @@
+// Provenance assertions for edited videos.
+//
+// After an edit is proved, its proof has to travel with the output media. A
+// `ProvenanceAssertion` is a JSON-LD document in the spirit of a C2PA actions assertion: it embeds
+// the original `SignedManifest` as its ingredient, so the signer, the algorithm and the signed
+// commitment come along, and records the edits together with the proof bytes. Verifying the
+// assertion re-checks the ingredient's signature and then the proof against the published media,
+// so a consumer needs nothing but the assertion, the output and a list of trusted keys.
+//
+// The edits are trims and crops, each applied to the output of the ones before. Only the original
+// video is signed, so they are not proved one at a time: the verifier checks each edit against
+// the shape of the previous output and folds them into the frames and rectangle of the original
+// the output keeps (`EditRegion`), and a single proof opens that region. Whole frames are proved
+// against the FRI commitment in the manifest itself. A crop is proved with `redact::crop` against
+// the Basefold commitment to the same packed frames, so the signer additionally signs that
+// commitment, bound to the manifest's commitment, and the assertion carries it as a
+// `SignedGridCommitment`.
+//
+// Blurs are out of scope: a blurred frame is computed from the signed pixels rather than cut out
+// of them, so no opening of the commitment shows it is faithful.
+
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::{hex_bytes, SignedManifest, VideoGeometry};
+use crate::packing::{CenoGoldilocksPacker, Packer};
+use crate::redact::crop::{CropProof, CropSetup, GridLayout};
+use crate::redact::multilinear::CommittedVideo;
+use crate::redact::Commitment as GridCommitment;
+use crate::signing::VideoSigner;
+use crate::univariate::{FriSetup, TrimProof};
+
+pub const PROVENANCE_VERSION: u32 = 2;
+
+// Every term in an assertion is resolved against this vocabulary.
+const JSON_LD_VOCAB: &str = "urn:video-trimming:provenance:v1#";
+const ASSERTION_TYPE: &str = "VideoEditAssertion";
+
+// Prepended to the signed grid commitment, so its signature can't be replayed as a manifest
+// signature or the other way round.
+const GRID_SIGNING_DOMAIN: &[u8] = b"video-trimming grid commitment";
+
+fn invalid_data<E: ToString>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonLdContext {
+    #[serde(rename = "@vocab")]
+    pub vocab: String,
+}
+
+/// The Basefold commitment a crop of the video is proved against, signed by the manifest's
+/// signer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedGridCommitment {
+    pub grid: GridLayout,
+    #[serde(with = "hex_bytes")]
+    pub commitment: Vec<u8>,
+    #[serde(with = "hex_bytes")]
+    pub signature: Vec<u8>,
+}
+
+impl SignedGridCommitment {
+    /// The grid a crop of the video in `manifest` is committed on, if its planes are all full
+    /// resolution.
+    pub fn grid(manifest: &SignedManifest) -> Option<GridLayout> {
+        let geometry = &manifest.geometry;
+        Some(GridLayout {
+            width: geometry.width,
+            height: geometry.height,
+            channels: geometry.channel_order.full_resolution_planes()?,
+            num_frames: geometry.num_frames,
+        })
+    }
+
+    /// The setup crops of the video in `manifest` are proved with, if its planes are all full
+    /// resolution and its frames are packed independently.
+    pub fn crop_setup(manifest: &SignedManifest) -> Option<CropSetup> {
+        let geometry = &manifest.geometry;
+        if !geometry.frame_aligned {
+            return None;
+        }
+        let packer = CenoGoldilocksPacker::new(geometry.bytes_per_element, true);
+        Some(CropSetup::new(Self::grid(manifest)?, packer))
+    }
+
+    /// Signs `video`, committed with `setup`, as the grid commitment of the video in `manifest`.
+    pub fn sign(
+        signer: &dyn VideoSigner,
+        manifest: &SignedManifest,
+        setup: &CropSetup,
+        video: &CommittedVideo,
+    ) -> Self {
+        let commitment = bincode::serialize(&video.commitment()).unwrap();
+        let payload = Self::signing_payload(&manifest.commitment, &setup.grid, &commitment);
+        Self {
+            grid: setup.grid,
+            commitment,
+            signature: signer.sign(&payload),
+        }
+    }
+
+    fn signing_payload(
+        manifest_commitment: &[u8],
+        grid: &GridLayout,
+        commitment: &[u8],
+    ) -> Vec<u8> {
+        let mut payload = GRID_SIGNING_DOMAIN.to_vec();
+        payload.extend(manifest_commitment);
+        for v in [grid.width, grid.height, grid.channels, grid.num_frames] {
+            payload.extend((v as u64).to_le_bytes());
+        }
+        payload.extend(commitment);
+        payload
+    }
+
+    // Checks the signature and that the grid is the one of the manifest's video.
+    fn verify(&self, manifest: &SignedManifest) -> io::Result<GridCommitment> {
+        if Self::grid(manifest) != Some(self.grid) {
+            return Err(invalid_data(
+                "grid commitment does not match the video in the manifest",
+            ));
+        }
+        let payload = Self::signing_payload(&manifest.commitment, &self.grid, &self.commitment);
+        manifest
+            .public_key()
+            .verify(&payload, &self.signature)
+            .map_err(invalid_data)?;
+        bincode::deserialize(&self.commitment).map_err(invalid_data)
+    }
+}
+
+/// An edit, applied to the output of the edits before it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum EditAction {
+    /// Keeps the `kept` frame ranges, back to back.
+    Trim { kept: Vec<Range<usize>> },
+    /// Keeps columns `x` and rows `y` of every frame.
+    Crop { x: Range<usize>, y: Range<usize> },
+}
+
+/// The part of the signed video a list of edits keeps, in the signed video's coordinates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditRegion {
+    /// Sorted, disjoint and non-touching frame ranges.
+    pub frames: Vec<Range<usize>>,
+    pub x: Range<usize>,
+    pub y: Range<usize>,
+}
+
+impl EditRegion {
+    /// Applies `actions` in order to the video of `geometry`, checking each against the output of
+    /// the ones before it.
+    pub fn apply(geometry: &VideoGeometry, actions: &[EditAction]) -> io::Result<Self> {
+        let mut region = Self {
+            frames: vec![0..geometry.num_frames],
+            x: 0..geometry.width,
+            y: 0..geometry.height,
+        };
+        for (i, action) in actions.iter().enumerate() {
+            match action {
+                EditAction::Trim { kept } => {
+                    let frames: Vec<usize> = region
+                        .frames
+                        .iter()
+                        .flat_map(|range| range.clone())
+                        .collect();
+                    let mut trimmed: Vec<Range<usize>> = Vec::new();
+                    let mut prev_end = 0;
+                    for segment in kept {
+                        if segment.start > segment.end
+                            || segment.start < prev_end
+                            || segment.end > frames.len()
+                        {
+                            return Err(invalid_data(format!(
+                                "edit {} keeps frames outside the previous output",
+                                i
+                            )));
+                        }
+                        prev_end = segment.end;
+                        for &frame in &frames[segment.clone()] {
+                            match trimmed.last_mut() {
+                                Some(last) if last.end == frame => last.end += 1,
+                                _ => trimmed.push(frame..frame + 1),
+                            }
+                        }
+                    }
+                    region.frames = trimmed;
+                }
+                EditAction::Crop { x, y } => {
+                    if geometry.channel_order.full_resolution_planes().is_none() {
+                        return Err(invalid_data(format!("edit {} crops subsampled planes", i)));
+                    }
+                    if x.start >= x.end
+                        || x.end > region.x.len()
+                        || y.start >= y.end
+                        || y.end > region.y.len()
+                    {
+                        return Err(invalid_data(format!(
+                            "edit {} crops outside the previous output",
+                            i
+                        )));
+                    }
+                    region.x = region.x.start + x.start..region.x.start + x.end;
+                    region.y = region.y.start + y.start..region.y.start + y.end;
+                }
+            }
+        }
+        Ok(region)
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.iter().map(|range| range.len()).sum()
+    }
+
+    /// Whether the region is whole frames of the video of `geometry`.
+    pub fn whole_frames(&self, geometry: &VideoGeometry) -> bool {
+        self.x == (0..geometry.width) && self.y == (0..geometry.height)
+    }
+
+    fn check_trim(&self, geometry: &VideoGeometry, proof: &TrimProof) -> io::Result<()> {
+        if !self.whole_frames(geometry) || proof.kept != self.frames {
+            return Err(invalid_data(
+                "trimming proof is not for the frames the edits keep",
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_crop(&self, proof: &CropProof) -> io::Result<()> {
+        if proof.frames != self.frames || proof.x != self.x || proof.y != self.y {
+            return Err(invalid_data(
+                "crop proof is not for the region the edits keep",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The proof that the output is the region of the signed video the edits keep.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "opening", rename_all = "kebab-case")]
+pub enum EditProof {
+    /// Whole frames, opened against the manifest's commitment. `proof` is a bincode `TrimProof`.
+    Frames {
+        #[serde(with = "hex_bytes")]
+        proof: Vec<u8>,
+    },
+    /// A crop, opened against `grid_commitment`. `proof` is a bincode `CropProof`.
+    Crop {
+        grid_commitment: SignedGridCommitment,
+        #[serde(with = "hex_bytes")]
+        proof: Vec<u8>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProvenanceAssertion {
+    #[serde(rename = "@context")]
+    pub context: JsonLdContext,
+    #[serde(rename = "@type")]
+    pub assertion_type: String,
+    pub version: u32,
+    /// The tool that produced the assertion.
+    pub claim_generator: String,
+    /// The signed original video.
+    pub ingredient: SignedManifest,
+    /// The edits, in the order they were applied.
+    pub actions: Vec<EditAction>,
+    pub proof: EditProof,
+}
+
+impl ProvenanceAssertion {
+    fn new(ingredient: SignedManifest, actions: Vec<EditAction>, proof: EditProof) -> Self {
+        Self {
+            context: JsonLdContext {
+                vocab: JSON_LD_VOCAB.to_string(),
+            },
+            assertion_type: ASSERTION_TYPE.to_string(),
+            version: PROVENANCE_VERSION,
+            claim_generator: format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            ingredient,
+            actions,
+            proof,
+        }
+    }
+
+    /// Records `actions`, which must keep whole frames, with `proof`, the trim of the signed video
+    /// to the frames of their `EditRegion`.
+    pub fn trim(
+        ingredient: SignedManifest,
+        actions: Vec<EditAction>,
+        proof: &TrimProof,
+    ) -> io::Result<Self> {
+        EditRegion::apply(&ingredient.geometry, &actions)?
+            .check_trim(&ingredient.geometry, proof)?;
+        let proof = EditProof::Frames {
+            proof: bincode::serialize(proof).unwrap(),
+        };
+        Ok(Self::new(ingredient, actions, proof))
+    }
+
+    /// Records `actions` with `proof`, the crop of the video committed in `grid_commitment` to
+    /// their `EditRegion`.
+    pub fn crop(
+        ingredient: SignedManifest,
+        actions: Vec<EditAction>,
+        grid_commitment: SignedGridCommitment,
+        proof: &CropProof,
+    ) -> io::Result<Self> {
+        EditRegion::apply(&ingredient.geometry, &actions)?.check_crop(proof)?;
+        let proof = EditProof::Crop {
+            grid_commitment,
+            proof: bincode::serialize(proof).unwrap(),
+        };
+        Ok(Self::new(ingredient, actions, proof))
+    }
+
+    /// Checks that `output`, planar frames back to back, is the result of the recorded edits on
+    /// the video signed in the ingredient. As with `SignedManifest::verify_signature`, whether
+    /// the signer is trusted is up to the caller.
+    pub fn verify(&self, output: &[u8]) -> io::Result<()> {
+        if self.version != PROVENANCE_VERSION
+            || self.context.vocab != JSON_LD_VOCAB
+            || self.assertion_type != ASSERTION_TYPE
+        {
+            return Err(invalid_data("unsupported provenance assertion"));
+        }
+        let manifest = &self.ingredient;
+        let commitment = manifest.verify_signature()?;
+        let geometry = &manifest.geometry;
+        let region = EditRegion::apply(geometry, &self.actions)?;
+
+        match &self.proof {
+            EditProof::Frames { proof } => {
+                let proof: TrimProof = bincode::deserialize(proof).map_err(invalid_data)?;
+                region.check_trim(geometry, &proof)?;
+                if output.len() != region.num_frames() * geometry.frame_len() {
+                    return Err(invalid_data(format!(
+                        "output is not {} frames of the signed video",
+                        region.num_frames()
+                    )));
+                }
+                let segment_coeffs = geometry.packer().pack(output, geometry.frame_len());
+                FriSetup::new(manifest.pcs.fri)
+                    .verify_trim(
+                        &commitment,
+                        manifest.pcs.log_degree,
+                        geometry.frame_size(),
+                        geometry.num_frames,
+                        &proof,
+                        &segment_coeffs,
+                    )
+                    .map_err(invalid_data)
+            }
+            EditProof::Crop {
+                grid_commitment,
+                proof,
+            } => {
+                let commitment = grid_commitment.verify(manifest)?;
+                let proof: CropProof = bincode::deserialize(proof).map_err(invalid_data)?;
+                region.check_crop(&proof)?;
+                SignedGridCommitment::crop_setup(manifest)
+                    .ok_or_else(|| invalid_data("the signed video can't be cropped"))?
+                    .verify_crop(&commitment, &proof, output)
+                    .map_err(invalid_data)
+            }
+        }
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        serde_json::from_slice(&fs::read(path)?).map_err(invalid_data)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(invalid_data)?;
+        fs::write(path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frames::ChannelOrder;
+    use crate::manifest::PcsParameters;
+    use crate::signing::{SecretKey, SignatureAlgorithm};
+    use crate::timecode::FrameRate;
+    use crate::univariate::{FriParameters, VideoCommitment};
+
+    fn geometry() -> VideoGeometry {
+        VideoGeometry {
+            width: 4,
+            height: 3,
+            channel_order: ChannelOrder::Rgb,
+            num_frames: 5,
+            frame_rate: FrameRate::new(25, 1).unwrap(),
+            bytes_per_element: 6,
+            frame_aligned: true,
+        }
+    }
+
+    fn test_video() -> Vec<u8> {
+        (0..geometry().frame_len() * geometry().num_frames)
+            .map(|i| (i * 37 + 11) as u8)
+            .collect()
+    }
+
+    // The bytes of the test video inside `region`.
+    fn cut(region: &EditRegion) -> Vec<u8> {
+        let geometry = geometry();
+        let grid = GridLayout {
+            width: geometry.width,
+            height: geometry.height,
+            channels: 3,
+            num_frames: geometry.num_frames,
+        };
+        let video = test_video();
+        grid.crop_runs(&region.frames, &region.x, &region.y)
+            .into_iter()
+            .flat_map(|run| video[run].to_vec())
+            .collect()
+    }
+
+    fn signed(key: &SecretKey) -> (SignedManifest, FriSetup, VideoCommitment) {
+        let geometry = geometry();
+        let setup = FriSetup::new(FriParameters::default());
+        let committed =
+            setup.commit_video(geometry.packer().pack(&test_video(), geometry.frame_len()));
+        let pcs = PcsParameters::univariate(setup.params, committed.log_degree);
+        let manifest = SignedManifest::sign(key, pcs, geometry, &committed.commitment);
+        (manifest, setup, committed)
+    }
+
+    // Signs the test video and proves `actions` against the manifest's commitment.
+    fn signed_trim(key: &SecretKey, actions: Vec<EditAction>) -> (ProvenanceAssertion, Vec<u8>) {
+        let (manifest, setup, committed) = signed(key);
+        let region = EditRegion::apply(&manifest.geometry, &actions).unwrap();
+        let proof = setup
+            .prove_trim_segments(
+                &committed,
+                geometry().frame_size(),
+                geometry().num_frames,
+                region.frames.clone(),
+            )
+            .unwrap();
+        (
+            ProvenanceAssertion::trim(manifest, actions, &proof).unwrap(),
+            cut(&region),
+        )
+    }
+
+    // Signs the test video and its grid commitment and proves `actions` against the latter.
+    fn signed_crop(key: &SecretKey, actions: Vec<EditAction>) -> (ProvenanceAssertion, Vec<u8>) {
+        let (manifest, _, _) = signed(key);
+        let region = EditRegion::apply(&manifest.geometry, &actions).unwrap();
+        let setup = SignedGridCommitment::crop_setup(&manifest).unwrap();
+        let committed = setup.commit_video(&test_video());
+        let grid_commitment = SignedGridCommitment::sign(key, &manifest, &setup, &committed);
+        let proof = setup
+            .prove_crop(
+                &committed,
+                region.frames.clone(),
+                region.x.clone(),
+                region.y.clone(),
+            )
+            .unwrap();
+        (
+            ProvenanceAssertion::crop(manifest, actions, grid_commitment, &proof).unwrap(),
+            cut(&region),
+        )
+    }
+
+    fn trim(kept: Vec<Range<usize>>) -> EditAction {
+        EditAction::Trim { kept }
+    }
+
+    fn crop(x: Range<usize>, y: Range<usize>) -> EditAction {
+        EditAction::Crop { x, y }
+    }
+
+    #[test]
+    fn trim_assertion_round_trips_and_verifies() {
+        for algorithm in SignatureAlgorithm::ALL {
+            let key = SecretKey::generate(algorithm);
+            let (assertion, output) = signed_trim(&key, vec![trim(vec![1..3])]);
+            let json = serde_json::to_vec(&assertion).unwrap();
+            let parsed: ProvenanceAssertion = serde_json::from_slice(&json).unwrap();
+            parsed.verify(&output).unwrap();
+        }
+    }
+
+    #[test]
+    fn tampered_trim_output_is_rejected() {
+        let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+        let (assertion, mut output) = signed_trim(&key, vec![trim(vec![1..3])]);
+        output[5] ^= 1;
+        assert!(assertion.verify(&output).is_err());
+        assert!(assertion.verify(&output[geometry().frame_len()..]).is_err());
+    }
+
+    #[test]
+    fn relabelled_trim_is_rejected() {
+        let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+        let (mut assertion, output) = signed_trim(&key, vec![trim(vec![1..3])]);
+        assertion.actions = vec![trim(vec![2..4])];
+        assert!(assertion.verify(&output).is_err());
+    }
+
+    #[test]
+    fn chained_trims_verify() {
+        let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+        let actions = vec![trim(vec![1..5]), trim(vec![0..1, 2..4])];
+        let region = EditRegion::apply(&geometry(), &actions).unwrap();
+        assert_eq!(region.frames, vec![1..2, 3..5]);
+        let (assertion, output) = signed_trim(&key, actions);
+        assertion.verify(&output).unwrap();
+    }
+
+    #[test]
+    fn crop_assertion_verifies() {
+        let key = SecretKey::generate(SignatureAlgorithm::EcdsaP256);
+        let (assertion, output) = signed_crop(&key, vec![crop(1..3, 0..2)]);
+        assertion.verify(&output).unwrap();
+
+        // A grid commitment signed by someone else does not carry the manifest's authority.
+        let other = SecretKey::generate(SignatureAlgorithm::EcdsaP256);
+        let (forged, _) = signed_crop(&other, vec![crop(1..3, 0..2)]);
+        let mut assertion = assertion;
+        assertion.proof = forged.proof;
+        assert!(assertion.verify(&output).is_err());
+    }
+
+    #[test]
+    fn chained_crop_verifies() {
+        let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+        let actions = vec![
+            trim(vec![1..4]),
+            crop(1..4, 0..2),
+            crop(1..3, 1..2),
+            trim(vec![0..1, 2..3]),
+        ];
+        let region = EditRegion::apply(&geometry(), &actions).unwrap();
+        assert_eq!(
+            region,
+            EditRegion {
+                frames: vec![1..2, 3..4],
+                x: 2..4,
+                y: 1..2,
+            }
+        );
+        let (assertion, mut output) = signed_crop(&key, actions);
+        assertion.verify(&output).unwrap();
+        output[3] ^= 1;
+        assert!(assertion.verify(&output).is_err());
+    }
+
+    #[test]
+    fn edits_outside_the_previous_output_are_rejected() {
+        let geometry = geometry();
+        for actions in [
+            vec![trim(vec![4..6])],
+            vec![trim(vec![1..3]), trim(vec![1..3])],
+            vec![crop(1..3, 0..2), crop(0..3, 0..1)],
+            vec![crop(1..3, 1..3), crop(0..2, 1..3)],
+            vec![crop(2..2, 0..1)],
+        ] {
+            let err = EditRegion::apply(&geometry, &actions).unwrap_err();
+            assert!(err.to_string().contains("outside the previous output"));
+        }
+
+        let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+        let (mut assertion, output) = signed_trim(&key, vec![trim(vec![3..5])]);
+        assertion.actions = vec![trim(vec![4..6])];
+        assert!(assertion.verify(&output).is_err());
+    }
+
+    #[test]
+    fn trim_proof_for_a_cropped_region_is_rejected() {
+        let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+        let (manifest, setup, committed) = signed(&key);
+        let proof = setup
+            .prove_trim(
+                &committed,
+                geometry().frame_size(),
+                geometry().num_frames,
+                0..5,
+            )
+            .unwrap();
+        assert!(ProvenanceAssertion::trim(manifest, vec![crop(1..3, 0..2)], &proof).is_err());
+    }
+}
@@ -0,0 +1,451 @@
+// Revealing an arbitrary set of frames as a union of dyadic blocks.
+//
+// A block of 2^s frames starting at a multiple of 2^s is a subcube of the committed hypercube:
+// fixing the high frame variables to the block index leaves a polynomial over the frame and
+// in-block variables that the verifier can evaluate from the published frames. Each block gives
+// one claim v(z_b) = y_b at a point z_b whose free coordinates are random, drawn after the
+// published frames are absorbed. Instead of opening v
+// once per block, the claims are combined with random weights a_b into
+//   sum_b a_b * y_b = sum_x v(x) * sum_b a_b * eq(z_b, x)
+// which a degree-2 sumcheck reduces to a single evaluation of v, opened once with Basefold.
+
+use std::ops::Range;
+
+use either::Either;
+use mpcs::{test_util::get_point_from_challenge, PolynomialCommitmentScheme};
+use multilinear_extensions::{
+    mle::{ArcMultilinearExtension, Point},
+    monomial::Term,
+    virtual_poly::build_eq_x_r,
+    virtual_polys::VirtualPolynomials,
+};
+use p3_field_git::{PrimeCharacteristicRing, PrimeField64};
+use p3_goldilocks_git::Goldilocks as CenoGoldilocks;
+use serde::{Deserialize, Serialize};
+use sumcheck::structs::{IOPProof, IOPProverState};
+use transcript::Transcript;
+
+use super::multilinear::{to_mle_matrix, CommittedVideo, MultilinearSetup, NUM_THREADS};
+use super::{
+    append_published, eq_eval, eq_table, to_ext, verify_sumcheck, Commitment, Pcs, PcsProof,
+    RedactError, VideoLayout, E, T, TRANSCRIPT_LABEL,
+};
+
+/// The frames `index << log_size .. (index + 1) << log_size`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    pub log_size: usize,
+    pub index: usize,
+}
+
+impl Block {
+    pub fn frames(&self) -> Range<usize> {
+        (self.index << self.log_size)..((self.index + 1) << self.log_size)
+    }
+}
+
+/// Splits a set of frame indices into the fewest dyadic blocks covering exactly those frames,
+/// in increasing frame order. Frames 9..=40, for example, become blocks of 1, 2, 4, 16, 8 and 1.
+pub fn dyadic_blocks(frames: &[usize]) -> Vec<Block> {
+    let mut frames = frames.to_vec();
+    frames.sort_unstable();
+    frames.dedup();
+
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < frames.len() {
+        // Extend to the end of this run of consecutive frames.
+        let mut run_end = i + 1;
+        while run_end < frames.len() && frames[run_end] == frames[run_end - 1] + 1 {
+            run_end += 1;
+        }
+        let (mut start, end) = (frames[i], frames[run_end - 1] + 1);
+        // Greedily take the largest aligned block that fits.
+        while start < end {
+            let mut log_size = if start == 0 {
+                usize::BITS as usize - 1
+            } else {
+                start.trailing_zeros() as usize
+            };
+            while start + (1 << log_size) > end {
+                log_size -= 1;
+            }
+            blocks.push(Block {
+                log_size,
+                index: start >> log_size,
+            });
+            start += 1 << log_size;
+        }
+        i = run_end;
+    }
+    blocks
+}
+
+/// Proof that the published frames are the frames of `blocks` of a committed video.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CompactProof {
+    pub blocks: Vec<Block>,
+    pub sumcheck: IOPProof<E>,
+    /// v at the point the sumcheck reduced to.
+    pub eval: E,
+    pub opening: PcsProof,
+}
+
+// Blocks must be sorted, disjoint and made of signed frames.
+fn check_blocks(blocks: &[Block], layout: &VideoLayout) -> Result<(), RedactError> {
+    let mut prev_end = 0;
+    for block in blocks {
+        if block.log_size > layout.index_vars()
+            || block.index >= layout.num_frames.next_power_of_two() >> block.log_size
+        {
+            return Err(RedactError::InvalidInterval);
+        }
+        let frames = block.frames();
+        if frames.start < prev_end || frames.end > layout.num_frames {
+            return Err(RedactError::InvalidInterval);
+        }
+        prev_end = frames.end;
+    }
+    if blocks.is_empty() {
+        return Err(RedactError::InvalidInterval);
+    }
+    Ok(())
+}
+
+// Samples the point of every block claim and the weights combining them.
+fn block_points(
+    commitment: &Commitment,
+    layout: &VideoLayout,
+    blocks: &[Block],
+    published: impl IntoIterator<Item = u64>,
+    transcript: &mut T,
+) -> (Vec<Point<E>>, Vec<E>) {
+    Pcs::write_commitment(commitment, transcript).unwrap();
+    for x in [layout.frame_size, layout.num_frames, blocks.len()] {
+        transcript.append_field_element_ext(&E::from_u64(x as u64));
+    }
+    for block in blocks {
+        transcript.append_field_element_ext(&E::from_u64(block.log_size as u64));
+        transcript.append_field_element_ext(&E::from_u64(block.index as u64));
+    }
+    append_published(transcript, published);
+
+    let points = blocks
+        .iter()
+        .map(|block| {
+            // Random frame and in-block variables, block index in the remaining high variables.
+            let free_vars = layout.frame_vars() + block.log_size;
+            let mut point = get_point_from_challenge(free_vars, transcript);
+            point.extend(
+                (0..layout.index_vars() - block.log_size)
+                    .map(|bit| E::from_u64(((block.index >> bit) & 1) as u64)),
+            );
+            point
+        })
+        .collect();
+    let weights = get_point_from_challenge(blocks.len(), transcript);
+    (points, weights)
+}
+
+impl MultilinearSetup {
+    /// Proves that `frames` of `video` are the published frames.
+    pub fn prove_frames(
+        &self,
+        video: &CommittedVideo,
+        frames: &[usize],
+    ) -> Result<CompactProof, RedactError> {
+        let blocks = dyadic_blocks(frames);
+        check_blocks(&blocks, &self.layout)?;
+        let num_vars = self.layout.num_vars();
+
+        let frame_size_padded = self.layout.frame_size.next_power_of_two();
+        let published = blocks.iter().flat_map(Block::frames).flat_map(|frame| {
+            let start = frame * frame_size_padded;
+            &video.evaluations[start..start + self.layout.frame_size]
+        });
+        let mut transcript = T::new(TRANSCRIPT_LABEL);
+        let (points, weights) = block_points(
+            &video.commitment(),
+            &self.layout,
+            &blocks,
+            published.map(|x| x.as_canonical_u64()),
+            &mut transcript,
+        );
+
+        let poly: ArcMultilinearExtension<_> = to_mle_matrix(video.evaluations.clone())
+            .to_mles::<E>()
+            .remove(0)
+            .into();
+        let eqs: Vec<ArcMultilinearExtension<_>> =
+            points.iter().map(|point| build_eq_x_r(point)).collect();
+
+        // We are sumchecking v(x) * sum_b a_b * eq(z_b, x).
+        let sumcheck_poly = VirtualPolynomials::<E>::new_from_monimials(
+            NUM_THREADS,
+            num_vars,
+            eqs.iter()
+                .zip(&weights)
+                .map(|(eq, weight)| Term {
+                    scalar: Either::Right(*weight),
+                    product: vec![Either::Left(&poly), Either::Left(eq)],
+                })
+                .collect(),
+        );
+        let (sumcheck, _) = IOPProverState::<E>::prove(sumcheck_poly.as_view(), &mut transcript);
+
+        let eval = poly.evaluate(&sumcheck.point);
+        transcript.append_field_element_ext(&eval);
+        let opening = Pcs::batch_open(
+            &self.pp,
+            &[(0, 1 << num_vars)],
+            None,
+            &video.comm,
+            &[sumcheck.point.clone()],
+            &[vec![eval]],
+            &[(1, 0)],
+            &mut transcript,
+        )
+        .map_err(|e| RedactError::Pcs(format!("{:?}", e)))?;
+
+        Ok(CompactProof {
+            blocks,
+            sumcheck,
+            eval,
+            opening,
+        })
+    }
+
+    /// Verifies `proof` against the signed `commitment` and `published`, the packed frames of
+    /// `proof.blocks` in increasing order.
+    pub fn verify_frames(
+        &self,
+        commitment: &Commitment,
+        proof: &CompactProof,
+        published: &[CenoGoldilocks],
+    ) -> Result<(), RedactError> {
+        let layout = &self.layout;
+        check_blocks(&proof.blocks, layout)?;
+        let num_frames: usize = proof.blocks.iter().map(|b| 1 << b.log_size).sum();
+        if published.len() != num_frames * layout.frame_size {
+            return Err(RedactError::MalformedProof);
+        }
+        let num_vars = layout.num_vars();
+
+        let mut transcript = T::new(TRANSCRIPT_LABEL);
+        let (points, weights) = block_points(
+            commitment,
+            layout,
+            &proof.blocks,
+            published.iter().map(|x| x.as_canonical_u64()),
+            &mut transcript,
+        );
+
+        // Each block claim is the block's own polynomial at the free part of its point.
+        let mut claimed_sum = E::ZERO;
+        let mut published_frames = published.chunks(layout.frame_size);
+        for ((block, point), weight) in proof.blocks.iter().zip(&points).zip(&weights) {
+            let (element_point, rest) = point.split_at(layout.frame_vars());
+            let eq_element = eq_table(element_point);
+            let eq_frame = eq_table(&rest[..block.log_size]);
+            let mut block_eval = E::ZERO;
+            for eq_f in eq_frame {
+                let values = published_frames.next().unwrap();
+                let frame_eval = values
+                    .iter()
+                    .zip(&eq_element)
+                    .fold(E::ZERO, |acc, (v, e)| acc + to_ext(v) * *e);
+                block_eval += eq_f * frame_eval;
+            }
+            claimed_sum += *weight * block_eval;
+        }
+
+        let (sumcheck_r, expected_evaluation) =
+            verify_sumcheck(claimed_sum, &proof.sumcheck, 2, num_vars, &mut transcript)?;
+
+        transcript.append_field_element_ext(&proof.eval);
+        Pcs::batch_verify(
+            &self.vp,
+            &[(0, 1 << num_vars)],
+            &[sumcheck_r.clone()],
+            None,
+            commitment,
+            &[vec![proof.eval]],
+            &proof.opening,
+            &[(1, 0)],
+            &mut transcript,
+        )
+        .map_err(|e| RedactError::Pcs(format!("{:?}", e)))?;
+
+        let combined_eq = points
+            .iter()
+            .zip(&weights)
+            .fold(E::ZERO, |acc, (point, weight)| {
+                acc + *weight * eq_eval(point, &sumcheck_r)
+            });
+        if proof.eval * combined_eq == expected_evaluation {
+            Ok(())
+        } else {
+            Err(RedactError::EvaluationMismatch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: VideoLayout = VideoLayout {
+        frame_size: 12,
+        num_frames: 50,
+    };
+
+    fn test_video() -> Vec<CenoGoldilocks> {
+        (0..(LAYOUT.frame_size * LAYOUT.num_frames) as u64)
+            .map(|i| CenoGoldilocks::from_u64(i * 7919 + 3))
+            .collect()
+    }
+
+    fn published(video: &[CenoGoldilocks], blocks: &[Block]) -> Vec<CenoGoldilocks> {
+        blocks
+            .iter()
+            .flat_map(|block| {
+                let frames = block.frames();
+                video[frames.start * LAYOUT.frame_size..frames.end * LAYOUT.frame_size].to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decomposes_into_fewest_blocks() {
+        let frames: Vec<usize> = (9..=40).collect();
+        let sizes: Vec<usize> = dyadic_blocks(&frames)
+            .iter()
+            .map(|b| 1 << b.log_size)
+            .collect();
+        assert_eq!(sizes, vec![1, 2, 4, 16, 8, 1]);
+
+        assert_eq!(
+            dyadic_blocks(&[7, 3, 0, 1, 2, 3]),
+            vec![
+                Block {
+                    log_size: 2,
+                    index: 0
+                },
+                Block {
+                    log_size: 0,
+                    index: 7
+                },
+            ]
+        );
+        assert!(dyadic_blocks(&[]).is_empty());
+
+        // Every frame is covered exactly once.
+        let frames = [0, 5, 6, 7, 8, 9, 10, 11, 12, 31, 32, 33];
+        let covered: Vec<usize> = dyadic_blocks(&frames)
+            .iter()
+            .flat_map(|b| b.frames())
+            .collect();
+        assert_eq!(covered, frames);
+    }
+
+    #[test]
+    fn frame_sets_verify() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let commitment = committed.commitment();
+
+        let sets: Vec<Vec<usize>> = vec![
+            (9..=40).collect(),
+            vec![3],
+            vec![0, 1, 2, 3, 17, 30, 31, 49],
+            (0..50).collect(),
+        ];
+        for frames in sets {
+            let proof = setup.prove_frames(&committed, &frames).unwrap();
+            setup
+                .verify_frames(&commitment, &proof, &published(&video, &proof.blocks))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn tampered_frame_is_rejected() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let frames: Vec<usize> = (9..=40).collect();
+        let proof = setup.prove_frames(&committed, &frames).unwrap();
+
+        let mut frames_published = published(&video, &proof.blocks);
+        let last = frames_published.len() - 1;
+        frames_published[last] += CenoGoldilocks::ONE;
+        assert!(setup
+            .verify_frames(&committed.commitment(), &proof, &frames_published)
+            .is_err());
+    }
+
+    #[test]
+    fn block_points_depend_on_published_frames() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let commitment = setup.commit_video(&video).commitment();
+        let blocks = dyadic_blocks(&[8, 9, 10, 11, 12]);
+        let mut frames_published = published(&video, &blocks);
+        let sample = |published: &[CenoGoldilocks]| {
+            block_points(
+                &commitment,
+                &LAYOUT,
+                &blocks,
+                published.iter().map(|x| x.as_canonical_u64()),
+                &mut T::new(TRANSCRIPT_LABEL),
+            )
+        };
+        let (honest_points, honest_weights) = sample(&frames_published);
+        frames_published[0] += CenoGoldilocks::ONE;
+        let (points, weights) = sample(&frames_published);
+        assert_ne!(points, honest_points);
+        assert_ne!(weights, honest_weights);
+    }
+
+    #[test]
+    fn relabelled_blocks_are_rejected() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let proof = setup.prove_frames(&committed, &[8, 9, 10, 11]).unwrap();
+
+        let mut moved = proof.clone();
+        moved.blocks = vec![Block {
+            log_size: 2,
+            index: 3,
+        }];
+        assert!(setup
+            .verify_frames(
+                &committed.commitment(),
+                &moved,
+                &published(&video, &moved.blocks)
+            )
+            .is_err());
+
+        let mut overlapping = proof.clone();
+        overlapping.blocks = vec![
+            Block {
+                log_size: 2,
+                index: 2,
+            },
+            Block {
+                log_size: 0,
+                index: 9,
+            },
+        ];
+        assert!(matches!(
+            setup.verify_frames(
+                &committed.commitment(),
+                &overlapping,
+                &published(&video, &overlapping.blocks)
+            ),
+            Err(RedactError::InvalidInterval)
+        ));
+    }
+}
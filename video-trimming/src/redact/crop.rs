@@ -0,0 +1,492 @@
+// Spatial cropping on top of the mask proof in `multilinear`.
+//
+// A crop is proven against the commitment the interval proofs open: the frames packed
+// `bytes_per_element` bytes per element and laid out by `compute_video_mle_evaluations_vec`, so
+// element j of frame f sits at index `f * frame_size_padded + j`. A crop (x0..x1, y0..y1) of a
+// set of frames keeps one byte run per row of every plane, and a run generally starts and
+// ends inside a packed element. The proof therefore reveals every element a run touches whole
+// (`crate::selection`): it carries the bytes of those elements outside the crop, fewer than
+// `bytes_per_element` on either side of each row, and the verifier rebuilds the elements from
+// them and the published pixels before checking the mask.
+//
+// The revealed elements are the same in every frame, so the mask and the revealed video both
+// factor into a frame part and an element part, and the verifier evaluates the element part by
+// splitting the element variables into two halves with one small eq table each.
+
+use std::ops::Range;
+
+use mpcs::PolynomialCommitmentScheme;
+use p3_field_git::{PrimeCharacteristicRing, PrimeField64};
+use serde::{Deserialize, Serialize};
+use transcript::Transcript;
+
+use super::multilinear::{CommittedVideo, MaskProof, MultilinearSetup};
+use super::{
+    append_published, eq_table, to_ext, Commitment, Pcs, RedactError, VideoLayout, E, T,
+    TRANSCRIPT_LABEL,
+};
+use crate::packing::{CenoGoldilocksPacker, Packer};
+use crate::selection::Selection;
+
+/// Shape of a planar video with `channels` full-resolution planes per frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridLayout {
+    pub width: usize,
+    pub height: usize,
+    pub channels: usize,
+    pub num_frames: usize,
+}
+
+impl GridLayout {
+    pub fn frame_len(&self) -> usize {
+        self.channels * self.width * self.height
+    }
+
+    /// The bytes of the frame ranges `frames` inside columns `x` and rows `y`, one run per row of
+    /// each plane, in the order they are published.
+    pub fn crop_runs(
+        &self,
+        frames: &[Range<usize>],
+        x: &Range<usize>,
+        y: &Range<usize>,
+    ) -> Vec<Range<usize>> {
+        let num_frames: usize = frames.iter().map(|range| range.len()).sum();
+        let mut runs = Vec::with_capacity(num_frames * self.channels * y.len());
+        for frame in frames.iter().flat_map(|range| range.clone()) {
+            for channel in 0..self.channels {
+                for row in y.clone() {
+                    let offset =
+                        ((frame * self.channels + channel) * self.height + row) * self.width;
+                    runs.push(offset + x.start..offset + x.end);
+                }
+            }
+        }
+        runs
+    }
+}
+
+/// Proof that the published frames are the rectangle `x` by `y` of the frame ranges `frames` of a
+/// committed video.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CropProof {
+    pub frames: Vec<Range<usize>>,
+    pub x: Range<usize>,
+    pub y: Range<usize>,
+    /// The bytes of the revealed elements outside the crop.
+    pub margins: Vec<u8>,
+    pub proof: MaskProof,
+}
+
+pub struct CropSetup {
+    pub grid: GridLayout,
+    packer: CenoGoldilocksPacker,
+    setup: MultilinearSetup,
+}
+
+// Writes everything the verifier's challenges must depend on into a fresh transcript.
+fn crop_transcript(
+    commitment: &Commitment,
+    grid: &GridLayout,
+    frames: &[Range<usize>],
+    x: &Range<usize>,
+    y: &Range<usize>,
+    revealed: impl IntoIterator<Item = u64>,
+) -> T {
+    let mut transcript = T::new(TRANSCRIPT_LABEL);
+    Pcs::write_commitment(commitment, &mut transcript).unwrap();
+    let mut shape = vec![
+        grid.width,
+        grid.height,
+        grid.channels,
+        grid.num_frames,
+        frames.len(),
+    ];
+    shape.extend(frames.iter().flat_map(|range| [range.start, range.end]));
+    shape.extend([x.start, x.end, y.start, y.end]);
+    for v in shape {
+        transcript.append_field_element_ext(&E::from_u64(v as u64));
+    }
+    append_published(&mut transcript, revealed);
+    transcript
+}
+
+fn sum(values: &[E]) -> E {
+    values.iter().fold(E::ZERO, |acc, v| acc + *v)
+}
+
+impl CropSetup {
+    /// Crops of videos shaped like `grid` and packed by `packer`, which must start every frame
+    /// with a fresh element.
+    pub fn new(grid: GridLayout, packer: CenoGoldilocksPacker) -> Self {
+        assert!(
+            packer.frame_aligned(),
+            "frames must be packed independently"
+        );
+        let layout = VideoLayout {
+            frame_size: packer.elements_per_frame(grid.frame_len()),
+            num_frames: grid.num_frames,
+        };
+        Self {
+            grid,
+            packer,
+            setup: MultilinearSetup::new(layout),
+        }
+    }
+
+    fn check_crop(
+        &self,
+        frames: &[Range<usize>],
+        x: &Range<usize>,
+        y: &Range<usize>,
+    ) -> Result<(), RedactError> {
+        let mut prev_end = 0;
+        for range in frames {
+            if range.start > range.end || range.start < prev_end || range.end > self.grid.num_frames
+            {
+                return Err(RedactError::InvalidInterval);
+            }
+            prev_end = range.end;
+        }
+        if self.grid.channels == 0
+            || x.start >= x.end
+            || x.end > self.grid.width
+            || y.start >= y.end
+            || y.end > self.grid.height
+        {
+            return Err(RedactError::InvalidCrop);
+        }
+        Ok(())
+    }
+
+    // The crop of the frame ranges `frames`, which `check_crop` has accepted.
+    fn selection(
+        &self,
+        frames: &[Range<usize>],
+        x: &Range<usize>,
+        y: &Range<usize>,
+    ) -> Selection<CenoGoldilocksPacker> {
+        let runs = self.grid.crop_runs(frames, x, y);
+        Selection::new(
+            self.packer,
+            self.grid.frame_len(),
+            self.grid.num_frames,
+            runs,
+        )
+        .expect("crop rows are sorted and inside the video")
+    }
+
+    // The indices inside a frame of the elements the crop reveals.
+    fn frame_elements(&self, x: &Range<usize>, y: &Range<usize>) -> Vec<usize> {
+        self.selection(&[0..1], x, y)
+            .elements()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Commits to `video`, the planar frames back to back. This is the commitment
+    /// `MultilinearSetup::commit_video` makes of the packed frames, so the same signature covers
+    /// interval proofs and crops.
+    pub fn commit_video(&self, video: &[u8]) -> CommittedVideo {
+        self.setup
+            .commit_video(&self.packer.pack(video, self.grid.frame_len()))
+    }
+
+    /// Proves that cropping the frame ranges `frames` of `video` to columns `x` and rows `y`
+    /// gives the published frames. The ranges must be sorted and must not overlap.
+    pub fn prove_crop(
+        &self,
+        video: &CommittedVideo,
+        frames: Vec<Range<usize>>,
+        x: Range<usize>,
+        y: Range<usize>,
+    ) -> Result<CropProof, RedactError> {
+        self.check_crop(&frames, &x, &y)?;
+        let frame_size = self.setup.layout.frame_size;
+        let frame_size_padded = frame_size.next_power_of_two();
+        let element =
+            |k: usize| video.evaluations[k / frame_size * frame_size_padded + k % frame_size];
+        let (_, margins) = self.selection(&frames, &x, &y).split(element);
+
+        let elements = self.frame_elements(&x, &y);
+        let revealed: Vec<u64> = frames
+            .iter()
+            .flat_map(|range| range.clone())
+            .flat_map(|frame| {
+                elements.iter().map(move |element| {
+                    video.evaluations[frame * frame_size_padded + element].as_canonical_u64()
+                })
+            })
+            .collect();
+        let transcript =
+            crop_transcript(&video.commitment(), &self.grid, &frames, &x, &y, revealed);
+
+        let mut in_crop = vec![false; frame_size_padded];
+        for element in &elements {
+            in_crop[*element] = true;
+        }
+        let mut in_frames = vec![false; self.grid.num_frames];
+        for frame in frames.iter().flat_map(|range| range.clone()) {
+            in_frames[frame] = true;
+        }
+        let proof = self.setup.prove_mask(video, transcript, |i| {
+            in_frames.get(i / frame_size_padded) == Some(&true) && in_crop[i % frame_size_padded]
+        })?;
+
+        Ok(CropProof {
+            frames,
+            x,
+            y,
+            margins,
+            proof,
+        })
+    }
+
+    /// Verifies `proof` against the signed `commitment` and `published`, the cropped planar
+    /// frames back to back.
+    pub fn verify_crop(
+        &self,
+        commitment: &Commitment,
+        proof: &CropProof,
+        published: &[u8],
+    ) -> Result<(), RedactError> {
+        let (frames, x, y) = (&proof.frames, &proof.x, &proof.y);
+        self.check_crop(frames, x, y)?;
+        let revealed = self
+            .selection(frames, x, y)
+            .rebuild(published, &proof.margins)
+            .ok_or(RedactError::MalformedProof)?;
+        let elements = self.frame_elements(x, y);
+        let transcript = crop_transcript(
+            commitment,
+            &self.grid,
+            frames,
+            x,
+            y,
+            revealed.iter().map(|v| v.as_canonical_u64()),
+        );
+
+        let frame_vars = self.setup.layout.frame_vars();
+        self.setup
+            .verify_mask(commitment, transcript, &proof.proof, |sumcheck_r| {
+                let (element_point, frame_point) = sumcheck_r.split_at(frame_vars);
+                // eq(r, element) = eq_low[low bits] * eq_high[high bits], so two tables of about
+                // the square root of the frame size cover every element.
+                let low_vars = frame_vars / 2;
+                let eq_low = eq_table(&element_point[..low_vars]);
+                let eq_high = eq_table(&element_point[low_vars..]);
+                let crop_eqs: Vec<E> = elements
+                    .iter()
+                    .map(|e| eq_low[e & ((1 << low_vars) - 1)] * eq_high[e >> low_vars])
+                    .collect();
+                let eq_frame = eq_table(frame_point);
+
+                let frames_eval = frames
+                    .iter()
+                    .fold(E::ZERO, |acc, range| acc + sum(&eq_frame[range.clone()]));
+                let mask_eval = sum(&crop_eqs) * frames_eval;
+                let mut revealed_eval = E::ZERO;
+                let kept_frames = frames.iter().flat_map(|range| range.clone());
+                for (frame, values) in kept_frames.zip(revealed.chunks(crop_eqs.len())) {
+                    let frame_eval = values
+                        .iter()
+                        .zip(&crop_eqs)
+                        .fold(E::ZERO, |acc, (v, e)| acc + *e * to_ext(v));
+                    revealed_eval += eq_frame[frame] * frame_eval;
+                }
+                (mask_eval, revealed_eval)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mpcs::test_util::get_point_from_challenge;
+    use p3_goldilocks_git::Goldilocks as CenoGoldilocks;
+
+    use super::*;
+
+    // Neither the width, the height, the channel count nor the frame count is a power of two, and
+    // rows of five bytes start and end inside elements of six.
+    const GRID: GridLayout = GridLayout {
+        width: 5,
+        height: 3,
+        channels: 3,
+        num_frames: 6,
+    };
+
+    fn packer() -> CenoGoldilocksPacker {
+        CenoGoldilocksPacker::new(6, true)
+    }
+
+    fn test_video() -> Vec<u8> {
+        (0..GRID.frame_len() * GRID.num_frames)
+            .map(|i| (i * 37 + 11) as u8)
+            .collect()
+    }
+
+    fn crop(video: &[u8], frames: &[Range<usize>], x: Range<usize>, y: Range<usize>) -> Vec<u8> {
+        GRID.crop_runs(frames, &x, &y)
+            .into_iter()
+            .flat_map(|run| video[run].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn crops_verify() {
+        let setup = CropSetup::new(GRID, packer());
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let commitment = committed.commitment();
+        for (frames, x, y) in [
+            (vec![1..4], 1..4, 0..2),
+            (vec![0..6], 0..5, 0..3),
+            (vec![5..6], 4..5, 2..3),
+            (vec![0..1, 3..5], 2..4, 1..3),
+        ] {
+            let proof = setup
+                .prove_crop(&committed, frames.clone(), x.clone(), y.clone())
+                .unwrap();
+            // At most one partial element is revealed on either side of each row.
+            let num_frames: usize = frames.iter().map(|range| range.len()).sum();
+            assert!(proof.margins.len() < 2 * 6 * num_frames * GRID.channels * y.len());
+            setup
+                .verify_crop(&commitment, &proof, &crop(&video, &frames, x, y))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn crop_opens_the_interval_commitment() {
+        let setup = CropSetup::new(GRID, packer());
+        let video = test_video();
+        let packed = packer().pack(&video, GRID.frame_len());
+        let committed = setup.setup.commit_video(&packed);
+        let commitment = committed.commitment();
+
+        let interval = setup.setup.prove_interval(&committed, 2, 4).unwrap();
+        let frame_size = setup.setup.layout.frame_size;
+        let published: Vec<CenoGoldilocks> = packed[2 * frame_size..4 * frame_size].to_vec();
+        setup
+            .setup
+            .verify_interval(&commitment, &interval, &published)
+            .unwrap();
+
+        let proof = setup
+            .prove_crop(&committed, vec![1..4], 1..4, 0..2)
+            .unwrap();
+        setup
+            .verify_crop(&commitment, &proof, &crop(&video, &[1..4], 1..4, 0..2))
+            .unwrap();
+    }
+
+    #[test]
+    fn tampered_pixel_is_rejected() {
+        let setup = CropSetup::new(GRID, packer());
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let proof = setup
+            .prove_crop(&committed, vec![1..4], 1..4, 0..2)
+            .unwrap();
+
+        let mut published = crop(&video, &[1..4], 1..4, 0..2);
+        published[7] ^= 1;
+        assert!(setup
+            .verify_crop(&committed.commitment(), &proof, &published)
+            .is_err());
+    }
+
+    #[test]
+    fn tampered_margin_is_rejected() {
+        let setup = CropSetup::new(GRID, packer());
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let published = crop(&video, &[1..4], 1..4, 0..2);
+
+        let mut proof = setup
+            .prove_crop(&committed, vec![1..4], 1..4, 0..2)
+            .unwrap();
+        proof.margins[3] ^= 1;
+        assert!(setup
+            .verify_crop(&committed.commitment(), &proof, &published)
+            .is_err());
+        proof.margins.pop();
+        assert!(matches!(
+            setup.verify_crop(&committed.commitment(), &proof, &published),
+            Err(RedactError::MalformedProof)
+        ));
+    }
+
+    #[test]
+    fn challenges_depend_on_revealed_elements() {
+        let setup = CropSetup::new(GRID, packer());
+        let committed = setup.commit_video(&test_video());
+        let commitment = committed.commitment();
+        let mut revealed: Vec<u64> = committed.evaluations[..8]
+            .iter()
+            .map(|v| v.as_canonical_u64())
+            .collect();
+        let point = |revealed: &[u64]| {
+            let mut transcript = crop_transcript(
+                &commitment,
+                &GRID,
+                &[1..4],
+                &(1..4),
+                &(0..2),
+                revealed.iter().copied(),
+            );
+            get_point_from_challenge(setup.setup.layout.num_vars(), &mut transcript)
+        };
+        let honest = point(&revealed);
+        revealed[7] ^= 1;
+        assert_ne!(point(&revealed), honest);
+    }
+
+    #[test]
+    fn shifted_rectangle_is_rejected() {
+        let setup = CropSetup::new(GRID, packer());
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let proof = setup
+            .prove_crop(&committed, vec![1..4], 1..4, 0..2)
+            .unwrap();
+
+        let mut shifted = proof.clone();
+        shifted.x = 2..5;
+        shifted.y = 1..3;
+        assert!(setup
+            .verify_crop(
+                &committed.commitment(),
+                &shifted,
+                &crop(&video, &[1..4], 2..5, 1..3)
+            )
+            .is_err());
+        assert!(setup
+            .verify_crop(
+                &committed.commitment(),
+                &shifted,
+                &crop(&video, &[1..4], 1..4, 0..2)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn out_of_frame_crop_is_an_error() {
+        let setup = CropSetup::new(GRID, packer());
+        let committed = setup.commit_video(&test_video());
+        assert!(matches!(
+            setup.prove_crop(&committed, vec![0..2], 3..6, 0..2),
+            Err(RedactError::InvalidCrop)
+        ));
+        assert!(matches!(
+            setup.prove_crop(&committed, vec![0..2], 2..2, 0..2),
+            Err(RedactError::InvalidCrop)
+        ));
+        for frames in [vec![2..4, 3..5], vec![4..5, 1..2], vec![5..7]] {
+            assert!(matches!(
+                setup.prove_crop(&committed, frames, 0..2, 0..2),
+                Err(RedactError::InvalidInterval)
+            ));
+        }
+    }
+}
@@ -0,0 +1,158 @@
+// Redaction proofs over the Basefold multilinear commitment.
+//
+// The video is laid out by `compute_video_mle_evaluations_vec`: frame `f`, element `j` sits at
+// index `f * frame_size_padded + j`, so the low `log2(frame_size_padded)` variables select the
+// element inside a frame and the high variables select the frame. `subcube` instead lays the
+// frames out back to back over a few power-of-two polynomials, so padding stays under one frame.
+
+pub mod compact;
+pub mod crop;
+pub mod multilinear;
+pub mod subcube;
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use ark_std::log2;
+use ff_ext::GoldilocksExt2;
+use mpcs::{Basefold, BasefoldRSParams, PolynomialCommitmentScheme};
+use multilinear_extensions::{mle::Point, virtual_poly::VPAuxInfo};
+use p3_field_git::{PrimeCharacteristicRing, PrimeField64};
+use p3_goldilocks_git::Goldilocks as CenoGoldilocks;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sumcheck::structs::{IOPProof, IOPVerifierState};
+use transcript::{BasicTranscript, Transcript};
+
+pub type E = GoldilocksExt2;
+pub type Pcs = Basefold<E, BasefoldRSParams>;
+pub type T = BasicTranscript<E>;
+pub type Commitment = <Pcs as PolynomialCommitmentScheme<E>>::Commitment;
+pub type PcsProof = <Pcs as PolynomialCommitmentScheme<E>>::Proof;
+
+// Domain separator every redaction transcript starts with.
+const TRANSCRIPT_LABEL: &[u8] = b"video-trimming redact";
+
+#[derive(Debug)]
+pub enum RedactError {
+    /// The requested frames are empty, unsorted or outside the video.
+    InvalidInterval,
+    /// The crop rectangle is empty or not inside the frame.
+    InvalidCrop,
+    /// The proof or the published frames do not have the expected shape.
+    MalformedProof,
+    /// The sumcheck proof did not verify.
+    Sumcheck,
+    /// The PCS opening proof did not verify.
+    Pcs(String),
+    /// The proof verifies but is inconsistent with the published frames.
+    EvaluationMismatch,
+}
+
+impl fmt::Display for RedactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedactError::InvalidInterval => write!(f, "frames are not inside the video"),
+            RedactError::InvalidCrop => write!(f, "crop rectangle is not inside the frame"),
+            RedactError::MalformedProof => write!(f, "malformed redaction proof"),
+            RedactError::Sumcheck => write!(f, "sumcheck proof rejected"),
+            RedactError::Pcs(e) => write!(f, "PCS opening proof rejected: {}", e),
+            RedactError::EvaluationMismatch => {
+                write!(f, "published frames do not match the signed video")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RedactError {}
+
+/// Shape of a committed video: `num_frames` frames of `frame_size` packed elements each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoLayout {
+    pub frame_size: usize,
+    pub num_frames: usize,
+}
+
+impl VideoLayout {
+    /// Number of variables selecting an element inside a frame.
+    pub fn frame_vars(&self) -> usize {
+        log2(self.frame_size.next_power_of_two()) as usize
+    }
+
+    /// Number of variables selecting a frame.
+    pub fn index_vars(&self) -> usize {
+        log2(self.num_frames.next_power_of_two()) as usize
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.frame_vars() + self.index_vars()
+    }
+}
+
+pub(crate) fn to_ext(x: &CenoGoldilocks) -> E {
+    E::from_u64(x.as_canonical_u64())
+}
+
+// Appends a SHA-256 digest of the published `values` to `transcript` as eight 32-bit limbs. It
+// has to go in before any challenge: a prover who knows the challenges before fixing the
+// published data can pick data that passes the checks at them without matching the video.
+pub(crate) fn append_published(transcript: &mut T, values: impl IntoIterator<Item = u64>) {
+    let mut hasher = Sha256::new();
+    for value in values {
+        hasher.update(value.to_le_bytes());
+    }
+    for limb in hasher.finalize().chunks(4) {
+        let limb = u32::from_le_bytes(limb.try_into().unwrap());
+        transcript.append_field_element_ext(&E::from_u64(limb as u64));
+    }
+}
+
+// eq(x, point) for every x in the hypercube, where bit k of the index is x_k.
+pub(crate) fn eq_table(point: &[E]) -> Vec<E> {
+    let mut table = vec![E::ONE];
+    for p in point {
+        let low: Vec<E> = table.iter().map(|t| *t * (E::ONE - *p)).collect();
+        let high: Vec<E> = table.iter().map(|t| *t * *p).collect();
+        table = low;
+        table.extend(high);
+    }
+    table
+}
+
+// eq(a, b) for two points of the same dimension.
+pub(crate) fn eq_eval(a: &[E], b: &[E]) -> E {
+    a.iter().zip(b).fold(E::ONE, |acc, (x, y)| {
+        acc * (*x * *y + (E::ONE - *x) * (E::ONE - *y))
+    })
+}
+
+// Runs the sumcheck verifier for a claimed sum over `num_vars` variables and returns the point it
+// reduces to together with the evaluation expected there.
+pub(crate) fn verify_sumcheck(
+    claimed_sum: E,
+    proof: &IOPProof<E>,
+    max_degree: usize,
+    num_vars: usize,
+    transcript: &mut T,
+) -> Result<(Point<E>, E), RedactError> {
+    // The sumcheck verifier panics on an inconsistent round message instead of returning an
+    // error.
+    let subclaim = panic::catch_unwind(AssertUnwindSafe(|| {
+        IOPVerifierState::<E>::verify(
+            claimed_sum,
+            proof,
+            &VPAuxInfo {
+                max_degree,
+                max_num_variables: num_vars,
+                ..Default::default()
+            },
+            transcript,
+        )
+    }))
+    .map_err(|_| RedactError::Sumcheck)?;
+    let point: Point<E> = subclaim.point.iter().map(|c| c.elements).collect();
+    if point.len() != num_vars {
+        return Err(RedactError::MalformedProof);
+    }
+    Ok((point, subclaim.expected_evaluation))
+}
@@ -0,0 +1,442 @@
+// Interval redaction with one sumcheck and one Basefold opening.
+//
+// To reveal frames [start, end) of the committed video v, the prover shows
+//   sum_x eq(r, x) * (m(x) * v(x) - p(x)) = 0
+// for a random r, where m is the 0/1 mask of the revealed elements and p is the published frames
+// laid out like v (zero elsewhere). r is drawn after the published frames are absorbed, so by
+// Schwartz-Zippel this forces m * v = p on the whole hypercube. The sumcheck reduces the claim to
+// one evaluation of v, which is opened with Basefold; the verifier evaluates eq, m and p at the
+// sumcheck point itself from the published frames.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use either::Either;
+use mpcs::{test_util::get_point_from_challenge, PolynomialCommitmentScheme, SecurityLevel};
+use multilinear_extensions::{
+    mle::ArcMultilinearExtension, monomial::Term, virtual_poly::build_eq_x_r,
+    virtual_polys::VirtualPolynomials,
+};
+use p3_field_git::{PrimeCharacteristicRing, PrimeField64};
+use p3_goldilocks_git::Goldilocks as CenoGoldilocks;
+use p3_matrix_git::dense::RowMajorMatrix as P3RowMajorMatrix;
+use serde::{Deserialize, Serialize};
+use sumcheck::structs::{IOPProof, IOPProverState};
+use transcript::Transcript;
+use witness::{InstancePaddingStrategy, RowMajorMatrix};
+
+use super::{
+    append_published, eq_eval, eq_table, to_ext, verify_sumcheck, Commitment, Pcs, PcsProof,
+    RedactError, VideoLayout, E, T, TRANSCRIPT_LABEL,
+};
+use crate::compute_video_mle_evaluations_vec;
+
+// Threads the sumcheck prover splits the virtual polynomial across.
+pub(super) const NUM_THREADS: usize = 8;
+
+type ProverParam = <Pcs as PolynomialCommitmentScheme<E>>::ProverParam;
+type VerifierParam = <Pcs as PolynomialCommitmentScheme<E>>::VerifierParam;
+type CommitmentWithWitness = <Pcs as PolynomialCommitmentScheme<E>>::CommitmentWithWitness;
+
+pub struct MultilinearSetup {
+    pub layout: VideoLayout,
+    pub(super) pp: ProverParam,
+    pub(super) vp: VerifierParam,
+}
+
+/// The signer's view of a committed video.
+pub struct CommittedVideo {
+    pub(super) comm: CommitmentWithWitness,
+    pub(super) evaluations: Vec<CenoGoldilocks>,
+}
+
+impl CommittedVideo {
+    /// The commitment to sign.
+    pub fn commitment(&self) -> Commitment {
+        Pcs::get_pure_commitment(&self.comm)
+    }
+}
+
+/// Sumcheck and opening proving that the masked part of a committed video is the published data.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MaskProof {
+    pub sumcheck: IOPProof<E>,
+    /// v at the point the sumcheck reduced to.
+    pub eval: E,
+    pub opening: PcsProof,
+}
+
+/// Proof that the published frames are frames [start, end) of a committed video.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IntervalProof {
+    pub start: usize,
+    pub end: usize,
+    pub proof: MaskProof,
+}
+
+pub(super) fn to_mle_matrix(evaluations: Vec<CenoGoldilocks>) -> RowMajorMatrix<CenoGoldilocks> {
+    RowMajorMatrix::new_by_inner_matrix(
+        P3RowMajorMatrix::new_col(evaluations),
+        InstancePaddingStrategy::Default,
+    )
+}
+
+// Writes everything the verifier's challenges must depend on into a fresh transcript.
+fn interval_transcript(
+    commitment: &Commitment,
+    layout: &VideoLayout,
+    interval: &Range<usize>,
+    published: impl IntoIterator<Item = u64>,
+) -> T {
+    let mut transcript = T::new(TRANSCRIPT_LABEL);
+    Pcs::write_commitment(commitment, &mut transcript).unwrap();
+    for x in [
+        layout.frame_size,
+        layout.num_frames,
+        interval.start,
+        interval.end,
+    ] {
+        transcript.append_field_element_ext(&E::from_u64(x as u64));
+    }
+    append_published(&mut transcript, published);
+    transcript
+}
+
+impl MultilinearSetup {
+    pub fn new(layout: VideoLayout) -> Self {
+        let poly_size = 1 << layout.num_vars();
+        let (pp, vp) = {
+            let param = Pcs::setup(poly_size, SecurityLevel::default()).unwrap();
+            Pcs::trim(param, poly_size).unwrap()
+        };
+        Self { layout, pp, vp }
+    }
+
+    /// Commits to `video`, the packed frames back to back.
+    pub fn commit_video(&self, video: &[CenoGoldilocks]) -> CommittedVideo {
+        let pixels: Vec<u64> = video.iter().map(|x| x.as_canonical_u64()).collect();
+        let evaluations = compute_video_mle_evaluations_vec(
+            &pixels,
+            self.layout.frame_size,
+            self.layout.num_frames,
+        );
+        self.commit_evaluations(evaluations)
+    }
+
+    pub(super) fn commit_evaluations(&self, evaluations: Vec<CenoGoldilocks>) -> CommittedVideo {
+        let rmms = BTreeMap::from([(0, to_mle_matrix(evaluations.clone()))]);
+        let comm =
+            Pcs::batch_commit_and_write(&self.pp, rmms, &mut T::new(TRANSCRIPT_LABEL)).unwrap();
+        CommittedVideo { comm, evaluations }
+    }
+
+    /// Proves sum_x eq(r, x) * (m(x) * v(x) - p(x)) = 0, where m is the indicator of the
+    /// indices `in_mask` accepts and p is v restricted to them. `transcript` must already hold
+    /// the commitment, everything that determines the mask and the published data.
+    pub(super) fn prove_mask(
+        &self,
+        video: &CommittedVideo,
+        mut transcript: T,
+        in_mask: impl Fn(usize) -> bool,
+    ) -> Result<MaskProof, RedactError> {
+        let num_vars = self.layout.num_vars();
+        let point = get_point_from_challenge(num_vars, &mut transcript);
+        let eq_x_r = build_eq_x_r(&point);
+
+        let mask = (0..video.evaluations.len())
+            .map(|i| {
+                if in_mask(i) {
+                    CenoGoldilocks::ONE
+                } else {
+                    CenoGoldilocks::ZERO
+                }
+            })
+            .collect();
+        let revealed = video
+            .evaluations
+            .iter()
+            .enumerate()
+            .map(|(i, v)| if in_mask(i) { *v } else { CenoGoldilocks::ZERO })
+            .collect();
+        let poly: ArcMultilinearExtension<_> = to_mle_matrix(video.evaluations.clone())
+            .to_mles::<E>()
+            .remove(0)
+            .into();
+        let mask_poly: ArcMultilinearExtension<_> =
+            to_mle_matrix(mask).to_mles::<E>().remove(0).into();
+        let revealed_poly: ArcMultilinearExtension<_> =
+            to_mle_matrix(revealed).to_mles::<E>().remove(0).into();
+
+        // We are sumchecking (m(x)*v(x) - p(x))*eq(r,x) = 0.
+        let sumcheck_poly = VirtualPolynomials::<E>::new_from_monimials(
+            NUM_THREADS,
+            num_vars,
+            vec![
+                Term {
+                    scalar: Either::Right(E::ONE),
+                    product: vec![
+                        Either::Left(&poly),
+                        Either::Left(&mask_poly),
+                        Either::Left(&eq_x_r),
+                    ],
+                },
+                Term {
+                    scalar: Either::Right(-E::ONE),
+                    product: vec![Either::Left(&revealed_poly), Either::Left(&eq_x_r)],
+                },
+            ],
+        );
+        let (sumcheck, _) = IOPProverState::<E>::prove(sumcheck_poly.as_view(), &mut transcript);
+
+        let eval = poly.evaluate(&sumcheck.point);
+        transcript.append_field_element_ext(&eval);
+        let opening = Pcs::batch_open(
+            &self.pp,
+            &[(0, 1 << num_vars)],
+            None,
+            &video.comm,
+            &[sumcheck.point.clone()],
+            &[vec![eval]],
+            &[(1, 0)],
+            &mut transcript,
+        )
+        .map_err(|e| RedactError::Pcs(format!("{:?}", e)))?;
+
+        Ok(MaskProof {
+            sumcheck,
+            eval,
+            opening,
+        })
+    }
+
+    /// Verifies a `prove_mask` proof. `evaluate` returns m and p at the point the sumcheck
+    /// reduces to, computed by the verifier from the published data.
+    pub(super) fn verify_mask(
+        &self,
+        commitment: &Commitment,
+        mut transcript: T,
+        proof: &MaskProof,
+        evaluate: impl FnOnce(&[E]) -> (E, E),
+    ) -> Result<(), RedactError> {
+        let num_vars = self.layout.num_vars();
+        let point = get_point_from_challenge(num_vars, &mut transcript);
+        let (sumcheck_r, expected_evaluation) =
+            verify_sumcheck(E::ZERO, &proof.sumcheck, 3, num_vars, &mut transcript)?;
+
+        transcript.append_field_element_ext(&proof.eval);
+        Pcs::batch_verify(
+            &self.vp,
+            &[(0, 1 << num_vars)],
+            &[sumcheck_r.clone()],
+            None,
+            commitment,
+            &[vec![proof.eval]],
+            &proof.opening,
+            &[(1, 0)],
+            &mut transcript,
+        )
+        .map_err(|e| RedactError::Pcs(format!("{:?}", e)))?;
+
+        let (mask_eval, revealed_eval) = evaluate(&sumcheck_r);
+        let inferred = eq_eval(&point, &sumcheck_r) * (proof.eval * mask_eval - revealed_eval);
+        if inferred == expected_evaluation {
+            Ok(())
+        } else {
+            Err(RedactError::EvaluationMismatch)
+        }
+    }
+
+    /// Proves that frames [start, end) of `video` are the published frames.
+    pub fn prove_interval(
+        &self,
+        video: &CommittedVideo,
+        start: usize,
+        end: usize,
+    ) -> Result<IntervalProof, RedactError> {
+        if start > end || end > self.layout.num_frames {
+            return Err(RedactError::InvalidInterval);
+        }
+        let interval = start..end;
+        let frame_size_padded = self.layout.frame_size.next_power_of_two();
+        let published = interval.clone().flat_map(|frame| {
+            let start = frame * frame_size_padded;
+            &video.evaluations[start..start + self.layout.frame_size]
+        });
+        let transcript = interval_transcript(
+            &video.commitment(),
+            &self.layout,
+            &interval,
+            published.map(|x| x.as_canonical_u64()),
+        );
+
+        let proof = self.prove_mask(video, transcript, |i| {
+            interval.contains(&(i / frame_size_padded))
+                && i % frame_size_padded < self.layout.frame_size
+        })?;
+
+        Ok(IntervalProof { start, end, proof })
+    }
+
+    /// Verifies `proof` against the signed `commitment` and `published`, the packed frames
+    /// [proof.start, proof.end) back to back.
+    pub fn verify_interval(
+        &self,
+        commitment: &Commitment,
+        proof: &IntervalProof,
+        published: &[CenoGoldilocks],
+    ) -> Result<(), RedactError> {
+        let layout = &self.layout;
+        if proof.start > proof.end || proof.end > layout.num_frames {
+            return Err(RedactError::InvalidInterval);
+        }
+        if published.len() != (proof.end - proof.start) * layout.frame_size {
+            return Err(RedactError::MalformedProof);
+        }
+        let interval = proof.start..proof.end;
+        let transcript = interval_transcript(
+            commitment,
+            layout,
+            &interval,
+            published.iter().map(|x| x.as_canonical_u64()),
+        );
+
+        self.verify_mask(commitment, transcript, &proof.proof, |sumcheck_r| {
+            // m and p factor into a frame part and an element part, so both are cheap to
+            // evaluate from the published frames.
+            let (element_point, frame_point) = sumcheck_r.split_at(layout.frame_vars());
+            let eq_element = eq_table(element_point);
+            let eq_frame = eq_table(frame_point);
+
+            let element_mask = eq_element[..layout.frame_size]
+                .iter()
+                .fold(E::ZERO, |acc, e| acc + *e);
+            let mut frame_mask = E::ZERO;
+            let mut revealed_eval = E::ZERO;
+            for (frame, values) in interval.zip(published.chunks(layout.frame_size)) {
+                frame_mask += eq_frame[frame];
+                let frame_eval = values
+                    .iter()
+                    .zip(&eq_element)
+                    .fold(E::ZERO, |acc, (v, e)| acc + to_ext(v) * *e);
+                revealed_eval += eq_frame[frame] * frame_eval;
+            }
+            (frame_mask * element_mask, revealed_eval)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 12 elements per frame and 10 frames, so neither dimension is a power of two.
+    const LAYOUT: VideoLayout = VideoLayout {
+        frame_size: 12,
+        num_frames: 10,
+    };
+
+    fn test_video() -> Vec<CenoGoldilocks> {
+        (0..(LAYOUT.frame_size * LAYOUT.num_frames) as u64)
+            .map(|i| CenoGoldilocks::from_u64(i * 7919 + 3))
+            .collect()
+    }
+
+    fn frames(video: &[CenoGoldilocks], start: usize, end: usize) -> Vec<CenoGoldilocks> {
+        video[start * LAYOUT.frame_size..end * LAYOUT.frame_size].to_vec()
+    }
+
+    #[test]
+    fn unaligned_intervals_verify() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let commitment = committed.commitment();
+        for (start, end) in [(3, 7), (1, 10), (0, 1), (9, 10), (0, 10), (5, 5)] {
+            let proof = setup.prove_interval(&committed, start, end).unwrap();
+            setup
+                .verify_interval(&commitment, &proof, &frames(&video, start, end))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn tampered_frames_are_rejected() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let proof = setup.prove_interval(&committed, 3, 7).unwrap();
+
+        let mut published = frames(&video, 3, 7);
+        published[13] += CenoGoldilocks::ONE;
+        assert!(setup
+            .verify_interval(&committed.commitment(), &proof, &published)
+            .is_err());
+    }
+
+    #[test]
+    fn challenges_depend_on_published_frames() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let commitment = setup.commit_video(&video).commitment();
+        let mut published = frames(&video, 3, 7);
+        let point = |published: &[CenoGoldilocks]| {
+            let mut transcript = interval_transcript(
+                &commitment,
+                &LAYOUT,
+                &(3..7),
+                published.iter().map(|x| x.as_canonical_u64()),
+            );
+            get_point_from_challenge(LAYOUT.num_vars(), &mut transcript)
+        };
+        let honest = point(&published);
+        published[13] += CenoGoldilocks::ONE;
+        assert_ne!(point(&published), honest);
+    }
+
+    #[test]
+    fn shifted_interval_is_rejected() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let proof = setup.prove_interval(&committed, 3, 7).unwrap();
+
+        let mut shifted = proof.clone();
+        shifted.start = 4;
+        shifted.end = 8;
+        assert!(setup
+            .verify_interval(&committed.commitment(), &shifted, &frames(&video, 4, 8))
+            .is_err());
+        // The right frames under the wrong interval must not verify either.
+        assert!(setup
+            .verify_interval(&committed.commitment(), &shifted, &frames(&video, 3, 7))
+            .is_err());
+    }
+
+    #[test]
+    fn proofs_round_trip_through_bincode() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let proof = setup.prove_interval(&committed, 2, 9).unwrap();
+
+        let bytes = bincode::serialize(&proof).unwrap();
+        let decoded: IntervalProof = bincode::deserialize(&bytes).unwrap();
+        setup
+            .verify_interval(&committed.commitment(), &decoded, &frames(&video, 2, 9))
+            .unwrap();
+    }
+
+    #[test]
+    fn out_of_range_interval_is_an_error() {
+        let setup = MultilinearSetup::new(LAYOUT);
+        let committed = setup.commit_video(&test_video());
+        assert!(matches!(
+            setup.prove_interval(&committed, 8, 11),
+            Err(RedactError::InvalidInterval)
+        ));
+        assert!(matches!(
+            setup.prove_interval(&committed, 4, 3),
+            Err(RedactError::InvalidInterval)
+        ));
+    }
+}
@@ -0,0 +1,420 @@
+// Committing a video as a concatenation of subcubes instead of one padded hypercube.
+//
+// `compute_video_mle_evaluations_vec` pads both the frame size and the frame count to powers of
+// two, so 250 frames of 460800 elements are committed as 256 frames of 524288, 16% more work than
+// the video itself. Here the frames are laid out back to back, element j of frame f at
+// f * frame_size + j, and the length is rounded up to a multiple of a granule of 2^g elements,
+// with 2^g at most one frame. Written in binary, the rounded length is a sum of a few powers of
+// two, each at least a granule; each is a subcube committed as its own multilinear polynomial,
+// largest first. The padding is less than a granule, so less than one frame.
+//
+// Frames [start, end) are the elements [start * frame_size, end * frame_size), which meet every
+// subcube in one contiguous range, and each subcube they meet gets a mask proof from
+// `multilinear`. To evaluate the mask and the published elements over a contiguous range, the
+// verifier splits it into aligned dyadic blocks: on the block of 2^m indices starting at c * 2^m,
+// eq(r, i) is eq of the high variables with the bits of c, as `to_binary_vec` builds them for a
+// block of frames, times an eq table over the low m variables.
+
+use std::ops::Range;
+
+use mpcs::PolynomialCommitmentScheme;
+use p3_field_git::{PrimeCharacteristicRing, PrimeField64};
+use p3_goldilocks_git::Goldilocks as CenoGoldilocks;
+use serde::{Deserialize, Serialize};
+use transcript::Transcript;
+
+use super::multilinear::{CommittedVideo, MaskProof, MultilinearSetup};
+use super::{
+    append_published, eq_table, to_ext, Commitment, Pcs, RedactError, VideoLayout, E, T,
+    TRANSCRIPT_LABEL,
+};
+use crate::compute_video_subcube_evaluations;
+
+// Subcubes are never smaller than this, so every sumcheck has variables to split across threads.
+// For frames under 16 elements the padding bound is 16 elements rather than one frame.
+const MIN_CUBE_VARS: usize = 4;
+
+/// A video of `video.num_frames` frames of `video.frame_size` elements, back to back, split into
+/// power-of-two subcubes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubcubeLayout {
+    pub video: VideoLayout,
+    /// Element ranges of the subcubes, largest first, covering the video and its padding.
+    pub cubes: Vec<Range<usize>>,
+}
+
+impl SubcubeLayout {
+    pub fn new(video: VideoLayout) -> Self {
+        let log_granule = (video.frame_size.max(1).ilog2() as usize).max(MIN_CUBE_VARS);
+        let granule = 1usize << log_granule;
+        let padded_len = (video.frame_size * video.num_frames)
+            .div_ceil(granule)
+            .max(1)
+            * granule;
+
+        let mut cubes = Vec::new();
+        let mut offset = 0;
+        for bit in (log_granule..usize::BITS as usize).rev() {
+            let size = 1usize << bit;
+            if padded_len & size != 0 {
+                cubes.push(offset..offset + size);
+                offset += size;
+            }
+        }
+        Self { video, cubes }
+    }
+
+    /// Number of committed elements, padding included.
+    pub fn padded_len(&self) -> usize {
+        self.cubes.last().unwrap().end
+    }
+}
+
+/// The signer's view of a video committed as subcubes.
+pub struct CommittedSubcubes {
+    cubes: Vec<CommittedVideo>,
+}
+
+impl CommittedSubcubes {
+    /// The commitments to sign, one per subcube.
+    pub fn commitments(&self) -> Vec<Commitment> {
+        self.cubes.iter().map(|cube| cube.commitment()).collect()
+    }
+}
+
+/// Proof that the published frames are frames [start, end) of a video committed as subcubes:
+/// one mask proof for every subcube the frames meet, in order.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SubcubeIntervalProof {
+    pub start: usize,
+    pub end: usize,
+    pub proofs: Vec<MaskProof>,
+}
+
+// Writes everything the verifier's challenges for one subcube must depend on into a fresh
+// transcript, including `published`, the published elements that fall inside the subcube.
+fn cube_transcript(
+    commitment: &Commitment,
+    layout: &VideoLayout,
+    cube: &Range<usize>,
+    interval: &Range<usize>,
+    published: &[CenoGoldilocks],
+) -> T {
+    let mut transcript = T::new(TRANSCRIPT_LABEL);
+    Pcs::write_commitment(commitment, &mut transcript).unwrap();
+    for x in [
+        layout.frame_size,
+        layout.num_frames,
+        cube.start,
+        cube.end,
+        interval.start,
+        interval.end,
+    ] {
+        transcript.append_field_element_ext(&E::from_u64(x as u64));
+    }
+    append_published(
+        &mut transcript,
+        published.iter().map(|x| x.as_canonical_u64()),
+    );
+    transcript
+}
+
+// The part of `elements` inside `cube`, relative to the start of the cube.
+fn overlap(cube: &Range<usize>, elements: &Range<usize>) -> Option<Range<usize>> {
+    let start = cube.start.max(elements.start);
+    let end = cube.end.min(elements.end);
+    (start < end).then(|| start - cube.start..end - cube.start)
+}
+
+// eq(point, i) for the hypercube vertex whose bit k is i_k.
+fn eq_at_index(point: &[E], index: usize) -> E {
+    point.iter().enumerate().fold(E::ONE, |acc, (k, p)| {
+        if (index >> k) & 1 == 1 {
+            acc * *p
+        } else {
+            acc * (E::ONE - *p)
+        }
+    })
+}
+
+// Returns sum_{i in range} eq(point, i) and sum_{i in range} eq(point, i) * values[i - start],
+// walking `range` in the largest aligned dyadic blocks that fit.
+fn range_evaluations(point: &[E], range: Range<usize>, values: &[CenoGoldilocks]) -> (E, E) {
+    let mut mask = E::ZERO;
+    let mut revealed = E::ZERO;
+    let mut i = range.start;
+    while i < range.end {
+        let mut log_size = if i == 0 {
+            point.len()
+        } else {
+            (i.trailing_zeros() as usize).min(point.len())
+        };
+        while i + (1 << log_size) > range.end {
+            log_size -= 1;
+        }
+        let (low, high) = point.split_at(log_size);
+        let high_eq = eq_at_index(high, i >> log_size);
+        // The low eq table sums to one, so the block adds high_eq to the mask.
+        mask += high_eq;
+        let block = &values[i - range.start..i - range.start + (1 << log_size)];
+        let block_eval = block
+            .iter()
+            .zip(eq_table(low))
+            .fold(E::ZERO, |acc, (v, e)| acc + to_ext(v) * e);
+        revealed += high_eq * block_eval;
+        i += 1 << log_size;
+    }
+    (mask, revealed)
+}
+
+pub struct SubcubeSetup {
+    pub layout: SubcubeLayout,
+    // One setup per subcube, each a single "frame" of the subcube's size.
+    setups: Vec<MultilinearSetup>,
+}
+
+impl SubcubeSetup {
+    pub fn new(video: VideoLayout) -> Self {
+        let layout = SubcubeLayout::new(video);
+        let setups = layout
+            .cubes
+            .iter()
+            .map(|cube| {
+                MultilinearSetup::new(VideoLayout {
+                    frame_size: cube.len(),
+                    num_frames: 1,
+                })
+            })
+            .collect();
+        Self { layout, setups }
+    }
+
+    /// Commits to `video`, the packed frames back to back.
+    pub fn commit_video(&self, video: &[CenoGoldilocks]) -> CommittedSubcubes {
+        let layout = &self.layout.video;
+        assert_eq!(
+            video.len(),
+            layout.frame_size * layout.num_frames,
+            "video does not match the layout"
+        );
+        let pixels: Vec<u64> = video.iter().map(|x| x.as_canonical_u64()).collect();
+        let cubes = compute_video_subcube_evaluations(&pixels, &self.layout.cubes)
+            .into_iter()
+            .zip(&self.setups)
+            .map(|(evaluations, setup)| setup.commit_evaluations(evaluations))
+            .collect();
+        CommittedSubcubes { cubes }
+    }
+
+    fn revealed_elements(&self, start: usize, end: usize) -> Result<Range<usize>, RedactError> {
+        let video = &self.layout.video;
+        if start > end || end > video.num_frames {
+            return Err(RedactError::InvalidInterval);
+        }
+        Ok(start * video.frame_size..end * video.frame_size)
+    }
+
+    /// Proves that frames [start, end) of `video` are the published frames.
+    pub fn prove_interval(
+        &self,
+        video: &CommittedSubcubes,
+        start: usize,
+        end: usize,
+    ) -> Result<SubcubeIntervalProof, RedactError> {
+        let revealed = self.revealed_elements(start, end)?;
+        let mut proofs = Vec::new();
+        for ((cube, setup), committed) in
+            self.layout.cubes.iter().zip(&self.setups).zip(&video.cubes)
+        {
+            let Some(local) = overlap(cube, &revealed) else {
+                continue;
+            };
+            let transcript = cube_transcript(
+                &committed.commitment(),
+                &self.layout.video,
+                cube,
+                &(start..end),
+                &committed.evaluations[local.clone()],
+            );
+            proofs.push(setup.prove_mask(committed, transcript, |i| local.contains(&i))?);
+        }
+        Ok(SubcubeIntervalProof { start, end, proofs })
+    }
+
+    /// Verifies `proof` against the signed `commitments`, one per subcube, and `published`, the
+    /// packed frames [proof.start, proof.end) back to back.
+    pub fn verify_interval(
+        &self,
+        commitments: &[Commitment],
+        proof: &SubcubeIntervalProof,
+        published: &[CenoGoldilocks],
+    ) -> Result<(), RedactError> {
+        let revealed = self.revealed_elements(proof.start, proof.end)?;
+        if commitments.len() != self.layout.cubes.len() || published.len() != revealed.len() {
+            return Err(RedactError::MalformedProof);
+        }
+
+        let mut mask_proofs = proof.proofs.iter();
+        for ((cube, setup), commitment) in
+            self.layout.cubes.iter().zip(&self.setups).zip(commitments)
+        {
+            let Some(local) = overlap(cube, &revealed) else {
+                continue;
+            };
+            let mask_proof = mask_proofs.next().ok_or(RedactError::MalformedProof)?;
+            let offset = cube.start + local.start - revealed.start;
+            let values = &published[offset..offset + local.len()];
+            let transcript = cube_transcript(
+                commitment,
+                &self.layout.video,
+                cube,
+                &(proof.start..proof.end),
+                values,
+            );
+            setup.verify_mask(commitment, transcript, mask_proof, |sumcheck_r| {
+                range_evaluations(sumcheck_r, local, values)
+            })?;
+        }
+        if mask_proofs.next().is_some() {
+            return Err(RedactError::MalformedProof);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mpcs::test_util::get_point_from_challenge;
+
+    use super::*;
+    use crate::random_felt_ceno;
+
+    // 140 elements round up to 144 = 128 + 16, so frame 6 straddles two subcubes.
+    const LAYOUT: VideoLayout = VideoLayout {
+        frame_size: 20,
+        num_frames: 7,
+    };
+
+    fn test_video() -> Vec<CenoGoldilocks> {
+        (0..(LAYOUT.frame_size * LAYOUT.num_frames) as u64)
+            .map(|i| CenoGoldilocks::from_u64(i * 7919 + 3))
+            .collect()
+    }
+
+    fn frames(video: &[CenoGoldilocks], start: usize, end: usize) -> Vec<CenoGoldilocks> {
+        video[start * LAYOUT.frame_size..end * LAYOUT.frame_size].to_vec()
+    }
+
+    #[test]
+    fn padding_is_less_than_a_frame() {
+        assert_eq!(SubcubeLayout::new(LAYOUT).cubes, vec![0..128, 128..144]);
+
+        // 250 frames of 720p RGB packed 6 bytes to an element.
+        let video = VideoLayout {
+            frame_size: 460800,
+            num_frames: 250,
+        };
+        let layout = SubcubeLayout::new(video);
+        let len = video.frame_size * video.num_frames;
+        assert!(layout.padded_len() - len < video.frame_size);
+        assert_eq!(layout.cubes.len(), 5);
+        for cube in &layout.cubes {
+            assert!(cube.len().is_power_of_two());
+            assert_eq!(cube.start % cube.len(), 0);
+        }
+    }
+
+    #[test]
+    fn range_evaluations_match_the_full_eq_table() {
+        let point: Vec<E> = (1..=5).map(random_felt_ceno).collect();
+        let table = eq_table(&point);
+        let values: Vec<CenoGoldilocks> = (0..32)
+            .map(|i| CenoGoldilocks::from_u64(i * 31 + 7))
+            .collect();
+        for range in [0..32, 3..29, 5..6, 16..32, 0..7] {
+            let expected_mask = table[range.clone()].iter().fold(E::ZERO, |acc, e| acc + *e);
+            let expected_revealed = range
+                .clone()
+                .fold(E::ZERO, |acc, i| acc + table[i] * to_ext(&values[i]));
+            assert_eq!(
+                range_evaluations(&point, range.clone(), &values[range]),
+                (expected_mask, expected_revealed)
+            );
+        }
+    }
+
+    #[test]
+    fn intervals_across_subcubes_verify() {
+        let setup = SubcubeSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let commitments = committed.commitments();
+        for (start, end) in [(0, 7), (2, 5), (6, 7), (5, 7), (0, 1), (3, 3)] {
+            let proof = setup.prove_interval(&committed, start, end).unwrap();
+            setup
+                .verify_interval(&commitments, &proof, &frames(&video, start, end))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn tampered_frames_are_rejected() {
+        let setup = SubcubeSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let proof = setup.prove_interval(&committed, 5, 7).unwrap();
+
+        // Element 35 of the published frames is element 135 of the video, in the second subcube.
+        let mut published = frames(&video, 5, 7);
+        published[35] += CenoGoldilocks::ONE;
+        assert!(setup
+            .verify_interval(&committed.commitments(), &proof, &published)
+            .is_err());
+    }
+
+    #[test]
+    fn challenges_depend_on_published_elements() {
+        let setup = SubcubeSetup::new(LAYOUT);
+        let video = test_video();
+        let commitment = setup.commit_video(&video).commitments().remove(0);
+        let cube = &setup.layout.cubes[0];
+        let mut published = video[20..60].to_vec();
+        let point = |published: &[CenoGoldilocks]| {
+            let mut transcript = cube_transcript(&commitment, &LAYOUT, cube, &(1..3), published);
+            get_point_from_challenge(cube.len().ilog2() as usize, &mut transcript)
+        };
+        let honest = point(&published);
+        published[3] += CenoGoldilocks::ONE;
+        assert_ne!(point(&published), honest);
+    }
+
+    #[test]
+    fn shifted_interval_is_rejected() {
+        let setup = SubcubeSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let proof = setup.prove_interval(&committed, 2, 4).unwrap();
+
+        let mut shifted = proof.clone();
+        shifted.start = 3;
+        shifted.end = 5;
+        assert!(setup
+            .verify_interval(&committed.commitments(), &shifted, &frames(&video, 3, 5))
+            .is_err());
+    }
+
+    #[test]
+    fn dropped_subcube_proof_is_rejected() {
+        let setup = SubcubeSetup::new(LAYOUT);
+        let video = test_video();
+        let committed = setup.commit_video(&video);
+        let mut proof = setup.prove_interval(&committed, 5, 7).unwrap();
+        assert_eq!(proof.proofs.len(), 2);
+
+        proof.proofs.pop();
+        assert!(matches!(
+            setup.verify_interval(&committed.commitments(), &proof, &frames(&video, 5, 7)),
+            Err(RedactError::MalformedProof)
+        ));
+    }
+}
@@ -0,0 +1,774 @@
+// One interface over every trimming backend.
+//
+// A redactable signature signs a commitment to the whole video with any `VideoSigner`; redacting
+// keeps a contiguous range of frames and proves, against the signed commitment, that the published
+// frames are exactly those frames. The backends differ only in the commitment and the proof, so
+// each one implements the commitment-level methods of `RedactableSignatureScheme` and inherits
+// signing and signature checking from the provided methods. All of them take and publish raw
+// planar frame bytes and pack them into their own field internally.
+//
+// Implemented by the Merkle, Goldilocks FRI, Ligero, sumcheck, subcube and Basefold dyadic-block
+// backends; `conformance_tests!` runs the same suite against each of them.
+
+use std::fmt;
+use std::ops::Range;
+
+use p3_field::PrimeField32;
+
+use crate::fri_params::{self, FriField, SoundnessModel, DEFAULT_SECURITY_BITS};
+use crate::ligero::{self, LigeroSetup};
+use crate::merkle::{Digest, FrameTree, MerkleError, MerkleHasher, MultiProof};
+use crate::packing::{
+    ArkBls12381Packer, BabyBearPacker, CenoGoldilocksPacker, GoldilocksPacker, Packer,
+};
+use crate::redact::compact::CompactProof;
+use crate::redact::multilinear::{CommittedVideo, IntervalProof, MultilinearSetup};
+use crate::redact::subcube::{CommittedSubcubes, SubcubeIntervalProof, SubcubeSetup};
+use crate::redact::{self, RedactError, VideoLayout};
+use crate::signing::{PublicKey, SigningError, VideoSigner};
+use crate::univariate::{
+    self, commitment_to_bytes, FriSetup, TrimError, TrimProof, VideoCommitment,
+};
+
+// Prepended to everything that is signed, so a signature can't be replayed across formats.
+const SIGNING_DOMAIN: &[u8] = b"video-trimming redactable signature";
+
+/// Geometry every backend is set up for: `num_frames` frames of `frame_len` bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VideoShape {
+    pub frame_len: usize,
+    pub num_frames: usize,
+}
+
+/// A signed commitment to a whole video.
+#[derive(Clone, Debug)]
+pub struct Signature<C> {
+    pub commitment: C,
+    pub signature: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum SchemeError<E> {
+    /// The signature over the commitment did not verify.
+    Signature(SigningError),
+    /// The proof is for frames other than the ones being checked.
+    WrongFrames,
+    /// The backend rejected the proof.
+    Proof(E),
+}
+
+impl<E: fmt::Display> fmt::Display for SchemeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemeError::Signature(e) => write!(f, "signature rejected: {}", e),
+            SchemeError::WrongFrames => write!(f, "proof is for different frames"),
+            SchemeError::Proof(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for SchemeError<E> {}
+
+pub trait RedactableSignatureScheme: Sized {
+    type Commitment: Clone;
+    /// What the signer keeps around to redact later.
+    type ProverState;
+    type Proof: Clone;
+    type Error: std::error::Error;
+
+    const NAME: &'static str;
+
+    /// Sets the backend up for videos of `shape`, or fails if it can't serve them.
+    fn setup(shape: VideoShape) -> Result<Self, Self::Error>;
+
+    fn shape(&self) -> VideoShape;
+
+    /// Commits to `video`, the planar frames back to back.
+    fn commit(&self, video: &[u8]) -> (Self::Commitment, Self::ProverState);
+
+    /// Canonical encoding of a commitment, which is what gets signed.
+    fn commitment_bytes(commitment: &Self::Commitment) -> Vec<u8>;
+
+    /// Proves that frames `kept` are part of the committed video.
+    fn redact(
+        &self,
+        state: &Self::ProverState,
+        kept: Range<usize>,
+    ) -> Result<Self::Proof, Self::Error>;
+
+    /// The frames a proof claims to reveal.
+    fn kept_frames(proof: &Self::Proof) -> Vec<Range<usize>>;
+
+    /// Checks `proof` against `commitment` and `published`, the kept frames back to back.
+    fn verify_proof(
+        &self,
+        commitment: &Self::Commitment,
+        proof: &Self::Proof,
+        published: &[u8],
+    ) -> Result<(), Self::Error>;
+
+    /// Serialized size of `proof` in bytes.
+    fn proof_size(proof: &Self::Proof) -> usize;
+
+    fn signing_payload(&self, commitment: &Self::Commitment) -> Vec<u8> {
+        let shape = self.shape();
+        let mut payload = SIGNING_DOMAIN.to_vec();
+        payload.extend(Self::NAME.as_bytes());
+        payload.extend((shape.frame_len as u64).to_le_bytes());
+        payload.extend((shape.num_frames as u64).to_le_bytes());
+        payload.extend(Self::commitment_bytes(commitment));
+        payload
+    }
+
+    /// Commits to `video` and signs the commitment.
+    fn sign(
+        &self,
+        signer: &dyn VideoSigner,
+        video: &[u8],
+    ) -> (Signature<Self::Commitment>, Self::ProverState) {
+        let (commitment, state) = self.commit(video);
+        let signature = signer.sign(&self.signing_payload(&commitment));
+        (
+            Signature {
+                commitment,
+                signature,
+            },
+            state,
+        )
+    }
+
+    /// Checks that `published` are frames `kept` of a video signed by `public_key`.
+    fn verify(
+        &self,
+        public_key: &PublicKey,
+        signature: &Signature<Self::Commitment>,
+        kept: Range<usize>,
+        proof: &Self::Proof,
+        published: &[u8],
+    ) -> Result<(), SchemeError<Self::Error>> {
+        public_key
+            .verify(
+                &self.signing_payload(&signature.commitment),
+                &signature.signature,
+            )
+            .map_err(SchemeError::Signature)?;
+        if Self::kept_frames(proof) != [kept] {
+            return Err(SchemeError::WrongFrames);
+        }
+        self.verify_proof(&signature.commitment, proof, published)
+            .map_err(SchemeError::Proof)
+    }
+}
+
+/// Poseidon2 Merkle tree over BabyBear, one leaf per frame.
+pub struct MerkleScheme {
+    shape: VideoShape,
+    hasher: MerkleHasher,
+    packer: BabyBearPacker,
+}
+
+impl MerkleScheme {
+    fn frame_size(&self) -> usize {
+        self.packer.elements_per_frame(self.shape.frame_len)
+    }
+}
+
+impl RedactableSignatureScheme for MerkleScheme {
+    type Commitment = Digest;
+    type ProverState = FrameTree;
+    type Proof = MultiProof;
+    type Error = MerkleError;
+
+    const NAME: &'static str = "merkle";
+
+    fn setup(shape: VideoShape) -> Result<Self, Self::Error> {
+        Ok(Self {
+            shape,
+            hasher: MerkleHasher::new(18),
+            packer: BabyBearPacker::new(3, true),
+        })
+    }
+
+    fn shape(&self) -> VideoShape {
+        self.shape
+    }
+
+    fn commit(&self, video: &[u8]) -> (Digest, FrameTree) {
+        let packed = self.packer.pack(video, self.shape.frame_len);
+        let tree = FrameTree::new(&self.hasher, &packed, self.frame_size());
+        (tree.root(), tree)
+    }
+
+    fn commitment_bytes(commitment: &Digest) -> Vec<u8> {
+        commitment
+            .iter()
+            .flat_map(|x| x.as_canonical_u32().to_le_bytes())
+            .collect()
+    }
+
+    fn redact(&self, tree: &FrameTree, kept: Range<usize>) -> Result<MultiProof, MerkleError> {
+        tree.prove(&[kept])
+    }
+
+    fn kept_frames(proof: &MultiProof) -> Vec<Range<usize>> {
+        proof.ranges.clone()
+    }
+
+    fn verify_proof(
+        &self,
+        commitment: &Digest,
+        proof: &MultiProof,
+        published: &[u8],
+    ) -> Result<(), MerkleError> {
+        let packed = self.packer.pack(published, self.shape.frame_len);
+        proof.verify(
+            &self.hasher,
+            commitment,
+            self.shape.num_frames,
+            &packed,
+            self.frame_size(),
+        )
+    }
+
+    fn proof_size(proof: &MultiProof) -> usize {
+        proof.size_in_bytes()
+    }
+}
+
+/// The packed video as the coefficients of a Goldilocks polynomial, committed with FRI.
+pub struct FriScheme {
+    shape: VideoShape,
+    setup: FriSetup,
+    packer: GoldilocksPacker,
+}
+
+impl FriScheme {
+    fn frame_size(&self) -> usize {
+        self.packer.elements_per_frame(self.shape.frame_len)
+    }
+
+    fn log_degree(&self) -> usize {
+        (self.frame_size() * self.shape.num_frames)
+            .next_power_of_two()
+            .trailing_zeros() as usize
+    }
+}
+
+impl RedactableSignatureScheme for FriScheme {
+    type Commitment = univariate::Commitment;
+    type ProverState = VideoCommitment;
+    type Proof = TrimProof;
+    type Error = TrimError;
+
+    const NAME: &'static str = "fri";
+
+    // Parameters for `DEFAULT_SECURITY_BITS` of conjectured security at the size of the video, if
+    // any reach it.
+    fn setup(shape: VideoShape) -> Result<Self, Self::Error> {
+        let packer = GoldilocksPacker::new(6, true);
+        let log_degree = (packer.elements_per_frame(shape.frame_len) * shape.num_frames)
+            .next_power_of_two()
+            .trailing_zeros() as usize;
+        let selection = fri_params::select(
+            DEFAULT_SECURITY_BITS,
+            SoundnessModel::Conjectured,
+            FriField::GoldilocksExt2,
+            log_degree,
+        )
+        .map_err(TrimError::Parameters)?;
+        Ok(Self {
+            shape,
+            setup: FriSetup::new(selection.params),
+            packer,
+        })
+    }
+
+    fn shape(&self) -> VideoShape {
+        self.shape
+    }
+
+    fn commit(&self, video: &[u8]) -> (univariate::Commitment, VideoCommitment) {
+        let committed = self
+            .setup
+            .commit_video(self.packer.pack(video, self.shape.frame_len));
+        (committed.commitment.clone(), committed)
+    }
+
+    fn commitment_bytes(commitment: &univariate::Commitment) -> Vec<u8> {
+        commitment_to_bytes(commitment)
+    }
+
+    fn redact(&self, video: &VideoCommitment, kept: Range<usize>) -> Result<TrimProof, TrimError> {
+        self.setup
+            .prove_trim(video, self.frame_size(), self.shape.num_frames, kept)
+    }
+
+    fn kept_frames(proof: &TrimProof) -> Vec<Range<usize>> {
+        proof.kept.clone()
+    }
+
+    fn verify_proof(
+        &self,
+        commitment: &univariate::Commitment,
+        proof: &TrimProof,
+        published: &[u8],
+    ) -> Result<(), TrimError> {
+        let packed = self.packer.pack(published, self.shape.frame_len);
+        self.setup.verify_trim(
+            commitment,
+            self.log_degree(),
+            self.frame_size(),
+            self.shape.num_frames,
+            proof,
+            &packed,
+        )
+    }
+
+    fn proof_size(proof: &TrimProof) -> usize {
+        bincode::serialized_size(proof).unwrap() as usize
+    }
+}
+
+/// The packed video as the coefficients of a BLS12-381 scalar field polynomial, committed with
+/// Ligero, which needs no trusted setup and only hashing.
+pub struct LigeroScheme {
+    shape: VideoShape,
+    setup: LigeroSetup,
+    packer: ArkBls12381Packer,
+}
+
+impl LigeroScheme {
+    fn frame_size(&self) -> usize {
+        self.packer.elements_per_frame(self.shape.frame_len)
+    }
+}
+
+impl RedactableSignatureScheme for LigeroScheme {
+    type Commitment = ligero::Commitment;
+    type ProverState = ligero::VideoCommitment;
+    type Proof = ligero::TrimProof;
+    type Error = TrimError;
+
+    const NAME: &'static str = "ligero";
+
+    fn setup(shape: VideoShape) -> Result<Self, Self::Error> {
+        Ok(Self {
+            shape,
+            setup: LigeroSetup::new(),
+            packer: ArkBls12381Packer::new(30, true),
+        })
+    }
+
+    fn shape(&self) -> VideoShape {
+        self.shape
+    }
+
+    fn commit(&self, video: &[u8]) -> (ligero::Commitment, ligero::VideoCommitment) {
+        let committed = self
+            .setup
+            .commit_video(self.packer.pack(video, self.shape.frame_len));
+        (committed.commitment.clone(), committed)
+    }
+
+    fn commitment_bytes(commitment: &ligero::Commitment) -> Vec<u8> {
+        ligero::commitment_to_bytes(commitment)
+    }
+
+    fn redact(
+        &self,
+        video: &ligero::VideoCommitment,
+        kept: Range<usize>,
+    ) -> Result<ligero::TrimProof, TrimError> {
+        self.setup.prove_trim(video, self.frame_size(), kept)
+    }
+
+    fn kept_frames(proof: &ligero::TrimProof) -> Vec<Range<usize>> {
+        vec![proof.start..proof.end]
+    }
+
+    fn verify_proof(
+        &self,
+        commitment: &ligero::Commitment,
+        proof: &ligero::TrimProof,
+        published: &[u8],
+    ) -> Result<(), TrimError> {
+        let frame_size = self.frame_size();
+        let packed = self.packer.pack(published, self.shape.frame_len);
+        self.setup.verify_trim(
+            commitment,
+            frame_size * self.shape.num_frames,
+            frame_size,
+            proof,
+            &packed,
+        )
+    }
+
+    fn proof_size(proof: &ligero::TrimProof) -> usize {
+        proof.size_in_bytes()
+    }
+}
+
+// The Basefold layout of a video of `shape` packed by `packer`.
+fn basefold_layout(shape: VideoShape, packer: &CenoGoldilocksPacker) -> VideoLayout {
+    VideoLayout {
+        frame_size: packer.elements_per_frame(shape.frame_len),
+        num_frames: shape.num_frames,
+    }
+}
+
+/// The packed video as a multilinear polynomial committed with Basefold, revealing the kept range
+/// with one masked sumcheck.
+pub struct SumcheckScheme {
+    shape: VideoShape,
+    setup: MultilinearSetup,
+    packer: CenoGoldilocksPacker,
+}
+
+impl RedactableSignatureScheme for SumcheckScheme {
+    type Commitment = redact::Commitment;
+    type ProverState = CommittedVideo;
+    type Proof = IntervalProof;
+    type Error = RedactError;
+
+    const NAME: &'static str = "sumcheck";
+
+    fn setup(shape: VideoShape) -> Result<Self, Self::Error> {
+        let packer = CenoGoldilocksPacker::new(6, true);
+        Ok(Self {
+            shape,
+            setup: MultilinearSetup::new(basefold_layout(shape, &packer)),
+            packer,
+        })
+    }
+
+    fn shape(&self) -> VideoShape {
+        self.shape
+    }
+
+    fn commit(&self, video: &[u8]) -> (redact::Commitment, CommittedVideo) {
+        let committed = self
+            .setup
+            .commit_video(&self.packer.pack(video, self.shape.frame_len));
+        (committed.commitment(), committed)
+    }
+
+    fn commitment_bytes(commitment: &redact::Commitment) -> Vec<u8> {
+        bincode::serialize(commitment).unwrap()
+    }
+
+    fn redact(
+        &self,
+        video: &CommittedVideo,
+        kept: Range<usize>,
+    ) -> Result<IntervalProof, RedactError> {
+        self.setup.prove_interval(video, kept.start, kept.end)
+    }
+
+    fn kept_frames(proof: &IntervalProof) -> Vec<Range<usize>> {
+        vec![proof.start..proof.end]
+    }
+
+    fn verify_proof(
+        &self,
+        commitment: &redact::Commitment,
+        proof: &IntervalProof,
+        published: &[u8],
+    ) -> Result<(), RedactError> {
+        let packed = self.packer.pack(published, self.shape.frame_len);
+        self.setup.verify_interval(commitment, proof, &packed)
+    }
+
+    fn proof_size(proof: &IntervalProof) -> usize {
+        bincode::serialized_size(proof).unwrap() as usize
+    }
+}
+
+/// The packed frames back to back over a few power-of-two Basefold polynomials, so padding stays
+/// under one frame, revealing the kept range with one masked sumcheck per polynomial it meets.
+pub struct SubcubeScheme {
+    shape: VideoShape,
+    setup: SubcubeSetup,
+    packer: CenoGoldilocksPacker,
+}
+
+impl RedactableSignatureScheme for SubcubeScheme {
+    type Commitment = Vec<redact::Commitment>;
+    type ProverState = CommittedSubcubes;
+    type Proof = SubcubeIntervalProof;
+    type Error = RedactError;
+
+    const NAME: &'static str = "sumcheck-subcubes";
+
+    fn setup(shape: VideoShape) -> Result<Self, Self::Error> {
+        let packer = CenoGoldilocksPacker::new(6, true);
+        Ok(Self {
+            shape,
+            setup: SubcubeSetup::new(basefold_layout(shape, &packer)),
+            packer,
+        })
+    }
+
+    fn shape(&self) -> VideoShape {
+        self.shape
+    }
+
+    fn commit(&self, video: &[u8]) -> (Vec<redact::Commitment>, CommittedSubcubes) {
+        let committed = self
+            .setup
+            .commit_video(&self.packer.pack(video, self.shape.frame_len));
+        (committed.commitments(), committed)
+    }
+
+    fn commitment_bytes(commitments: &Vec<redact::Commitment>) -> Vec<u8> {
+        bincode::serialize(commitments).unwrap()
+    }
+
+    fn redact(
+        &self,
+        video: &CommittedSubcubes,
+        kept: Range<usize>,
+    ) -> Result<SubcubeIntervalProof, RedactError> {
+        self.setup.prove_interval(video, kept.start, kept.end)
+    }
+
+    fn kept_frames(proof: &SubcubeIntervalProof) -> Vec<Range<usize>> {
+        vec![proof.start..proof.end]
+    }
+
+    fn verify_proof(
+        &self,
+        commitments: &Vec<redact::Commitment>,
+        proof: &SubcubeIntervalProof,
+        published: &[u8],
+    ) -> Result<(), RedactError> {
+        let packed = self.packer.pack(published, self.shape.frame_len);
+        self.setup.verify_interval(commitments, proof, &packed)
+    }
+
+    fn proof_size(proof: &SubcubeIntervalProof) -> usize {
+        bincode::serialized_size(proof).unwrap() as usize
+    }
+}
+
+/// The same Basefold commitment as `SumcheckScheme`, revealing the kept range as a union of
+/// dyadic blocks opened together.
+pub struct BasefoldBlocksScheme {
+    shape: VideoShape,
+    setup: MultilinearSetup,
+    packer: CenoGoldilocksPacker,
+}
+
+impl RedactableSignatureScheme for BasefoldBlocksScheme {
+    type Commitment = redact::Commitment;
+    type ProverState = CommittedVideo;
+    type Proof = CompactProof;
+    type Error = RedactError;
+
+    const NAME: &'static str = "basefold-blocks";
+
+    fn setup(shape: VideoShape) -> Result<Self, Self::Error> {
+        let packer = CenoGoldilocksPacker::new(6, true);
+        Ok(Self {
+            shape,
+            setup: MultilinearSetup::new(basefold_layout(shape, &packer)),
+            packer,
+        })
+    }
+
+    fn shape(&self) -> VideoShape {
+        self.shape
+    }
+
+    fn commit(&self, video: &[u8]) -> (redact::Commitment, CommittedVideo) {
+        let committed = self
+            .setup
+            .commit_video(&self.packer.pack(video, self.shape.frame_len));
+        (committed.commitment(), committed)
+    }
+
+    fn commitment_bytes(commitment: &redact::Commitment) -> Vec<u8> {
+        bincode::serialize(commitment).unwrap()
+    }
+
+    fn redact(
+        &self,
+        video: &CommittedVideo,
+        kept: Range<usize>,
+    ) -> Result<CompactProof, RedactError> {
+        self.setup.prove_frames(video, &kept.collect::<Vec<_>>())
+    }
+
+    fn kept_frames(proof: &CompactProof) -> Vec<Range<usize>> {
+        // Blocks are sorted and disjoint; merge the ones that touch.
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for frames in proof.blocks.iter().map(|block| block.frames()) {
+            match ranges.last_mut() {
+                Some(last) if last.end == frames.start => last.end = frames.end,
+                _ => ranges.push(frames),
+            }
+        }
+        ranges
+    }
+
+    fn verify_proof(
+        &self,
+        commitment: &redact::Commitment,
+        proof: &CompactProof,
+        published: &[u8],
+    ) -> Result<(), RedactError> {
+        let packed = self.packer.pack(published, self.shape.frame_len);
+        self.setup.verify_frames(commitment, proof, &packed)
+    }
+
+    fn proof_size(proof: &CompactProof) -> usize {
+        bincode::serialized_size(proof).unwrap() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::{SecretKey, SignatureAlgorithm};
+
+    const SHAPE: VideoShape = VideoShape {
+        frame_len: 18,
+        num_frames: 6,
+    };
+
+    fn test_video() -> Vec<u8> {
+        (0..SHAPE.frame_len * SHAPE.num_frames)
+            .map(|i| (i * 37 + 11) as u8)
+            .collect()
+    }
+
+    fn frames(video: &[u8], kept: Range<usize>) -> Vec<u8> {
+        video[kept.start * SHAPE.frame_len..kept.end * SHAPE.frame_len].to_vec()
+    }
+
+    // The same behaviour is required of every backend.
+    macro_rules! conformance_tests {
+        ($name:ident, $scheme:ty) => {
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn honest_redaction_verifies() {
+                    let scheme = <$scheme>::setup(SHAPE).unwrap();
+                    let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+                    let video = test_video();
+                    let (signature, state) = scheme.sign(&key, &video);
+                    for kept in [1..4, 0..6, 5..6] {
+                        let proof = scheme.redact(&state, kept.clone()).unwrap();
+                        assert!(<$scheme>::proof_size(&proof) > 0);
+                        scheme
+                            .verify(
+                                &key.public_key(),
+                                &signature,
+                                kept.clone(),
+                                &proof,
+                                &frames(&video, kept),
+                            )
+                            .unwrap();
+                    }
+                }
+
+                #[test]
+                fn tampered_frame_is_rejected() {
+                    let scheme = <$scheme>::setup(SHAPE).unwrap();
+                    let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+                    let video = test_video();
+                    let (signature, state) = scheme.sign(&key, &video);
+                    let proof = scheme.redact(&state, 1..4).unwrap();
+
+                    let mut published = frames(&video, 1..4);
+                    published[20] ^= 1;
+                    assert!(matches!(
+                        scheme.verify(&key.public_key(), &signature, 1..4, &proof, &published),
+                        Err(SchemeError::Proof(_))
+                    ));
+                }
+
+                #[test]
+                fn other_frames_are_rejected() {
+                    let scheme = <$scheme>::setup(SHAPE).unwrap();
+                    let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+                    let video = test_video();
+                    let (signature, state) = scheme.sign(&key, &video);
+                    let proof = scheme.redact(&state, 1..4).unwrap();
+
+                    assert!(matches!(
+                        scheme.verify(
+                            &key.public_key(),
+                            &signature,
+                            2..5,
+                            &proof,
+                            &frames(&video, 2..5)
+                        ),
+                        Err(SchemeError::WrongFrames)
+                    ));
+                }
+
+                #[test]
+                fn other_signer_is_rejected() {
+                    let scheme = <$scheme>::setup(SHAPE).unwrap();
+                    let key = SecretKey::generate(SignatureAlgorithm::Ed25519);
+                    let video = test_video();
+                    let (signature, state) = scheme.sign(&key, &video);
+                    let proof = scheme.redact(&state, 1..4).unwrap();
+
+                    let other = SecretKey::generate(SignatureAlgorithm::Ed25519);
+                    assert!(matches!(
+                        scheme.verify(
+                            &other.public_key(),
+                            &signature,
+                            1..4,
+                            &proof,
+                            &frames(&video, 1..4)
+                        ),
+                        Err(SchemeError::Signature(_))
+                    ));
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn fri_setup_fails_when_no_parameters_reach_the_default() {
+        // 2^32 packed elements: the commit phase alone gives fewer than 96 bits.
+        let shape = VideoShape {
+            frame_len: 6 << 16,
+            num_frames: 1 << 16,
+        };
+        assert!(matches!(
+            FriScheme::setup(shape),
+            Err(TrimError::Parameters(_))
+        ));
+    }
+
+    conformance_tests!(merkle, MerkleScheme);
+    conformance_tests!(fri, FriScheme);
+    conformance_tests!(ligero, LigeroScheme);
+    conformance_tests!(sumcheck, SumcheckScheme);
+    conformance_tests!(sumcheck_subcubes, SubcubeScheme);
+    conformance_tests!(basefold_blocks, BasefoldBlocksScheme);
+
+    #[test]
+    fn every_signature_algorithm_signs_commitments() {
+        let scheme = MerkleScheme::setup(SHAPE).unwrap();
+        let video = test_video();
+        for algorithm in SignatureAlgorithm::ALL {
+            let key = SecretKey::generate(algorithm);
+            let (signature, state) = scheme.sign(&key, &video);
+            let proof = scheme.redact(&state, 2..5).unwrap();
+            scheme
+                .verify(
+                    &key.public_key(),
+                    &signature,
+                    2..5,
+                    &proof,
+                    &frames(&video, 2..5),
+                )
+                .unwrap();
+        }
+    }
+}
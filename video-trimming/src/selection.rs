@@ -0,0 +1,250 @@
+// Revealing byte runs of a packed video.
+//
+// A commitment opens whole packed elements, but an edit such as a crop keeps byte runs whose ends
+// fall inside elements. The editor therefore reveals every element a kept run touches and
+// publishes, besides the kept bytes, the margins: the bytes of those elements outside the runs,
+// fewer than `bytes_per_element` at either end of a run. The verifier rebuilds the revealed
+// elements from the published bytes and the margins, so opening them against the signed
+// commitment checks that the published bytes sit at the claimed positions inside it. The margins
+// are disclosed along with the edit.
+
+use std::ops::Range;
+
+use crate::packing::Packer;
+
+/// Sorted, disjoint byte runs of a video of `num_frames` frames of `frame_len` bytes, packed by
+/// `packer`.
+#[derive(Clone, Debug)]
+pub struct Selection<P> {
+    packer: P,
+    frame_len: usize,
+    num_frames: usize,
+    runs: Vec<Range<usize>>,
+}
+
+impl<P: Packer> Selection<P> {
+    /// Selects `runs` of the video. Returns None unless every run is non-empty, after the previous
+    /// one and inside the video. Touching runs are merged.
+    pub fn new(
+        packer: P,
+        frame_len: usize,
+        num_frames: usize,
+        runs: impl IntoIterator<Item = Range<usize>>,
+    ) -> Option<Self> {
+        let video_len = frame_len.checked_mul(num_frames)?;
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for run in runs {
+            let prev_end = merged.last().map_or(0, |prev| prev.end);
+            if run.start >= run.end || run.start < prev_end || run.end > video_len {
+                return None;
+            }
+            match merged.last_mut() {
+                Some(prev) if prev.end == run.start => prev.end = run.end,
+                _ => merged.push(run),
+            }
+        }
+        Some(Self {
+            packer,
+            frame_len,
+            num_frames,
+            runs: merged,
+        })
+    }
+
+    pub fn runs(&self) -> &[Range<usize>] {
+        &self.runs
+    }
+
+    /// Number of selected bytes.
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|run| run.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    fn elements_per_frame(&self) -> usize {
+        self.packer.elements_per_frame(self.frame_len)
+    }
+
+    // The element byte `byte` of the video is packed into.
+    fn element_of(&self, byte: usize) -> usize {
+        let bytes_per_element = self.packer.bytes_per_element();
+        if self.packer.frame_aligned() {
+            byte / self.frame_len * self.elements_per_frame()
+                + byte % self.frame_len / bytes_per_element
+        } else {
+            byte / bytes_per_element
+        }
+    }
+
+    // The bytes of the video packed into element `element`.
+    fn element_bytes(&self, element: usize) -> Range<usize> {
+        let bytes_per_element = self.packer.bytes_per_element();
+        let (start, end) = if self.packer.frame_aligned() {
+            let elements_per_frame = self.elements_per_frame();
+            let frame_start = element / elements_per_frame * self.frame_len;
+            (
+                frame_start + element % elements_per_frame * bytes_per_element,
+                frame_start + self.frame_len,
+            )
+        } else {
+            (
+                element * bytes_per_element,
+                self.frame_len * self.num_frames,
+            )
+        };
+        start..(start + bytes_per_element).min(end)
+    }
+
+    /// The elements the runs touch, as sorted, disjoint ranges of element indices.
+    pub fn elements(&self) -> Vec<Range<usize>> {
+        let mut elements: Vec<Range<usize>> = Vec::with_capacity(self.runs.len());
+        for run in &self.runs {
+            let touched = self.element_of(run.start)..self.element_of(run.end - 1) + 1;
+            match elements.last_mut() {
+                Some(prev) if prev.end >= touched.start => prev.end = touched.end,
+                _ => elements.push(touched),
+            }
+        }
+        elements
+    }
+
+    // Calls `visit` with every revealed element in order and, for each of its bytes, whether the
+    // byte is selected.
+    fn visit(&self, mut visit: impl FnMut(usize, &[bool])) {
+        let mut run = 0;
+        let mut selected = Vec::with_capacity(self.packer.bytes_per_element());
+        for element in self.elements().into_iter().flatten() {
+            selected.clear();
+            for byte in self.element_bytes(element) {
+                while run < self.runs.len() && self.runs[run].end <= byte {
+                    run += 1;
+                }
+                selected.push(run < self.runs.len() && self.runs[run].start <= byte);
+            }
+            visit(element, &selected);
+        }
+    }
+
+    /// Splits the revealed elements of a video into the selected bytes and the margins, where
+    /// `element(k)` is element `k` of the packed video.
+    pub fn split(&self, element: impl Fn(usize) -> P::Element) -> (Vec<u8>, Vec<u8>) {
+        let mut kept = Vec::with_capacity(self.len());
+        let mut margins = Vec::new();
+        let mut buf = vec![0u8; self.packer.bytes_per_element()];
+        self.visit(|k, selected| {
+            let bytes = &mut buf[..selected.len()];
+            self.packer.unpack_element(&element(k), bytes);
+            for (byte, keep) in bytes.iter().zip(selected) {
+                if *keep {
+                    kept.push(*byte);
+                } else {
+                    margins.push(*byte);
+                }
+            }
+        });
+        (kept, margins)
+    }
+
+    /// Rebuilds the revealed elements, in order, from the selected bytes `kept` and `margins`.
+    /// Returns None unless both are used up exactly.
+    pub fn rebuild(&self, kept: &[u8], margins: &[u8]) -> Option<Vec<P::Element>> {
+        let mut kept = kept.iter();
+        let mut margins = margins.iter();
+        let mut complete = true;
+        let mut elements = Vec::new();
+        let mut buf = Vec::with_capacity(self.packer.bytes_per_element());
+        self.visit(|_, selected| {
+            buf.clear();
+            for keep in selected {
+                let byte = if *keep { kept.next() } else { margins.next() };
+                match byte {
+                    Some(byte) => buf.push(*byte),
+                    None => complete = false,
+                }
+            }
+            elements.push(self.packer.pack_element(&buf));
+        });
+        (complete && kept.next().is_none() && margins.next().is_none()).then_some(elements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packing::GoldilocksPacker;
+
+    const FRAME_LEN: usize = 14;
+    const NUM_FRAMES: usize = 3;
+
+    fn test_video() -> Vec<u8> {
+        (0..FRAME_LEN * NUM_FRAMES)
+            .map(|i| (i * 37 + 11) as u8)
+            .collect()
+    }
+
+    fn kept(video: &[u8], runs: &[Range<usize>]) -> Vec<u8> {
+        runs.iter()
+            .flat_map(|run| video[run.clone()].iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn split_and_rebuild_round_trip() {
+        let video = test_video();
+        // Runs ending inside elements, two sharing an element and one crossing a frame boundary.
+        let runs = vec![1..4, 5..9, 13..16, 30..31];
+        for frame_aligned in [false, true] {
+            let packer = GoldilocksPacker::new(6, frame_aligned);
+            let elements = packer.pack(&video, FRAME_LEN);
+            let selection = Selection::new(packer, FRAME_LEN, NUM_FRAMES, runs.clone()).unwrap();
+
+            let (published, margins) = selection.split(|k| elements[k]);
+            assert_eq!(published, kept(&video, &runs));
+            let revealed: Vec<_> = selection
+                .elements()
+                .into_iter()
+                .flatten()
+                .map(|k| elements[k])
+                .collect();
+            assert_eq!(selection.rebuild(&published, &margins).unwrap(), revealed);
+            assert_eq!(
+                published.len() + margins.len(),
+                selection
+                    .elements()
+                    .into_iter()
+                    .flatten()
+                    .map(|k| selection.element_bytes(k).len())
+                    .sum::<usize>()
+            );
+
+            assert!(selection.rebuild(&published[1..], &margins).is_none());
+            assert!(selection.rebuild(&published, &margins[1..]).is_none());
+            let mut longer = margins.clone();
+            longer.push(0);
+            assert!(selection.rebuild(&published, &longer).is_none());
+        }
+    }
+
+    #[test]
+    fn aligned_elements_stay_inside_frames() {
+        // 14-byte frames pack into elements of 6, 6 and 2 bytes.
+        let packer = GoldilocksPacker::new(6, true);
+        let selection = Selection::new(packer, FRAME_LEN, NUM_FRAMES, vec![13..16]).unwrap();
+        assert_eq!(selection.elements(), vec![2..4]);
+        assert_eq!(selection.element_bytes(2), 12..14);
+        assert_eq!(selection.element_bytes(3), 14..20);
+    }
+
+    #[test]
+    fn touching_runs_merge_and_invalid_runs_are_rejected() {
+        let packer = GoldilocksPacker::new(6, true);
+        let selection = Selection::new(packer, FRAME_LEN, NUM_FRAMES, vec![0..3, 3..5]).unwrap();
+        assert_eq!(selection.runs(), &[0..5]);
+        for runs in [vec![2..2], vec![3..5, 4..6], vec![5..6, 1..2], vec![40..43]] {
+            assert!(Selection::new(packer, FRAME_LEN, NUM_FRAMES, runs).is_none());
+        }
+    }
+}
@@ -0,0 +1,428 @@
+// Signature algorithms a video commitment can be signed with.
+//
+// Cameras sign with whatever their secure element supports, so the signature over a commitment is
+// not tied to one algorithm. A `PublicKey` records its algorithm next to its bytes, and signatures
+// are plain byte strings in each algorithm's standard encoding:
+//   - ed25519: 32-byte keys and 64-byte signatures (RFC 8032).
+//   - ECDSA over P-256 with SHA-256: SEC1 keys, compressed or not, and 64-byte r || s signatures.
+//     DER signatures, which most secure elements emit, are accepted as well.
+//   - BLS over BLS12-381: 48-byte compressed G1 keys and 96-byte compressed G2 signatures, in the
+//     message-augmentation scheme of the IETF BLS draft. Every message is hashed together with the
+//     signer's key, so signatures by many cameras over many videos aggregate into one signature
+//     without proofs of possession.
+// Anything that can produce such signatures, hardware included, plugs in through `VideoSigner`.
+
+use std::fmt;
+
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::SeedableRng;
+use blstrs::{G1Affine, G1Projective, G2Affine, G2Projective, Gt, Scalar};
+use ed25519_dalek::{Signer as _, Verifier as _};
+use ff::Field;
+use group::{Curve, Group};
+use p256::ecdsa::signature::{Signer as _, Verifier as _};
+use rand::{rngs::OsRng, TryRngCore};
+use serde::{Deserialize, Serialize};
+
+// Domain separation tag of the BLS ciphersuite with message augmentation.
+const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_";
+
+const BLS_PUBLIC_KEY_BYTES: usize = 48;
+const BLS_SIGNATURE_BYTES: usize = 96;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignatureAlgorithm {
+    Ed25519,
+    EcdsaP256,
+    Bls12381,
+}
+
+impl SignatureAlgorithm {
+    pub const ALL: [SignatureAlgorithm; 3] = [
+        SignatureAlgorithm::Ed25519,
+        SignatureAlgorithm::EcdsaP256,
+        SignatureAlgorithm::Bls12381,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Ed25519 => "ed25519",
+            SignatureAlgorithm::EcdsaP256 => "ecdsa-p256",
+            SignatureAlgorithm::Bls12381 => "bls12-381",
+        }
+    }
+}
+
+impl fmt::Display for SignatureAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for SignatureAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|a| a.name()).collect();
+                format!(
+                    "unknown signature algorithm {}, expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SigningError {
+    MalformedKey,
+    MalformedSignature,
+    /// The signature is well formed but does not verify.
+    Rejected,
+    /// Keys of different algorithms were mixed, or there were no signatures at all.
+    UnsupportedAggregate,
+}
+
+impl fmt::Display for SigningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SigningError::MalformedKey => write!(f, "malformed public key"),
+            SigningError::MalformedSignature => write!(f, "malformed signature"),
+            SigningError::Rejected => write!(f, "signature does not verify"),
+            SigningError::UnsupportedAggregate => {
+                write!(
+                    f,
+                    "only a non-empty set of BLS signatures can be aggregated"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SigningError {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PublicKey {
+    pub algorithm: SignatureAlgorithm,
+    pub bytes: Vec<u8>,
+}
+
+impl PublicKey {
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), SigningError> {
+        match self.algorithm {
+            SignatureAlgorithm::Ed25519 => {
+                let bytes: [u8; 32] = self.bytes[..]
+                    .try_into()
+                    .map_err(|_| SigningError::MalformedKey)?;
+                let key = ed25519_dalek::VerifyingKey::from_bytes(&bytes)
+                    .map_err(|_| SigningError::MalformedKey)?;
+                let signature = ed25519_dalek::Signature::from_slice(signature)
+                    .map_err(|_| SigningError::MalformedSignature)?;
+                key.verify(message, &signature)
+                    .map_err(|_| SigningError::Rejected)
+            }
+            SignatureAlgorithm::EcdsaP256 => {
+                let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&self.bytes)
+                    .map_err(|_| SigningError::MalformedKey)?;
+                let signature = p256::ecdsa::Signature::from_slice(signature)
+                    .or_else(|_| p256::ecdsa::Signature::from_der(signature))
+                    .map_err(|_| SigningError::MalformedSignature)?;
+                key.verify(message, &signature)
+                    .map_err(|_| SigningError::Rejected)
+            }
+            SignatureAlgorithm::Bls12381 => {
+                verify_aggregate(&[(self.clone(), message.to_vec())], signature)
+            }
+        }
+    }
+
+    fn bls_point(&self) -> Result<G1Affine, SigningError> {
+        let bytes: &[u8; BLS_PUBLIC_KEY_BYTES] = self.bytes[..]
+            .try_into()
+            .map_err(|_| SigningError::MalformedKey)?;
+        // Decompression checks that the point is in the prime-order subgroup.
+        let point = Option::<G1Affine>::from(G1Affine::from_compressed(bytes))
+            .ok_or(SigningError::MalformedKey)?;
+        if bool::from(G1Projective::from(point).is_identity()) {
+            return Err(SigningError::MalformedKey);
+        }
+        Ok(point)
+    }
+}
+
+/// Anything that can sign a commitment, from a key in memory to a camera's secure element.
+pub trait VideoSigner {
+    fn public_key(&self) -> PublicKey;
+
+    /// Signs `message`, encoded as described at the top of this module.
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// A signing key held in memory.
+pub enum SecretKey {
+    Ed25519(ed25519_dalek::SigningKey),
+    EcdsaP256(p256::ecdsa::SigningKey),
+    Bls12381(Scalar),
+}
+
+impl SecretKey {
+    /// Derives a key from a 32-byte seed. An ed25519 seed is the RFC 8032 secret key itself;
+    /// other algorithms sample their key from an RNG seeded with it.
+    pub fn from_seed(algorithm: SignatureAlgorithm, seed: [u8; 32]) -> Self {
+        match algorithm {
+            SignatureAlgorithm::Ed25519 => {
+                SecretKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&seed))
+            }
+            SignatureAlgorithm::EcdsaP256 => SecretKey::EcdsaP256(p256::ecdsa::SigningKey::random(
+                &mut StdRng::from_seed(seed),
+            )),
+            SignatureAlgorithm::Bls12381 => {
+                let mut rng = StdRng::from_seed(seed);
+                let mut scalar = Scalar::random(&mut rng);
+                while bool::from(scalar.is_zero()) {
+                    scalar = Scalar::random(&mut rng);
+                }
+                SecretKey::Bls12381(scalar)
+            }
+        }
+    }
+
+    pub fn generate(algorithm: SignatureAlgorithm) -> Self {
+        let mut seed = [0u8; 32];
+        OsRng.try_fill_bytes(&mut seed).unwrap();
+        Self::from_seed(algorithm, seed)
+    }
+
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            SecretKey::Ed25519(_) => SignatureAlgorithm::Ed25519,
+            SecretKey::EcdsaP256(_) => SignatureAlgorithm::EcdsaP256,
+            SecretKey::Bls12381(_) => SignatureAlgorithm::Bls12381,
+        }
+    }
+}
+
+impl VideoSigner for SecretKey {
+    fn public_key(&self) -> PublicKey {
+        let bytes = match self {
+            SecretKey::Ed25519(key) => key.verifying_key().to_bytes().to_vec(),
+            SecretKey::EcdsaP256(key) => key
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+            SecretKey::Bls12381(scalar) => (G1Projective::generator() * scalar)
+                .to_affine()
+                .to_compressed()
+                .to_vec(),
+        };
+        PublicKey {
+            algorithm: self.algorithm(),
+            bytes,
+        }
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            SecretKey::Ed25519(key) => key.sign(message).to_bytes().to_vec(),
+            SecretKey::EcdsaP256(key) => {
+                let signature: p256::ecdsa::Signature = key.sign(message);
+                signature.to_bytes().to_vec()
+            }
+            SecretKey::Bls12381(scalar) => {
+                let public_key = self.public_key();
+                (bls_hash(&public_key.bytes, message) * scalar)
+                    .to_affine()
+                    .to_compressed()
+                    .to_vec()
+            }
+        }
+    }
+}
+
+fn bls_hash(public_key: &[u8], message: &[u8]) -> G2Projective {
+    G2Projective::hash_to_curve(message, BLS_DST, public_key)
+}
+
+fn bls_signature(signature: &[u8]) -> Result<G2Affine, SigningError> {
+    let bytes: &[u8; BLS_SIGNATURE_BYTES] = signature
+        .try_into()
+        .map_err(|_| SigningError::MalformedSignature)?;
+    Option::from(G2Affine::from_compressed(bytes)).ok_or(SigningError::MalformedSignature)
+}
+
+/// Combines BLS signatures, over any messages by any signers, into one signature of the same size.
+pub fn aggregate(signatures: &[Vec<u8>]) -> Result<Vec<u8>, SigningError> {
+    if signatures.is_empty() {
+        return Err(SigningError::UnsupportedAggregate);
+    }
+    let mut sum = G2Projective::identity();
+    for signature in signatures {
+        sum += G2Projective::from(bls_signature(signature)?);
+    }
+    Ok(sum.to_affine().to_compressed().to_vec())
+}
+
+/// Checks an aggregate of BLS signatures, one by each key over the message paired with it.
+pub fn verify_aggregate(
+    signed: &[(PublicKey, Vec<u8>)],
+    signature: &[u8],
+) -> Result<(), SigningError> {
+    if signed.is_empty()
+        || signed
+            .iter()
+            .any(|(key, _)| key.algorithm != SignatureAlgorithm::Bls12381)
+    {
+        return Err(SigningError::UnsupportedAggregate);
+    }
+    let signature = bls_signature(signature)?;
+
+    // e(g1, sigma) == prod_i e(pk_i, H(pk_i || m_i)), written additively as blstrs does for Gt.
+    let mut expected = Gt::identity();
+    for (key, message) in signed {
+        let hash = bls_hash(&key.bytes, message).to_affine();
+        expected += blstrs::pairing(&key.bls_point()?, &hash);
+    }
+    if blstrs::pairing(&G1Projective::generator().to_affine(), &signature) == expected {
+        Ok(())
+    } else {
+        Err(SigningError::Rejected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_algorithm_signs_and_verifies() {
+        for algorithm in SignatureAlgorithm::ALL {
+            let key = SecretKey::from_seed(algorithm, [7; 32]);
+            let public_key = key.public_key();
+            let signature = key.sign(b"commitment");
+            assert_eq!(public_key.verify(b"commitment", &signature), Ok(()));
+            assert_eq!(
+                public_key.verify(b"other commitment", &signature),
+                Err(SigningError::Rejected)
+            );
+
+            let other = SecretKey::from_seed(algorithm, [8; 32]).public_key();
+            assert_eq!(
+                other.verify(b"commitment", &signature),
+                Err(SigningError::Rejected)
+            );
+        }
+    }
+
+    #[test]
+    fn keys_are_deterministic_in_the_seed() {
+        for algorithm in SignatureAlgorithm::ALL {
+            assert_eq!(
+                SecretKey::from_seed(algorithm, [1; 32]).public_key(),
+                SecretKey::from_seed(algorithm, [1; 32]).public_key()
+            );
+        }
+    }
+
+    #[test]
+    fn encodings_have_the_documented_sizes() {
+        let sizes = [
+            (SignatureAlgorithm::Ed25519, 32, 64),
+            (SignatureAlgorithm::EcdsaP256, 33, 64),
+            (SignatureAlgorithm::Bls12381, 48, 96),
+        ];
+        for (algorithm, key_len, signature_len) in sizes {
+            let key = SecretKey::from_seed(algorithm, [3; 32]);
+            assert_eq!(key.public_key().bytes.len(), key_len);
+            assert_eq!(key.sign(b"m").len(), signature_len);
+        }
+    }
+
+    #[test]
+    fn ecdsa_accepts_der_and_uncompressed_keys() {
+        let key = p256::ecdsa::SigningKey::random(&mut StdRng::from_seed([5; 32]));
+        let signature: p256::ecdsa::Signature = key.sign(b"frame");
+        let public_key = PublicKey {
+            algorithm: SignatureAlgorithm::EcdsaP256,
+            bytes: key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+        };
+        assert_eq!(
+            public_key.verify(b"frame", signature.to_der().as_bytes()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn bls_signatures_aggregate() {
+        let keys: Vec<_> = (0..3)
+            .map(|i| SecretKey::from_seed(SignatureAlgorithm::Bls12381, [i; 32]))
+            .collect();
+        // The same message twice is fine: each signature hashes its signer's key in.
+        let messages = [
+            b"video a".to_vec(),
+            b"video b".to_vec(),
+            b"video a".to_vec(),
+        ];
+        let signatures: Vec<_> = keys
+            .iter()
+            .zip(&messages)
+            .map(|(key, message)| key.sign(message))
+            .collect();
+        let signed: Vec<_> = keys
+            .iter()
+            .map(|key| key.public_key())
+            .zip(messages.iter().cloned())
+            .collect();
+
+        let aggregate_signature = aggregate(&signatures).unwrap();
+        assert_eq!(aggregate_signature.len(), BLS_SIGNATURE_BYTES);
+        assert_eq!(verify_aggregate(&signed, &aggregate_signature), Ok(()));
+
+        let mut swapped = signed.clone();
+        swapped[0].1 = b"video b".to_vec();
+        assert_eq!(
+            verify_aggregate(&swapped, &aggregate_signature),
+            Err(SigningError::Rejected)
+        );
+        assert_eq!(
+            verify_aggregate(&signed[..2], &aggregate_signature),
+            Err(SigningError::Rejected)
+        );
+    }
+
+    #[test]
+    fn mixed_algorithms_do_not_aggregate() {
+        let ed = SecretKey::from_seed(SignatureAlgorithm::Ed25519, [1; 32]);
+        let signature = ed.sign(b"m");
+        assert_eq!(
+            verify_aggregate(&[(ed.public_key(), b"m".to_vec())], &signature),
+            Err(SigningError::UnsupportedAggregate)
+        );
+        assert_eq!(aggregate(&[]), Err(SigningError::UnsupportedAggregate));
+    }
+
+    #[test]
+    fn identity_bls_key_is_rejected() {
+        let public_key = PublicKey {
+            algorithm: SignatureAlgorithm::Bls12381,
+            bytes: G1Projective::identity()
+                .to_affine()
+                .to_compressed()
+                .to_vec(),
+        };
+        let signature = G2Projective::identity().to_affine().to_compressed();
+        assert_eq!(
+            public_key.verify(b"m", &signature),
+            Err(SigningError::MalformedKey)
+        );
+    }
+}
@@ -0,0 +1,480 @@
+// Frame rates, timestamps and time ranges.
+//
+// Editors think in seconds and SMPTE timecode, while the commitments are over frames. Everything
+// here is exact rational arithmetic, so the same time range always resolves to the same frames,
+// and a time that does not land on a frame boundary is an error rather than being rounded.
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimecodeError {
+    /// The string could not be parsed.
+    Parse(String),
+    /// Frame rates must have a non-zero numerator and denominator.
+    InvalidFrameRate,
+    /// A timecode field is out of range (hours from 24, minutes or seconds from 60, frames from
+    /// the nominal frame rate), or a time is too large to represent.
+    FieldOutOfRange(String),
+    /// Drop-frame timecode at a rate other than 29.97 or 59.94 fps, or naming a dropped frame.
+    InvalidDropFrame(String),
+    /// The time does not fall on a frame boundary.
+    MidFrame(String),
+    /// A range ends before it starts.
+    EndBeforeStart { start: usize, end: usize },
+    /// A range ends after the last signed frame.
+    BeyondSignedLength { end: usize, num_frames: usize },
+    /// Ranges must be sorted and must not overlap.
+    Overlapping { previous_end: usize, start: usize },
+}
+
+impl fmt::Display for TimecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimecodeError::Parse(s) => write!(f, "cannot parse {:?}", s),
+            TimecodeError::InvalidFrameRate => write!(f, "invalid frame rate"),
+            TimecodeError::FieldOutOfRange(s) => write!(f, "timecode field out of range in {}", s),
+            TimecodeError::InvalidDropFrame(s) => write!(f, "invalid drop-frame timecode {}", s),
+            TimecodeError::MidFrame(s) => write!(f, "{} does not fall on a frame boundary", s),
+            TimecodeError::EndBeforeStart { start, end } => {
+                write!(
+                    f,
+                    "range ends at frame {} before it starts at {}",
+                    end, start
+                )
+            }
+            TimecodeError::BeyondSignedLength { end, num_frames } => write!(
+                f,
+                "range ends at frame {} but only {} frames were signed",
+                end, num_frames
+            ),
+            TimecodeError::Overlapping {
+                previous_end,
+                start,
+            } => write!(
+                f,
+                "range starting at frame {} overlaps the previous range ending at {}",
+                start, previous_end
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TimecodeError {}
+
+/// Frames per second as the exact fraction `num / den`, e.g. 30000/1001 for NTSC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedFrameRate")]
+pub struct FrameRate {
+    num: u32,
+    den: u32,
+}
+
+// The serialized form, checked by `FrameRate::new` before it is used.
+#[derive(Deserialize)]
+struct UncheckedFrameRate {
+    num: u32,
+    den: u32,
+}
+
+impl TryFrom<UncheckedFrameRate> for FrameRate {
+    type Error = TimecodeError;
+
+    fn try_from(rate: UncheckedFrameRate) -> Result<Self, TimecodeError> {
+        FrameRate::new(rate.num, rate.den)
+    }
+}
+
+impl FrameRate {
+    pub fn new(num: u32, den: u32) -> Result<Self, TimecodeError> {
+        if num == 0 || den == 0 {
+            return Err(TimecodeError::InvalidFrameRate);
+        }
+        let g = gcd(num as u128, den as u128) as u32;
+        Ok(Self {
+            num: num / g,
+            den: den / g,
+        })
+    }
+
+    pub fn num(&self) -> u32 {
+        self.num
+    }
+
+    pub fn den(&self) -> u32 {
+        self.den
+    }
+
+    /// Frames counted per timecode second: the rate rounded up, so 29.97 fps counts 30.
+    pub fn nominal(&self) -> u64 {
+        (self.num as u64).div_ceil(self.den as u64)
+    }
+
+    // Frames dropped per minute by drop-frame timecode at this rate.
+    fn dropped_per_minute(&self) -> Option<u64> {
+        match (self.num, self.den) {
+            (30000, 1001) => Some(2),
+            (60000, 1001) => Some(4),
+            _ => None,
+        }
+    }
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        Self { num: 25, den: 1 }
+    }
+}
+
+impl fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl FromStr for FrameRate {
+    type Err = TimecodeError;
+
+    /// Accepts `25`, `30000/1001`, and the usual shorthands `23.976`, `29.97` and `59.94`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.parse::<u32>()
+                .map_err(|_| TimecodeError::Parse(s.to_string()))
+        };
+        match s {
+            "23.976" => FrameRate::new(24000, 1001),
+            "29.97" => FrameRate::new(30000, 1001),
+            "59.94" => FrameRate::new(60000, 1001),
+            _ => match s.split_once('/') {
+                Some((num, den)) => FrameRate::new(parse(num)?, parse(den)?),
+                None => FrameRate::new(parse(s)?, 1),
+            },
+        }
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// A position in a video, resolved to a frame index against a `FrameRate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timestamp {
+    /// A 0-based frame index.
+    Frame(usize),
+    /// Exactly `num / den` seconds from the start.
+    Seconds { num: u128, den: u128 },
+    /// SMPTE `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame.
+    Timecode {
+        hours: u64,
+        minutes: u64,
+        seconds: u64,
+        frames: u64,
+        drop_frame: bool,
+    },
+}
+
+impl Timestamp {
+    pub fn to_frame(&self, rate: FrameRate) -> Result<usize, TimecodeError> {
+        let frame = match *self {
+            Timestamp::Frame(frame) => return Ok(frame),
+            Timestamp::Seconds { num, den } => {
+                let (frames_num, frames_den) = match (
+                    num.checked_mul(rate.num as u128),
+                    den.checked_mul(rate.den as u128),
+                ) {
+                    (Some(frames_num), Some(frames_den)) => (frames_num, frames_den),
+                    _ => return Err(TimecodeError::FieldOutOfRange(self.to_string())),
+                };
+                if frames_num % frames_den != 0 {
+                    return Err(TimecodeError::MidFrame(self.to_string()));
+                }
+                frames_num / frames_den
+            }
+            Timestamp::Timecode {
+                hours,
+                minutes,
+                seconds,
+                frames,
+                drop_frame,
+            } => {
+                let nominal = rate.nominal();
+                if hours >= 24 || minutes >= 60 || seconds >= 60 || frames >= nominal {
+                    return Err(TimecodeError::FieldOutOfRange(self.to_string()));
+                }
+                let total_minutes = 60 * hours + minutes;
+                let counted = (total_minutes * 60 + seconds) * nominal + frames;
+                if !drop_frame {
+                    counted as u128
+                } else {
+                    // Drop-frame timecode skips the first `drop` frame numbers of every minute
+                    // except every tenth, so the count stays in sync with wall-clock time.
+                    let drop = rate
+                        .dropped_per_minute()
+                        .ok_or_else(|| TimecodeError::InvalidDropFrame(self.to_string()))?;
+                    if seconds == 0 && frames < drop && minutes % 10 != 0 {
+                        return Err(TimecodeError::InvalidDropFrame(self.to_string()));
+                    }
+                    (counted - drop * (total_minutes - total_minutes / 10)) as u128
+                }
+            }
+        };
+        usize::try_from(frame).map_err(|_| TimecodeError::FieldOutOfRange(self.to_string()))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Timestamp::Frame(frame) => write!(f, "frame {}", frame),
+            Timestamp::Seconds { num, den } => write!(f, "{}/{}s", num, den),
+            Timestamp::Timecode {
+                hours,
+                minutes,
+                seconds,
+                frames,
+                drop_frame,
+            } => write!(
+                f,
+                "{:02}:{:02}:{:02}{}{:02}",
+                hours,
+                minutes,
+                seconds,
+                if drop_frame { ';' } else { ':' },
+                frames
+            ),
+        }
+    }
+}
+
+// Parses a non-negative decimal like `12` or `1.04` into an exact fraction.
+fn parse_decimal(s: &str) -> Option<(u128, u128)> {
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    let all_digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty() || !all_digits(int) || !all_digits(frac) || frac.len() > 18 {
+        return None;
+    }
+    let den = 10u128.pow(frac.len() as u32);
+    let num = format!("{}{}", int, frac).parse::<u128>().ok()?;
+    Some((num, den))
+}
+
+impl FromStr for Timestamp {
+    type Err = TimecodeError;
+
+    /// Accepts a frame index (`120`), seconds with an `s` suffix (`4.8s`) or SMPTE timecode
+    /// (`00:00:04:20`, `00:00:04;20` for drop-frame).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || TimecodeError::Parse(s.to_string());
+
+        if let Some(seconds) = s.strip_suffix('s') {
+            let (num, den) = parse_decimal(seconds).ok_or_else(err)?;
+            return Ok(Timestamp::Seconds { num, den });
+        }
+
+        if s.contains(':') {
+            let drop_frame = s.contains(';');
+            let fields: Vec<u64> = s
+                .split([':', ';'])
+                .map(|field| field.parse::<u64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| err())?;
+            // Only the separator before the frame field may be a semicolon.
+            if fields.len() != 4 || (drop_frame && s.rfind(';') != s.rfind([':', ';'])) {
+                return Err(err());
+            }
+            return Ok(Timestamp::Timecode {
+                hours: fields[0],
+                minutes: fields[1],
+                seconds: fields[2],
+                frames: fields[3],
+                drop_frame,
+            });
+        }
+
+        s.parse::<usize>().map(Timestamp::Frame).map_err(|_| err())
+    }
+}
+
+/// A half-open range `start..end` of timestamps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl TimeRange {
+    /// Resolves the range to frames of a video with `num_frames` frames at `rate`.
+    pub fn resolve(
+        &self,
+        rate: FrameRate,
+        num_frames: usize,
+    ) -> Result<Range<usize>, TimecodeError> {
+        let start = self.start.to_frame(rate)?;
+        let end = self.end.to_frame(rate)?;
+        if end < start {
+            return Err(TimecodeError::EndBeforeStart { start, end });
+        }
+        if end > num_frames {
+            return Err(TimecodeError::BeyondSignedLength { end, num_frames });
+        }
+        Ok(start..end)
+    }
+}
+
+impl FromStr for TimeRange {
+    type Err = TimecodeError;
+
+    /// Parses `START..END`, where both ends are in any format `Timestamp` accepts.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once("..")
+            .ok_or_else(|| TimecodeError::Parse(s.to_string()))?;
+        Ok(TimeRange {
+            start: start.parse()?,
+            end: end.parse()?,
+        })
+    }
+}
+
+/// Resolves a list of time ranges to sorted, non-overlapping frame ranges.
+pub fn resolve_segments(
+    ranges: &[TimeRange],
+    rate: FrameRate,
+    num_frames: usize,
+) -> Result<Vec<Range<usize>>, TimecodeError> {
+    let mut frames = Vec::with_capacity(ranges.len());
+    let mut previous_end = 0;
+    for range in ranges {
+        let segment = range.resolve(rate, num_frames)?;
+        if segment.start < previous_end {
+            return Err(TimecodeError::Overlapping {
+                previous_end,
+                start: segment.start,
+            });
+        }
+        previous_end = segment.end;
+        frames.push(segment);
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(s: &str) -> FrameRate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_frame_rates() {
+        assert_eq!(rate("25"), FrameRate::new(25, 1).unwrap());
+        assert_eq!(rate("29.97"), FrameRate::new(30000, 1001).unwrap());
+        assert_eq!(rate("50/2"), rate("25"));
+        assert_eq!(rate("29.97").nominal(), 30);
+        assert_eq!(
+            "0".parse::<FrameRate>(),
+            Err(TimecodeError::InvalidFrameRate)
+        );
+        assert!("fast".parse::<FrameRate>().is_err());
+    }
+
+    #[test]
+    fn deserialized_frame_rates_are_checked() {
+        let parse = |json: &str| serde_json::from_str::<FrameRate>(json);
+        assert_eq!(
+            parse(r#"{"num": 30000, "den": 1001}"#).unwrap(),
+            rate("29.97")
+        );
+        assert_eq!(parse(r#"{"num": 50, "den": 2}"#).unwrap(), rate("25"));
+        assert!(parse(r#"{"num": 25, "den": 0}"#).is_err());
+        assert!(parse(r#"{"num": 0, "den": 1}"#).is_err());
+        let json = serde_json::to_string(&rate("59.94")).unwrap();
+        assert_eq!(parse(&json).unwrap(), rate("59.94"));
+    }
+
+    #[test]
+    fn seconds_resolve_exactly() {
+        let ts = |s: &str| s.parse::<Timestamp>().unwrap();
+        assert_eq!(ts("4.8s").to_frame(rate("25")), Ok(120));
+        assert_eq!(ts("10s").to_frame(rate("25")), Ok(250));
+        assert_eq!(ts("1001s").to_frame(rate("29.97")), Ok(30000));
+        assert!(matches!(
+            ts("0.01s").to_frame(rate("25")),
+            Err(TimecodeError::MidFrame(_))
+        ));
+        // 1 second is not a whole number of NTSC frames.
+        assert!(matches!(
+            ts("1s").to_frame(rate("29.97")),
+            Err(TimecodeError::MidFrame(_))
+        ));
+    }
+
+    #[test]
+    fn timecodes_resolve() {
+        let ts = |s: &str| s.parse::<Timestamp>().unwrap();
+        assert_eq!(ts("00:00:04:20").to_frame(rate("25")), Ok(120));
+        assert_eq!(ts("01:00:00:00").to_frame(rate("25")), Ok(90000));
+        assert!(matches!(
+            ts("00:00:00:25").to_frame(rate("25")),
+            Err(TimecodeError::FieldOutOfRange(_))
+        ));
+
+        // Drop-frame: 00:01:00;02 is the first frame of minute one.
+        assert_eq!(ts("00:00:59;29").to_frame(rate("29.97")), Ok(1799));
+        assert_eq!(ts("00:01:00;02").to_frame(rate("29.97")), Ok(1800));
+        assert_eq!(ts("00:10:00;00").to_frame(rate("29.97")), Ok(17982));
+        assert!(matches!(
+            ts("00:01:00;00").to_frame(rate("29.97")),
+            Err(TimecodeError::InvalidDropFrame(_))
+        ));
+        assert!(matches!(
+            ts("00:00:01;00").to_frame(rate("25")),
+            Err(TimecodeError::InvalidDropFrame(_))
+        ));
+        assert!("00:00;01:00".parse::<Timestamp>().is_err());
+    }
+
+    #[test]
+    fn ranges_are_checked() {
+        let ranges =
+            |s: &[&str]| -> Vec<TimeRange> { s.iter().map(|r| r.parse().unwrap()).collect() };
+        assert_eq!(
+            resolve_segments(
+                &ranges(&["1..40", "2s..00:00:04:00", "200..250"]),
+                rate("25"),
+                250
+            ),
+            Ok(vec![1..40, 50..100, 200..250])
+        );
+        assert_eq!(
+            resolve_segments(&ranges(&["1..260"]), rate("25"), 250),
+            Err(TimecodeError::BeyondSignedLength {
+                end: 260,
+                num_frames: 250
+            })
+        );
+        assert_eq!(
+            resolve_segments(&ranges(&["40..1"]), rate("25"), 250),
+            Err(TimecodeError::EndBeforeStart { start: 40, end: 1 })
+        );
+        assert_eq!(
+            resolve_segments(&ranges(&["1..40", "1s..2s"]), rate("25"), 250),
+            Err(TimecodeError::Overlapping {
+                previous_end: 40,
+                start: 25
+            })
+        );
+    }
+}
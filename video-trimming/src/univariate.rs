@@ -0,0 +1,954 @@
+// Univariate (FRI-based) video trimming over Goldilocks.
+//
+// The video is packed into the coefficients of P(x) and committed with a two-adic FRI PCS. Keeping
+// the frames [start, end) is proven by committing to the prefix polynomial R and suffix
+// polynomial Q and opening P, R and Q at a common point zeta, so the verifier can check
+//   P(zeta) = R(zeta) + zeta^(start * m) * F(zeta) + zeta^(end * m) * Q(zeta)
+// where F is the polynomial of the published frames and m the number of coefficients per frame.
+// Keeping several segments works the same way with one polynomial per removed gap, each shifted
+// by zeta^(gap start), and all of them opened in one batched FRI proof. FRI only bounds degrees
+// by powers of two, so each gap is split into pieces whose lengths are the powers of two in the
+// binary expansion of its length, and every piece is committed on its own. Committing a gap in a
+// larger domain would leave room for extra coefficients that end up in the neighbouring segment.
+//
+// zeta is sampled from a challenger that has absorbed the signed commitment, the segment bounds, a
+// digest of the published frames and the commitment to the gaps, so the prover has to fix both
+// the gaps and the published frames before learning the point. Otherwise zeta is known when the
+// frames are chosen, and adding any multiple of its minimal polynomial to F leaves F(zeta) alone.
+
+use std::fmt;
+use std::ops::Range;
+
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::SeedableRng;
+use p3_challenger::{CanObserve, DuplexChallenger, FieldChallenger};
+use p3_commit::{ExtensionMmcs, OpenedValues, Pcs};
+use p3_dft::{Radix2DitParallel, TwoAdicSubgroupDft};
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{AbstractField, Field, PrimeField64};
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_goldilocks::{DiffusionMatrixGoldilocks, Goldilocks};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{Poseidon2, Poseidon2ExternalMatrixGeneral};
+use p3_symmetric::{Hash, PaddingFreeSponge, TruncatedPermutation};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::fri_params::ParameterError;
+
+pub type Val = Goldilocks;
+pub type Challenge = BinomialExtensionField<Val, 2>;
+
+pub type Perm = Poseidon2<Val, Poseidon2ExternalMatrixGeneral, DiffusionMatrixGoldilocks, 8, 7>;
+pub type MyHash = PaddingFreeSponge<Perm, 8, 4, 4>;
+pub type MyCompress = TruncatedPermutation<Perm, 2, 4, 8>;
+
+pub type ValMmcs =
+    FieldMerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 4>;
+pub type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+
+pub type Dft = Radix2DitParallel;
+pub type Challenger = DuplexChallenger<Val, Perm, 8, 4>;
+pub type MyPcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+
+pub type Commitment = <MyPcs as Pcs<Challenge, Challenger>>::Commitment;
+pub type ProverData = <MyPcs as Pcs<Challenge, Challenger>>::ProverData;
+pub type PcsProof = <MyPcs as Pcs<Challenge, Challenger>>::Proof;
+type Domain = <MyPcs as Pcs<Challenge, Challenger>>::Domain;
+
+/// Seed for the Poseidon2 round constants. Signer and verifier must agree on it.
+pub const POSEIDON_SEED: u64 = 18;
+
+/// Number of Goldilocks elements in a commitment digest.
+pub const DIGEST_ELEMS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriParameters {
+    pub log_blowup: usize,
+    pub num_queries: usize,
+    pub proof_of_work_bits: usize,
+}
+
+// Small and fast, for tests only: this gives 18 bits of conjectured security. Everything else gets
+// its parameters from `fri_params::select`.
+#[cfg(test)]
+impl Default for FriParameters {
+    fn default() -> Self {
+        Self {
+            log_blowup: 1,
+            num_queries: 10,
+            proof_of_work_bits: 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TrimError {
+    /// The kept segments are not sorted, overlap, or run past the end of the video.
+    InvalidSegments,
+    /// The opened values in the proof do not have the shape of a trimming proof.
+    MalformedProof,
+    /// The FRI opening proof did not verify.
+    Pcs(String),
+    /// The openings verify but are inconsistent with the published segment.
+    EvaluationMismatch,
+    /// No FRI parameters reach the requested security for the video.
+    Parameters(ParameterError),
+}
+
+impl fmt::Display for TrimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrimError::InvalidSegments => {
+                write!(
+                    f,
+                    "kept segments must be sorted, disjoint and inside the video"
+                )
+            }
+            TrimError::MalformedProof => write!(f, "malformed trimming proof"),
+            TrimError::Pcs(e) => write!(f, "PCS opening proof rejected: {}", e),
+            TrimError::EvaluationMismatch => {
+                write!(f, "published segment does not match the signed video")
+            }
+            TrimError::Parameters(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TrimError {}
+
+/// Canonical byte encoding of a commitment: each digest element as 8 little-endian bytes.
+pub fn commitment_to_bytes(commitment: &Commitment) -> Vec<u8> {
+    let mut comm_bytes = Vec::with_capacity(8 * DIGEST_ELEMS);
+    for felt in commitment.clone() {
+        comm_bytes.extend((felt.as_canonical_u64()).to_le_bytes());
+    }
+    comm_bytes
+}
+
+pub fn commitment_from_bytes(bytes: &[u8]) -> Option<Commitment> {
+    if bytes.len() != 8 * DIGEST_ELEMS {
+        return None;
+    }
+    let mut digest = [Val::zero(); DIGEST_ELEMS];
+    for (felt, chunk) in digest.iter_mut().zip(bytes.chunks_exact(8)) {
+        let value = u64::from_le_bytes(chunk.try_into().unwrap());
+        if value >= Val::ORDER_U64 {
+            return None;
+        }
+        *felt = Val::from_canonical_u64(value);
+    }
+    Some(Hash::from(digest))
+}
+
+/// The signer's view of a committed video.
+pub struct VideoCommitment {
+    pub commitment: Commitment,
+    /// The committed polynomial has `1 << log_degree` coefficients.
+    pub log_degree: usize,
+    data: ProverData,
+    coeffs: Vec<Val>,
+}
+
+/// Proof that the published frames are the `kept` segments of a committed video, in order.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrimProof {
+    /// Sorted, non-overlapping frame ranges that were kept.
+    pub kept: Vec<Range<usize>>,
+    /// Commitment to the padded polynomials of the removed gaps, absent if nothing was removed.
+    pub trim_commitment: Option<Commitment>,
+    pub opened_values: OpenedValues<Challenge>,
+    pub proof: PcsProof,
+}
+
+pub struct FriSetup {
+    pub params: FriParameters,
+    pcs: MyPcs,
+    challenger: Challenger,
+}
+
+// Splits `gap` into consecutive pieces whose lengths are powers of two, largest first.
+fn power_of_two_pieces(gap: Range<usize>) -> impl Iterator<Item = Range<usize>> {
+    let len = gap.len();
+    let mut start = gap.start;
+    (0..usize::BITS).rev().filter_map(move |bit| {
+        let piece_len = 1usize << bit;
+        (len & piece_len != 0).then(|| {
+            start += piece_len;
+            start - piece_len..start
+        })
+    })
+}
+
+// Converts kept frame ranges into coefficient ranges. Returns None unless the ranges are sorted,
+// non-overlapping and inside the first `num_frames` frames, so that the zero padding after the
+// last frame can never be passed off as signed frames.
+fn kept_coeff_ranges(
+    kept: &[Range<usize>],
+    frame_size: usize,
+    num_frames: usize,
+) -> Option<Vec<Range<usize>>> {
+    let mut ranges = Vec::with_capacity(kept.len());
+    let mut prev_end = 0;
+    for segment in kept {
+        let start = frame_size.checked_mul(segment.start)?;
+        let end = frame_size.checked_mul(segment.end)?;
+        if start < prev_end || start > end || segment.end > num_frames {
+            return None;
+        }
+        ranges.push(start..end);
+        prev_end = end;
+    }
+    Some(ranges)
+}
+
+// The non-empty coefficient ranges of a `degree`-coefficient polynomial not covered by `kept`.
+fn removed_gaps(kept: &[Range<usize>], degree: usize) -> Vec<Range<usize>> {
+    let mut gaps = Vec::with_capacity(kept.len() + 1);
+    let mut prev_end = 0;
+    for segment in kept.iter().chain(std::iter::once(&(degree..degree))) {
+        if segment.start > prev_end {
+            gaps.push(prev_end..segment.start);
+        }
+        prev_end = segment.end;
+    }
+    gaps
+}
+
+// SHA-256 of the published coefficients, split into 32-bit limbs so that each fits into a Val.
+fn published_digest<'a>(coeffs: impl IntoIterator<Item = &'a Val>) -> [Val; 8] {
+    let mut hasher = Sha256::new();
+    for coeff in coeffs {
+        hasher.update(coeff.as_canonical_u64().to_le_bytes());
+    }
+    let digest = hasher.finalize();
+    std::array::from_fn(|i| {
+        Val::from_canonical_u32(u32::from_le_bytes(
+            digest[4 * i..4 * i + 4].try_into().unwrap(),
+        ))
+    })
+}
+
+// Evaluates the polynomial with coefficients `coeffs` at `point` with Horner's rule.
+pub fn evaluate_coeffs(coeffs: &[Val], point: Challenge) -> Challenge {
+    let mut eval = Challenge::zero();
+    for i in coeffs.iter().rev() {
+        eval *= point;
+        eval += *i;
+    }
+    eval
+}
+
+impl FriSetup {
+    pub fn new(params: FriParameters) -> Self {
+        // Setup the PCS with basic parameters
+        let mut rng = StdRng::seed_from_u64(POSEIDON_SEED);
+        let perm = Perm::new_from_rng_128(
+            Poseidon2ExternalMatrixGeneral,
+            DiffusionMatrixGoldilocks::default(),
+            &mut rng,
+        );
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm.clone());
+
+        let val_mmcs = ValMmcs::new(hash, compress);
+        let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+
+        let fri_config = FriConfig {
+            log_blowup: params.log_blowup,
+            num_queries: params.num_queries,
+            proof_of_work_bits: params.proof_of_work_bits,
+            mmcs: challenge_mmcs,
+        };
+
+        let pcs = MyPcs::new(Dft {}, val_mmcs, fri_config);
+        let challenger = Challenger::new(perm);
+
+        Self {
+            params,
+            pcs,
+            challenger,
+        }
+    }
+
+    fn domain(&self, degree: usize) -> Domain {
+        <MyPcs as Pcs<Challenge, Challenger>>::natural_domain_for_degree(&self.pcs, degree)
+    }
+
+    fn commit_coeffs(&self, polys: Vec<(usize, Vec<Val>)>) -> (Commitment, ProverData) {
+        let dft = Dft::default();
+        let evaluations = polys
+            .into_iter()
+            .map(|(degree, coeffs)| {
+                let evals = dft
+                    .dft_batch(RowMajorMatrix::new(coeffs, 1))
+                    .to_row_major_matrix();
+                (self.domain(degree), evals)
+            })
+            .collect();
+        <MyPcs as Pcs<Challenge, Challenger>>::commit(&self.pcs, evaluations)
+    }
+
+    // Challenger for a trimming proof, with everything the opening point must depend on already
+    // observed.
+    fn trim_challenger(
+        &self,
+        commitment: &Commitment,
+        log_degree: usize,
+        frame_size: usize,
+        kept: &[Range<usize>],
+        published: &[Val; 8],
+        trim_commitment: &Option<Commitment>,
+    ) -> Challenger {
+        let mut challenger = self.challenger.clone();
+        challenger.observe(commitment.clone());
+        challenger.observe(Val::from_canonical_usize(log_degree));
+        challenger.observe(Val::from_canonical_usize(frame_size));
+        challenger.observe(Val::from_canonical_usize(kept.len()));
+        for segment in kept {
+            challenger.observe(Val::from_canonical_usize(segment.start));
+            challenger.observe(Val::from_canonical_usize(segment.end));
+        }
+        challenger.observe_slice(published);
+        if let Some(trim_commitment) = trim_commitment {
+            challenger.observe(trim_commitment.clone());
+        }
+        challenger
+    }
+
+    /// Commits to the polynomial whose coefficients are the packed video, zero-padded up to the
+    /// power-of-two size required by FRI.
+    pub fn commit_video(&self, mut coeffs: Vec<Val>) -> VideoCommitment {
+        let log_degree = coeffs.len().next_power_of_two().trailing_zeros() as usize;
+        coeffs.resize(1 << log_degree, Val::zero());
+        let (commitment, data) = self.commit_coeffs(vec![(1 << log_degree, coeffs.clone())]);
+
+        VideoCommitment {
+            commitment,
+            log_degree,
+            data,
+            coeffs,
+        }
+    }
+
+    /// Proves that frames `segment` (of `frame_size` coefficients each) are a contiguous part of
+    /// the committed video of `num_frames` frames.
+    pub fn prove_trim(
+        &self,
+        video: &VideoCommitment,
+        frame_size: usize,
+        num_frames: usize,
+        segment: Range<usize>,
+    ) -> Result<TrimProof, TrimError> {
+        self.prove_trim_segments(video, frame_size, num_frames, vec![segment])
+    }
+
+    /// Proves that the concatenation of the frame ranges `kept` was cut out of the committed
+    /// video of `num_frames` frames. The ranges must be sorted, must not overlap and must end by
+    /// `num_frames`, the signed frame count `verify_trim` checks against, so the padding after the
+    /// last frame is never offered as kept frames.
+    pub fn prove_trim_segments(
+        &self,
+        video: &VideoCommitment,
+        frame_size: usize,
+        num_frames: usize,
+        kept: Vec<Range<usize>>,
+    ) -> Result<TrimProof, TrimError> {
+        if num_frames
+            .checked_mul(frame_size)
+            .map_or(true, |len| len > video.coeffs.len())
+        {
+            return Err(TrimError::InvalidSegments);
+        }
+        let kept_coeffs =
+            kept_coeff_ranges(&kept, frame_size, num_frames).ok_or(TrimError::InvalidSegments)?;
+
+        // ==== Build one polynomial per removed gap ====
+        let removed: Vec<&[Val]> = removed_gaps(&kept_coeffs, video.coeffs.len())
+            .into_iter()
+            .map(|gap| &video.coeffs[gap])
+            .collect();
+        Ok(self.prove_trim_with(video, frame_size, kept, &removed))
+    }
+
+    // Proves the trim with the given removed gaps. Only honest when they are the actual gaps
+    // of `video`; split out so tests can build forged proofs.
+    fn prove_trim_with(
+        &self,
+        video: &VideoCommitment,
+        frame_size: usize,
+        kept: Vec<Range<usize>>,
+        removed: &[&[Val]],
+    ) -> TrimProof {
+        let published = published_digest(kept.iter().flat_map(|segment| {
+            &video.coeffs[frame_size * segment.start..frame_size * segment.end]
+        }));
+        let pieces = removed
+            .iter()
+            .flat_map(|gap| power_of_two_pieces(0..gap.len()).map(move |piece| &gap[piece]))
+            .map(|piece| (piece.len(), piece.to_vec()))
+            .collect();
+        self.prove_trim_committed(video, frame_size, kept, &published, pieces)
+    }
+
+    // Proves the trim with the given (domain size, coefficients) gap pieces committed and the
+    // published frames hashing to `published`.
+    fn prove_trim_committed(
+        &self,
+        video: &VideoCommitment,
+        frame_size: usize,
+        kept: Vec<Range<usize>>,
+        published: &[Val; 8],
+        pieces: Vec<(usize, Vec<Val>)>,
+    ) -> TrimProof {
+        let num_pieces = pieces.len();
+        let trim = (!pieces.is_empty()).then(|| self.commit_coeffs(pieces));
+        let trim_commitment = trim.as_ref().map(|(commitment, _)| commitment.clone());
+
+        let mut p_challenger = self.trim_challenger(
+            &video.commitment,
+            video.log_degree,
+            frame_size,
+            &kept,
+            published,
+            &trim_commitment,
+        );
+        let zeta: Challenge = p_challenger.sample_ext_element();
+
+        let mut rounds = vec![(&video.data, vec![vec![zeta]])];
+        if let Some((_, trim_data)) = &trim {
+            rounds.push((trim_data, vec![vec![zeta]; num_pieces]));
+        }
+        let (opened_values, proof) = self.pcs.open(rounds, &mut p_challenger);
+
+        TrimProof {
+            kept,
+            trim_commitment,
+            opened_values,
+            proof,
+        }
+    }
+
+    /// Verifies `proof` against the signed `commitment` of a video of `num_frames` frames and the
+    /// published `segment_coeffs`, the packed kept frames in order.
+    pub fn verify_trim(
+        &self,
+        commitment: &Commitment,
+        log_degree: usize,
+        frame_size: usize,
+        num_frames: usize,
+        proof: &TrimProof,
+        segment_coeffs: &[Val],
+    ) -> Result<(), TrimError> {
+        let degree = 1usize << log_degree;
+        if num_frames
+            .checked_mul(frame_size)
+            .map_or(true, |len| len > degree)
+        {
+            return Err(TrimError::MalformedProof);
+        }
+        let kept = kept_coeff_ranges(&proof.kept, frame_size, num_frames)
+            .ok_or(TrimError::MalformedProof)?;
+        if segment_coeffs.len() != kept.iter().map(|segment| segment.len()).sum::<usize>() {
+            return Err(TrimError::MalformedProof);
+        }
+        let pieces: Vec<Range<usize>> = removed_gaps(&kept, degree)
+            .into_iter()
+            .flat_map(power_of_two_pieces)
+            .collect();
+        if proof.trim_commitment.is_some() == pieces.is_empty() {
+            return Err(TrimError::MalformedProof);
+        }
+
+        // Every round must open each of its matrices at exactly one point to a single value.
+        let expected_shape = [1]
+            .into_iter()
+            .chain(proof.trim_commitment.as_ref().map(|_| pieces.len()));
+        if proof.opened_values.len() != expected_shape.clone().count()
+            || !proof
+                .opened_values
+                .iter()
+                .zip(expected_shape)
+                .all(|(round, num_mats)| {
+                    round.len() == num_mats
+                        && round.iter().all(|mat| mat.len() == 1 && mat[0].len() == 1)
+                })
+        {
+            return Err(TrimError::MalformedProof);
+        }
+        let p_value = proof.opened_values[0][0][0][0];
+
+        let mut v_challenger = self.trim_challenger(
+            commitment,
+            log_degree,
+            frame_size,
+            &proof.kept,
+            &published_digest(segment_coeffs),
+            &proof.trim_commitment,
+        );
+        let zeta: Challenge = v_challenger.sample_ext_element();
+
+        let mut rounds = vec![(
+            commitment.clone(),
+            vec![(self.domain(degree), vec![(zeta, vec![p_value])])],
+        )];
+        if let Some(trim_commitment) = &proof.trim_commitment {
+            let openings = pieces
+                .iter()
+                .zip(&proof.opened_values[1])
+                .map(|(piece, values)| (self.domain(piece.len()), vec![(zeta, values[0].clone())]))
+                .collect();
+            rounds.push((trim_commitment.clone(), openings));
+        }
+        self.pcs
+            .verify(rounds, &proof.proof, &mut v_challenger)
+            .map_err(|e| TrimError::Pcs(format!("{:?}", e)))?;
+
+        let mut inferred_eval_value = Challenge::zero();
+
+        // Shift every piece of a removed gap into its position inside P.
+        if proof.trim_commitment.is_some() {
+            for (piece, values) in pieces.iter().zip(&proof.opened_values[1]) {
+                inferred_eval_value += values[0][0] * zeta.exp_u64(piece.start as u64);
+            }
+        }
+
+        // Evaluate every kept segment at zeta using the trimmed data and shift it into place.
+        let mut published = segment_coeffs;
+        for segment in &kept {
+            let (segment_data, rest) = published.split_at(segment.len());
+            inferred_eval_value +=
+                evaluate_coeffs(segment_data, zeta) * zeta.exp_u64(segment.start as u64);
+            published = rest;
+        }
+
+        if inferred_eval_value == p_value {
+            Ok(())
+        } else {
+            Err(TrimError::EvaluationMismatch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractExtensionField;
+
+    use super::*;
+
+    const FRAME_SIZE: usize = 4;
+    const NUM_FRAMES: usize = 8;
+
+    fn test_video(seed: u64) -> Vec<Val> {
+        (0..(FRAME_SIZE * NUM_FRAMES) as u64)
+            .map(|i| Val::from_canonical_u64(i * 1_000_003 + seed))
+            .collect()
+    }
+
+    fn segment_coeffs(video: &[Val], segment: &Range<usize>) -> Vec<Val> {
+        video[FRAME_SIZE * segment.start..FRAME_SIZE * segment.end].to_vec()
+    }
+
+    #[test]
+    fn honest_trim_verifies() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        for segment in [1..2, 2..7, 3..3] {
+            let proof = setup
+                .prove_trim(&video, FRAME_SIZE, NUM_FRAMES, segment.clone())
+                .unwrap();
+            setup
+                .verify_trim(
+                    &video.commitment,
+                    video.log_degree,
+                    FRAME_SIZE,
+                    NUM_FRAMES,
+                    &proof,
+                    &segment_coeffs(&coeffs, &segment),
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn tampered_segment_is_rejected() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let segment = 2..5;
+        let proof = setup
+            .prove_trim(&video, FRAME_SIZE, NUM_FRAMES, segment.clone())
+            .unwrap();
+
+        let mut published = segment_coeffs(&coeffs, &segment);
+        published[5] += Val::one();
+        assert!(matches!(
+            setup.verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES,
+                &proof,
+                &published
+            ),
+            Err(TrimError::EvaluationMismatch)
+        ));
+    }
+
+    #[test]
+    fn swapped_prefix_is_rejected() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let other = test_video(2);
+        let video = setup.commit_video(coeffs.clone());
+        let segment = 2..5;
+
+        // Commit to the prefix of a different video but open the signed one.
+        let forged = setup.prove_trim_with(
+            &video,
+            FRAME_SIZE,
+            vec![segment.clone()],
+            &[
+                &other[..FRAME_SIZE * segment.start],
+                &coeffs[FRAME_SIZE * segment.end..],
+            ],
+        );
+        assert!(setup
+            .verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES,
+                &forged,
+                &segment_coeffs(&coeffs, &segment)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn shifted_boundary_is_rejected() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let proof = setup
+            .prove_trim(&video, FRAME_SIZE, NUM_FRAMES, 2..5)
+            .unwrap();
+
+        for shifted in [3..6, 1..4, 2..6, 3..5] {
+            let mut moved = proof.clone();
+            moved.kept = vec![shifted.clone()];
+            assert!(setup
+                .verify_trim(
+                    &video.commitment,
+                    video.log_degree,
+                    FRAME_SIZE,
+                    NUM_FRAMES,
+                    &moved,
+                    &segment_coeffs(&coeffs, &shifted)
+                )
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn proof_for_other_video_is_rejected() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let other = setup.commit_video(test_video(2));
+        let segment = 2..5;
+        let proof = setup
+            .prove_trim(&video, FRAME_SIZE, NUM_FRAMES, segment.clone())
+            .unwrap();
+        assert!(setup
+            .verify_trim(
+                &other.commitment,
+                other.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES,
+                &proof,
+                &segment_coeffs(&coeffs, &segment)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn published_frames_are_bound_to_the_challenge() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let segment = 2..5;
+        let proof = setup
+            .prove_trim(&video, FRAME_SIZE, NUM_FRAMES, segment.clone())
+            .unwrap();
+        let honest = segment_coeffs(&coeffs, &segment);
+
+        // The point the proof was opened at.
+        let zeta: Challenge = setup
+            .trim_challenger(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                &proof.kept,
+                &published_digest(&honest),
+                &proof.trim_commitment,
+            )
+            .sample_ext_element();
+
+        // zeta^2 = a * zeta + b for some a, b in Val, so adding (x^2 - a x - b) * x^j to the
+        // published frames does not change their value at zeta.
+        let square = zeta * zeta;
+        let (z, s) = (zeta.as_base_slice(), square.as_base_slice());
+        let a = s[1] / z[1];
+        let b = s[0] - a * z[0];
+        let j = 3;
+        let mut forged = honest.clone();
+        forged[j] -= b;
+        forged[j + 1] -= a;
+        forged[j + 2] += Val::one();
+        assert_eq!(
+            evaluate_coeffs(&forged, zeta),
+            evaluate_coeffs(&honest, zeta)
+        );
+
+        assert!(setup
+            .verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES,
+                &proof,
+                &forged
+            )
+            .is_err());
+    }
+
+    fn kept_coeffs(video: &[Val], kept: &[Range<usize>]) -> Vec<Val> {
+        kept.iter()
+            .flat_map(|segment| segment_coeffs(video, segment))
+            .collect()
+    }
+
+    #[test]
+    fn multi_segment_trim_verifies() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        for kept in [
+            vec![1..2, 4..6],
+            vec![0..2, 3..4, 7..8],
+            vec![1..2, 5..7],
+            vec![0..3, 3..5],
+            vec![2..8],
+            vec![0..8],
+        ] {
+            let proof = setup
+                .prove_trim_segments(&video, FRAME_SIZE, NUM_FRAMES, kept.clone())
+                .unwrap();
+            setup
+                .verify_trim(
+                    &video.commitment,
+                    video.log_degree,
+                    FRAME_SIZE,
+                    NUM_FRAMES,
+                    &proof,
+                    &kept_coeffs(&coeffs, &kept),
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn reordered_segments_are_rejected() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let kept = vec![1..2, 4..6];
+        let proof = setup
+            .prove_trim_segments(&video, FRAME_SIZE, NUM_FRAMES, kept.clone())
+            .unwrap();
+
+        // Publishing the clips in a different order must not verify.
+        let swapped = vec![4..6, 1..2];
+        assert!(setup
+            .verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES,
+                &proof,
+                &kept_coeffs(&coeffs, &swapped)
+            )
+            .is_err());
+
+        let mut reordered = proof.clone();
+        reordered.kept = swapped.clone();
+        assert!(matches!(
+            setup.verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES,
+                &reordered,
+                &kept_coeffs(&coeffs, &swapped)
+            ),
+            Err(TrimError::MalformedProof)
+        ));
+    }
+
+    #[test]
+    fn dropped_gap_is_rejected() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let kept = vec![1..2, 4..6];
+
+        // Claim the middle gap is empty by opening only the outer two.
+        let forged = setup.prove_trim_with(
+            &video,
+            FRAME_SIZE,
+            kept.clone(),
+            &[&coeffs[..FRAME_SIZE], &coeffs[FRAME_SIZE * 6..]],
+        );
+        assert!(matches!(
+            setup.verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES,
+                &forged,
+                &kept_coeffs(&coeffs, &kept)
+            ),
+            Err(TrimError::MalformedProof)
+        ));
+    }
+
+    #[test]
+    fn padded_middle_gap_is_rejected() {
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1);
+        let video = setup.commit_video(coeffs.clone());
+        let kept = vec![1..2, 5..7];
+
+        // Commit the three-frame middle gap G in a domain of 16 as x^4 * G + delta. Once
+        // divided by zeta^4 and shifted to the gap, delta lands on the last four coefficients
+        // of the first kept segment, where the forger subtracts it again.
+        let delta = [1, 2, 3, 4].map(Val::from_canonical_u64);
+        let mut middle = delta.to_vec();
+        middle.extend_from_slice(&coeffs[FRAME_SIZE * 2..FRAME_SIZE * 5]);
+        let mut published = kept_coeffs(&coeffs, &kept);
+        for (coeff, d) in published.iter_mut().zip(delta) {
+            *coeff -= d;
+        }
+        let forged = setup.prove_trim_committed(
+            &video,
+            FRAME_SIZE,
+            kept.clone(),
+            &published_digest(&published),
+            vec![
+                (FRAME_SIZE, coeffs[..FRAME_SIZE].to_vec()),
+                (16, middle),
+                (FRAME_SIZE, coeffs[FRAME_SIZE * 7..].to_vec()),
+            ],
+        );
+        assert!(setup
+            .verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES,
+                &forged,
+                &published
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn gaps_are_split_into_powers_of_two() {
+        let pieces: Vec<_> = power_of_two_pieces(4..16).collect();
+        assert_eq!(pieces, vec![4..12, 12..16]);
+        assert_eq!(power_of_two_pieces(3..3).count(), 0);
+        let pieces: Vec<_> = power_of_two_pieces(0..7).collect();
+        assert_eq!(pieces, vec![0..4, 4..6, 6..7]);
+    }
+
+    #[test]
+    fn invalid_segments_are_an_error() {
+        let setup = FriSetup::new(FriParameters::default());
+        let video = setup.commit_video(test_video(1));
+        for kept in [vec![4..2], vec![1..3, 2..5], vec![3..4, 1..2], vec![2..9]] {
+            assert!(matches!(
+                setup.prove_trim_segments(&video, FRAME_SIZE, NUM_FRAMES, kept),
+                Err(TrimError::InvalidSegments)
+            ));
+        }
+    }
+
+    #[test]
+    fn padding_frames_are_rejected() {
+        // Six frames are padded to eight frames' worth of coefficients.
+        let setup = FriSetup::new(FriParameters::default());
+        let coeffs = test_video(1)[..FRAME_SIZE * 6].to_vec();
+        let video = setup.commit_video(coeffs.clone());
+        assert_eq!(1 << video.log_degree, FRAME_SIZE * NUM_FRAMES);
+
+        let padding = vec![Val::zero(); FRAME_SIZE * 2];
+        for kept in [vec![6..8], vec![4..7]] {
+            assert!(matches!(
+                setup.prove_trim_segments(&video, FRAME_SIZE, 6, kept.clone()),
+                Err(TrimError::InvalidSegments)
+            ));
+            // Counting the padding as frames gets a proof out of the prover, which the verifier
+            // still rejects against the signed frame count.
+            let proof = setup
+                .prove_trim_segments(&video, FRAME_SIZE, NUM_FRAMES, kept.clone())
+                .unwrap();
+            let mut published = coeffs.clone();
+            published.extend(&padding);
+            let published = kept_coeffs(&published, &kept);
+            assert!(matches!(
+                setup.verify_trim(
+                    &video.commitment,
+                    video.log_degree,
+                    FRAME_SIZE,
+                    6,
+                    &proof,
+                    &published
+                ),
+                Err(TrimError::MalformedProof)
+            ));
+        }
+
+        let proof = setup.prove_trim(&video, FRAME_SIZE, 6, 4..6).unwrap();
+        setup
+            .verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                6,
+                &proof,
+                &segment_coeffs(&coeffs, &(4..6)),
+            )
+            .unwrap();
+
+        // A frame count that does not fit in the commitment is malformed.
+        assert!(matches!(
+            setup.verify_trim(
+                &video.commitment,
+                video.log_degree,
+                FRAME_SIZE,
+                NUM_FRAMES + 1,
+                &proof,
+                &segment_coeffs(&coeffs, &(4..6))
+            ),
+            Err(TrimError::MalformedProof)
+        ));
+        assert!(matches!(
+            setup.prove_trim(&video, FRAME_SIZE, NUM_FRAMES + 1, 4..6),
+            Err(TrimError::InvalidSegments)
+        ));
+    }
+}